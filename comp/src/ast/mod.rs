@@ -0,0 +1,585 @@
+//! Abstract syntax tree of the language.
+
+use crate::{
+    lexer::token::{FloatLit, IntLit},
+    span::Span,
+    symbol::Symbol,
+};
+
+/// A parsed source file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Module {
+    /// Inner doc comments (`//!`) of the module.
+    pub docs: Vec<Symbol>,
+    /// The `module <name>` declaration, if any.
+    pub decl: Option<Ident>,
+    /// Top level items of the module.
+    pub items: Vec<Item>,
+    /// Span of the whole module.
+    pub span: Span,
+}
+
+/// An identifier with its location.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ident {
+    /// Name of the identifier.
+    pub name: Symbol,
+    /// Span of the identifier.
+    pub span: Span,
+}
+
+impl Ident {
+    /// Creates a new identifier.
+    pub fn new(name: Symbol, span: Span) -> Ident {
+        Ident { name, span }
+    }
+}
+
+/// A path made of `::` separated segments, e.g. `x`, `Self` or
+/// `Color::Red`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path {
+    /// Segments of the path, never empty.
+    pub segments: Vec<Ident>,
+    /// Span of the whole path.
+    pub span: Span,
+}
+
+impl Path {
+    /// Creates a path with a single segment.
+    pub fn from_ident(ident: Ident) -> Path {
+        Path { span: ident.span, segments: vec![ident] }
+    }
+
+    /// Returns the identifier if the path has a single segment.
+    pub fn as_ident(&self) -> Option<&Ident> {
+        match self.segments.as_slice() {
+            [ident] => Some(ident),
+            _ => None,
+        }
+    }
+}
+
+/// Attribute attached to an item, e.g. `@[link("c")]` or `@link("c")`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attribute {
+    /// Name of the attribute.
+    pub name: Ident,
+    /// Arguments of the attribute.
+    pub args: Vec<Expr>,
+    /// Span of the attribute, including the `@`.
+    pub span: Span,
+}
+
+/// Visibility of an item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visibility {
+    /// Visible only inside the declaring module.
+    Private,
+    /// Visible to every module, declared with `pub`.
+    Public,
+}
+
+/// Top level item.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Item {
+    /// Outer doc comments (`///`) of the item.
+    pub docs: Vec<Symbol>,
+    /// Attributes of the item.
+    pub attrs: Vec<Attribute>,
+    /// Visibility of the item.
+    pub vis: Visibility,
+    /// Kind of the item.
+    pub kind: ItemKind,
+    /// Span of the item, including attributes.
+    pub span: Span,
+}
+
+/// Kind of top level item.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ItemKind {
+    /// `import "path"`
+    Import(Import),
+    /// `extern { ... }`
+    Extern(ExternBlock),
+    /// `fn name(...) -> T { ... }`
+    Fn(FnDecl),
+    /// `struct Name { field: T }`
+    Struct(StructDecl),
+    /// `union Name { field: T }`
+    Union(StructDecl),
+    /// `enum Name { Variant }`
+    Enum(EnumDecl),
+    /// `type Name = T`
+    TypeAlias(TypeAlias),
+    /// `impl T { ... }` or `impl Trait for T { ... }`
+    Impl(ImplBlock),
+    /// `trait Name { ... }`
+    Trait(TraitDecl),
+}
+
+/// An import declaration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Import {
+    /// The imported path as written in the source.
+    pub path: Symbol,
+    /// Span of the path literal.
+    pub path_span: Span,
+}
+
+/// A block of external declarations.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExternBlock {
+    /// Declarations inside the block.
+    pub items: Vec<Item>,
+}
+
+/// A function declaration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FnDecl {
+    /// Name of the function.
+    pub name: Ident,
+    /// Generic parameters, e.g. `[T]`.
+    pub generics: Vec<Ident>,
+    /// Parameters of the function.
+    pub params: Vec<Param>,
+    /// Whether the function takes variadic arguments (`...`).
+    pub variadic: bool,
+    /// Return type, `None` if omitted.
+    pub ret: Option<Ty>,
+    /// Body of the function, `None` for external declarations.
+    pub body: Option<Block>,
+}
+
+/// A struct or union declaration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructDecl {
+    /// Name of the type.
+    pub name: Ident,
+    /// Generic parameters, e.g. `[T]`.
+    pub generics: Vec<Ident>,
+    /// Fields of the type.
+    pub fields: Vec<FieldDef>,
+}
+
+/// A field of a struct or union.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldDef {
+    /// Visibility of the field.
+    pub vis: Visibility,
+    /// Name of the field.
+    pub name: Ident,
+    /// Type of the field.
+    pub ty: Ty,
+    /// Span of the field.
+    pub span: Span,
+}
+
+/// An enum declaration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumDecl {
+    /// Name of the enum.
+    pub name: Ident,
+    /// Generic parameters, e.g. `[T]`.
+    pub generics: Vec<Ident>,
+    /// Variants of the enum.
+    pub variants: Vec<Variant>,
+}
+
+/// A variant of an enum, e.g. `Red`, `Some(T)` or `Max = 10`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variant {
+    /// Name of the variant.
+    pub name: Ident,
+    /// Types of the variant's fields, empty for unit variants.
+    pub fields: Vec<Ty>,
+    /// Explicit discriminant, if any.
+    pub discriminant: Option<Expr>,
+    /// Span of the variant.
+    pub span: Span,
+}
+
+/// A type alias, `type Name = T`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeAlias {
+    /// Name of the alias.
+    pub name: Ident,
+    /// Generic parameters, e.g. `[T]`.
+    pub generics: Vec<Ident>,
+    /// The aliased type.
+    pub ty: Ty,
+}
+
+/// An implementation block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImplBlock {
+    /// Generic parameters, e.g. `impl[T]`.
+    pub generics: Vec<Ident>,
+    /// The implemented trait, if any.
+    pub trait_ref: Option<Path>,
+    /// The type the block implements methods for.
+    pub self_ty: Ty,
+    /// Methods of the block.
+    pub items: Vec<Item>,
+}
+
+/// A trait declaration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraitDecl {
+    /// Name of the trait.
+    pub name: Ident,
+    /// Generic parameters, e.g. `[T]`.
+    pub generics: Vec<Ident>,
+    /// Methods of the trait, with or without a default body.
+    pub items: Vec<Item>,
+}
+
+/// A function parameter. A `self` parameter is named `self` and has type
+/// `Self` unless written otherwise.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Param {
+    /// Name of the parameter.
+    pub name: Ident,
+    /// Type of the parameter.
+    pub ty: Ty,
+    /// Span of the parameter.
+    pub span: Span,
+}
+
+/// A type as written in the source.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ty {
+    /// Kind of the type.
+    pub kind: TyKind,
+    /// Span of the type.
+    pub span: Span,
+}
+
+/// Kind of type.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TyKind {
+    /// A named type, e.g. `isize`, `T` or `Self`.
+    Path(Path),
+    /// A raw pointer, e.g. `*const u8` or `*mut u8`.
+    Ptr(Mutability, Box<Ty>),
+    /// The never type `!`.
+    Never,
+    /// A function type with its parameter types and return type, e.g.
+    /// `fn(i32, i32) -> bool`.
+    Fn(Vec<Ty>, Option<Box<Ty>>),
+}
+
+/// Mutability of a binding or pointer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Mutability {
+    Const,
+    Mut,
+}
+
+/// A block of statements.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+    /// Statements of the block.
+    pub stmts: Vec<Stmt>,
+    /// Span of the block, including the braces.
+    pub span: Span,
+}
+
+/// A statement.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stmt {
+    /// Kind of the statement.
+    pub kind: StmtKind,
+    /// Span of the statement.
+    pub span: Span,
+}
+
+/// Kind of statement.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StmtKind {
+    /// `let`, `let mut` or `const` binding.
+    Local(Local),
+    /// `return` with an optional value.
+    Return(Option<Expr>),
+    /// `defer expr`, runs the expression when the enclosing block exits.
+    Defer(Expr),
+    /// An expression statement.
+    Expr(Expr),
+}
+
+/// Kind of local binding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LocalKind {
+    Let,
+    Const,
+}
+
+/// A local binding.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Local {
+    /// Whether it is declared with `let` or `const`.
+    pub kind: LocalKind,
+    /// Mutability of the binding.
+    pub mutability: Mutability,
+    /// Name of the binding.
+    pub name: Ident,
+    /// Type annotation, if any.
+    pub ty: Option<Ty>,
+    /// Initializer, if any.
+    pub init: Option<Expr>,
+}
+
+/// An expression.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr {
+    /// Kind of the expression.
+    pub kind: ExprKind,
+    /// Span of the expression.
+    pub span: Span,
+}
+
+/// Kind of expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExprKind {
+    /// A literal, e.g. `10`, `"hello"` or `true`.
+    Lit(Lit),
+    /// A name, e.g. `x`, `self` or `Color::Red`.
+    Path(Path),
+    /// A unary operation, e.g. `-x`.
+    Unary(UnOp, Box<Expr>),
+    /// A binary operation, e.g. `x + y`.
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+    /// An assignment, e.g. `x = y`.
+    Assign(Box<Expr>, Box<Expr>),
+    /// A compound assignment, e.g. `x += y`.
+    AssignOp(BinOp, Box<Expr>, Box<Expr>),
+    /// A range, e.g. `a..b`, `a..` or `..=b`. The flag is set for inclusive
+    /// ranges (`..=`).
+    Range(Option<Box<Expr>>, Option<Box<Expr>>, bool),
+    /// A function call, e.g. `add(x, y)`.
+    Call(Box<Expr>, Vec<Expr>),
+    /// A generic function with type arguments, e.g. `sum[i32]`.
+    TypeArgs(Box<Expr>, Vec<Ty>),
+    /// A field access, e.g. `math.add`.
+    Field(Box<Expr>, Ident),
+    /// A cast, e.g. `x as u8`.
+    Cast(Box<Expr>, Ty),
+    /// A parenthesized expression.
+    Paren(Box<Expr>),
+    /// A block expression.
+    Block(Block),
+    /// `if cond { ... } else { ... }`, the else branch is either a block
+    /// or another `if` expression.
+    If(Box<Expr>, Block, Option<Box<Expr>>),
+    /// `while cond { ... }`
+    While(Box<Expr>, Block),
+    /// `for name in iter { ... }`
+    For(Ident, Box<Expr>, Block),
+    /// `loop { ... }`
+    Loop(Block),
+    /// `break` with an optional value.
+    Break(Option<Box<Expr>>),
+    /// `continue`
+    Continue,
+    /// `match expr { arms }`
+    Match(Box<Expr>, Vec<Arm>),
+    /// A struct literal, e.g. `Point { x: 1, y }`.
+    Struct(Path, Vec<FieldInit>),
+    /// `sizeof(T)`
+    SizeOf(Ty),
+}
+
+/// A field of a struct literal.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldInit {
+    /// Name of the field.
+    pub name: Ident,
+    /// Value of the field, a path to `name` for the shorthand `Point { x }`.
+    pub expr: Expr,
+    /// Span of the field.
+    pub span: Span,
+}
+
+/// An arm of a match expression, `pat [if guard] => body`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Arm {
+    /// Pattern of the arm.
+    pub pat: Pat,
+    /// Guard of the arm, if any.
+    pub guard: Option<Expr>,
+    /// Body of the arm.
+    pub body: Expr,
+    /// Span of the arm.
+    pub span: Span,
+}
+
+/// A pattern.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pat {
+    /// Kind of the pattern.
+    pub kind: PatKind,
+    /// Span of the pattern.
+    pub span: Span,
+}
+
+/// Kind of pattern.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatKind {
+    /// `_`
+    Wild,
+    /// A literal, possibly negated, e.g. `1`, `-1` or `"a"`.
+    Lit(Box<Expr>),
+    /// A name binding the matched value, e.g. `x`.
+    Binding(Ident),
+    /// A unit variant or constant, e.g. `Color::Red`.
+    Path(Path),
+    /// A variant with fields, e.g. `Shape::Circle(r)`.
+    TupleStruct(Path, Vec<Pat>),
+    /// Alternatives, e.g. `1 | 2`.
+    Or(Vec<Pat>),
+}
+
+/// A literal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Lit {
+    /// An integer literal, e.g. `10` or `0xffu8`.
+    Int(IntLit),
+    /// A float literal, e.g. `1.5` or `2e10f32`.
+    Float(FloatLit),
+    /// A string literal, e.g. `"hello\n"` or `r"C:\path"`.
+    Str(Symbol),
+    /// A character literal, e.g. `'a'`.
+    Char(char),
+    /// A byte string literal, e.g. `b"\x7fELF"`.
+    ByteStr(Vec<u8>),
+    /// A NUL terminated C string literal, e.g. `c"hello"`.
+    CStr(Vec<u8>),
+    /// `true` or `false`.
+    Bool(bool),
+    /// `null`
+    Null,
+}
+
+/// Unary operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnOp {
+    /// `-`
+    Neg,
+    /// `!`
+    Not,
+    /// `~`
+    BitNot,
+}
+
+impl UnOp {
+    /// Returns the operator as written in the source.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            UnOp::Neg => "-",
+            UnOp::Not => "!",
+            UnOp::BitNot => "~",
+        }
+    }
+}
+
+/// Associativity of a binary operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Assoc {
+    /// `a - b - c` is `(a - b) - c`.
+    Left,
+    /// `a < b < c` is an error.
+    None,
+}
+
+/// Binary operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinOp {
+    /// `+`
+    Add,
+    /// `-`
+    Sub,
+    /// `*`
+    Mul,
+    /// `/`
+    Div,
+    /// `%`
+    Rem,
+    /// `^`
+    BitXor,
+    /// `&`
+    BitAnd,
+    /// `|`
+    BitOr,
+    /// `<<`
+    Shl,
+    /// `>>`
+    Shr,
+    /// `==`
+    Eq,
+    /// `!=`
+    Ne,
+    /// `<`
+    Lt,
+    /// `<=`
+    Le,
+    /// `>`
+    Gt,
+    /// `>=`
+    Ge,
+    /// `&&`
+    And,
+    /// `||`
+    Or,
+}
+
+impl BinOp {
+    /// Returns the binding power of the operator, higher binds tighter. See
+    /// the parser documentation for the full table.
+    pub fn precedence(self) -> u8 {
+        match self {
+            BinOp::Mul | BinOp::Div | BinOp::Rem => 10,
+            BinOp::Add | BinOp::Sub => 9,
+            BinOp::Shl | BinOp::Shr => 8,
+            BinOp::BitAnd => 7,
+            BinOp::BitXor => 6,
+            BinOp::BitOr => 5,
+            BinOp::Eq | BinOp::Ne | BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge => 4,
+            BinOp::And => 3,
+            BinOp::Or => 2,
+        }
+    }
+
+    /// Returns the associativity of the operator.
+    pub fn assoc(self) -> Assoc {
+        if self.is_comparison() { Assoc::None } else { Assoc::Left }
+    }
+
+    /// Checks whether the operator compares its operands.
+    pub fn is_comparison(self) -> bool {
+        matches!(self, BinOp::Eq | BinOp::Ne | BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge)
+    }
+
+    /// Returns the operator as written in the source.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Mul => "*",
+            BinOp::Div => "/",
+            BinOp::Rem => "%",
+            BinOp::BitXor => "^",
+            BinOp::BitAnd => "&",
+            BinOp::BitOr => "|",
+            BinOp::Shl => "<<",
+            BinOp::Shr => ">>",
+            BinOp::Eq => "==",
+            BinOp::Ne => "!=",
+            BinOp::Lt => "<",
+            BinOp::Le => "<=",
+            BinOp::Gt => ">",
+            BinOp::Ge => ">=",
+            BinOp::And => "&&",
+            BinOp::Or => "||",
+        }
+    }
+}
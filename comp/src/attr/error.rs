@@ -0,0 +1,87 @@
+use super::{Args, BUILTIN_ATTRS, Target, lookup};
+use crate::{diag::Diagnostic, span::Span, symbol::Symbol};
+use std::fmt;
+
+/// AttrError is an invalid attribute on an item.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttrError {
+    pub kind: AttrErrorKind,
+    pub span: Span,
+}
+
+impl AttrError {
+    /// Creates a new attribute error.
+    pub fn new(kind: AttrErrorKind, span: Span) -> AttrError {
+        AttrError { kind, span }
+    }
+
+    /// Converts the error into a diagnostic.
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let (code, label) = match &self.kind {
+            AttrErrorKind::Unknown(_) => ("E0200", "unknown attribute".to_string()),
+            AttrErrorKind::Misplaced { target, .. } => {
+                ("E0201", format!("not allowed on {}", target.describe()))
+            }
+            AttrErrorKind::InvalidArgs { expected, .. } => {
+                ("E0202", format!("expected {}", expected.describe()))
+            }
+            AttrErrorKind::Duplicate { .. } => ("E0203", "used again here".to_string()),
+            AttrErrorKind::Conflicting { .. } => ("E0204", "conflicting attribute".to_string()),
+        };
+
+        let diag = Diagnostic::error(self.kind.to_string()).code(code).primary(self.span, label);
+        match &self.kind {
+            AttrErrorKind::Unknown(_) => {
+                let names: Vec<_> =
+                    BUILTIN_ATTRS.iter().map(|attr| format!("`{}`", attr.name)).collect();
+                diag.help(format!("built-in attributes are {}", names.join(", ")))
+            }
+            AttrErrorKind::Misplaced { name, .. } => {
+                let targets = lookup(*name).map_or(&[][..], |attr| attr.targets);
+                let targets: Vec<_> = targets.iter().map(|target| target.plural()).collect();
+                diag.note(format!("`{}` can be used on {}", name, targets.join(", ")))
+            }
+            AttrErrorKind::Duplicate { first, .. } => diag.secondary(*first, "first used here"),
+            AttrErrorKind::Conflicting { other_span, .. } => {
+                diag.secondary(*other_span, "conflicts with this attribute")
+            }
+            _ => diag,
+        }
+    }
+}
+
+/// AttrErrorKind is the kind of attribute error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AttrErrorKind {
+    /// An attribute that is not built in.
+    Unknown(Symbol),
+    /// A built-in attribute on an item it does not apply to.
+    Misplaced { name: Symbol, target: Target },
+    /// A built-in attribute with arguments of the wrong shape.
+    InvalidArgs { name: Symbol, expected: Args },
+    /// An attribute used twice on the same item.
+    Duplicate { name: Symbol, first: Span },
+    /// Two attributes that cannot be used together, e.g. `inline` and
+    /// `noinline`.
+    Conflicting { name: Symbol, other: Symbol, other_span: Span },
+}
+
+impl fmt::Display for AttrErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AttrErrorKind::Unknown(name) => write!(f, "unknown attribute `{}`", name),
+            AttrErrorKind::Misplaced { name, target } => {
+                write!(f, "attribute `{}` cannot be used on {}", name, target.describe())
+            }
+            AttrErrorKind::InvalidArgs { name, expected } => {
+                write!(f, "attribute `{}` expects {}", name, expected.describe())
+            }
+            AttrErrorKind::Duplicate { name, .. } => {
+                write!(f, "attribute `{}` is used more than once", name)
+            }
+            AttrErrorKind::Conflicting { name, other, .. } => {
+                write!(f, "attribute `{}` cannot be used together with `{}`", name, other)
+            }
+        }
+    }
+}
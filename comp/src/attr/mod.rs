@@ -0,0 +1,507 @@
+//! Built-in attributes.
+//!
+//! The parser accepts any attribute name with any expressions as arguments,
+//! this module checks them against the registry of built-in attributes in
+//! `BUILTIN_ATTRS`: the name must be known, the attribute must be allowed on
+//! the item it is attached to and its arguments must have the expected shape.
+//!
+//! ```text
+//! @link("c")                      extern blocks, repeatable
+//! @inline  @noinline              functions and methods
+//! @export                         functions
+//! @extern_name("puts")            functions in extern blocks
+//! @deprecated  @deprecated("..")  functions, methods and type declarations
+//! @cfg(predicate)                 any item, repeatable
+//! @test                           functions
+//! @align(16)                      structs and unions
+//! @packed                         structs and unions
+//! ```
+//!
+//! A `cfg` predicate is a flag (`unix`), a key and a value
+//! (`target_os = "linux"`), or `not(p)`, `all(p, ...)` or `any(p, ...)`.
+//! Items whose predicates do not all hold are removed by `strip_cfg`. The
+//! uses of `deprecated` items are reported once names are resolved.
+
+use self::error::{AttrError, AttrErrorKind};
+use crate::{
+    ast::{Attribute, Expr, ExprKind, Item, ItemKind, Lit, Module},
+    span::Span,
+    symbol::{Symbol, sym},
+};
+use std::{collections::HashSet, env::consts};
+
+pub mod error;
+
+/// Kind of item an attribute is attached to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    Import,
+    ExternBlock,
+    Fn,
+    /// A function declared in an extern block.
+    ExternFn,
+    /// A function in an `impl` block or a trait.
+    Method,
+    Struct,
+    Union,
+    Enum,
+    TypeAlias,
+    Impl,
+    Trait,
+}
+
+impl Target {
+    /// Returns the target of an item, given the target of the item it is
+    /// nested in.
+    pub fn of(item: &Item, parent: Option<Target>) -> Target {
+        match &item.kind {
+            ItemKind::Import(_) => Target::Import,
+            ItemKind::Extern(_) => Target::ExternBlock,
+            ItemKind::Fn(_) => match parent {
+                Some(Target::ExternBlock) => Target::ExternFn,
+                Some(Target::Impl | Target::Trait) => Target::Method,
+                _ => Target::Fn,
+            },
+            ItemKind::Struct(_) => Target::Struct,
+            ItemKind::Union(_) => Target::Union,
+            ItemKind::Enum(_) => Target::Enum,
+            ItemKind::TypeAlias(_) => Target::TypeAlias,
+            ItemKind::Impl(_) => Target::Impl,
+            ItemKind::Trait(_) => Target::Trait,
+        }
+    }
+
+    /// Returns a description of the target with an article, e.g. `a struct`.
+    pub fn describe(self) -> &'static str {
+        match self {
+            Target::Import => "an import",
+            Target::ExternBlock => "an extern block",
+            Target::Fn => "a function",
+            Target::ExternFn => "an extern function",
+            Target::Method => "a method",
+            Target::Struct => "a struct",
+            Target::Union => "a union",
+            Target::Enum => "an enum",
+            Target::TypeAlias => "a type alias",
+            Target::Impl => "an impl block",
+            Target::Trait => "a trait",
+        }
+    }
+
+    /// Returns the plural description of the target, e.g. `structs`.
+    pub fn plural(self) -> &'static str {
+        match self {
+            Target::Import => "imports",
+            Target::ExternBlock => "extern blocks",
+            Target::Fn => "functions",
+            Target::ExternFn => "extern functions",
+            Target::Method => "methods",
+            Target::Struct => "structs",
+            Target::Union => "unions",
+            Target::Enum => "enums",
+            Target::TypeAlias => "type aliases",
+            Target::Impl => "impl blocks",
+            Target::Trait => "traits",
+        }
+    }
+}
+
+/// Arguments accepted by a built-in attribute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Args {
+    /// No arguments, e.g. `@inline`.
+    None,
+    /// A single string, e.g. `@link("c")`.
+    Str,
+    /// Nothing or a single string, e.g. `@deprecated("use `bar`")`.
+    OptionalStr,
+    /// A single power of two, e.g. `@align(16)`.
+    PowerOfTwo,
+    /// A single `cfg` predicate, e.g. `@cfg(unix)`.
+    Cfg,
+}
+
+impl Args {
+    /// Returns a description of the expected arguments.
+    pub fn describe(self) -> &'static str {
+        match self {
+            Args::None => "no arguments",
+            Args::Str => "a string",
+            Args::OptionalStr => "no arguments or a string",
+            Args::PowerOfTwo => "a power of two",
+            Args::Cfg => "a `cfg` predicate",
+        }
+    }
+}
+
+/// A built-in attribute.
+#[derive(Debug)]
+pub struct BuiltinAttr {
+    /// Name of the attribute.
+    pub name: Symbol,
+    /// Items the attribute can be attached to.
+    pub targets: &'static [Target],
+    /// Arguments the attribute takes.
+    pub args: Args,
+    /// Whether the attribute can be used more than once on an item.
+    pub repeatable: bool,
+}
+
+/// Every kind of item.
+const ANY: &[Target] = &[
+    Target::Import,
+    Target::ExternBlock,
+    Target::Fn,
+    Target::ExternFn,
+    Target::Method,
+    Target::Struct,
+    Target::Union,
+    Target::Enum,
+    Target::TypeAlias,
+    Target::Impl,
+    Target::Trait,
+];
+
+/// Registry of the built-in attributes.
+pub const BUILTIN_ATTRS: &[BuiltinAttr] = &[
+    BuiltinAttr {
+        name: sym::link,
+        targets: &[Target::ExternBlock],
+        args: Args::Str,
+        repeatable: true,
+    },
+    BuiltinAttr {
+        name: sym::inline,
+        targets: &[Target::Fn, Target::Method],
+        args: Args::None,
+        repeatable: false,
+    },
+    BuiltinAttr {
+        name: sym::noinline,
+        targets: &[Target::Fn, Target::Method],
+        args: Args::None,
+        repeatable: false,
+    },
+    BuiltinAttr { name: sym::export, targets: &[Target::Fn], args: Args::None, repeatable: false },
+    BuiltinAttr {
+        name: sym::extern_name,
+        targets: &[Target::ExternFn],
+        args: Args::Str,
+        repeatable: false,
+    },
+    BuiltinAttr {
+        name: sym::deprecated,
+        targets: &[
+            Target::Fn,
+            Target::ExternFn,
+            Target::Method,
+            Target::Struct,
+            Target::Union,
+            Target::Enum,
+            Target::TypeAlias,
+            Target::Trait,
+        ],
+        args: Args::OptionalStr,
+        repeatable: false,
+    },
+    BuiltinAttr { name: sym::cfg, targets: ANY, args: Args::Cfg, repeatable: true },
+    BuiltinAttr { name: sym::test, targets: &[Target::Fn], args: Args::None, repeatable: false },
+    BuiltinAttr {
+        name: sym::align,
+        targets: &[Target::Struct, Target::Union],
+        args: Args::PowerOfTwo,
+        repeatable: false,
+    },
+    BuiltinAttr {
+        name: sym::packed,
+        targets: &[Target::Struct, Target::Union],
+        args: Args::None,
+        repeatable: false,
+    },
+];
+
+/// Returns the built-in attribute with the given name.
+pub fn lookup(name: Symbol) -> Option<&'static BuiltinAttr> {
+    BUILTIN_ATTRS.iter().find(|attr| attr.name == name)
+}
+
+/// Inlining requested for a function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Inline {
+    /// `@inline`
+    Always,
+    /// `@noinline`
+    Never,
+}
+
+/// Validated built-in attributes of an item.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Attrs {
+    /// Libraries to link, from `@link`.
+    pub link: Vec<Symbol>,
+    /// Inlining requested by `@inline` or `@noinline`.
+    pub inline: Option<Inline>,
+    /// Whether the function is exported with its unmangled name.
+    pub export: bool,
+    /// Symbol name of an extern function, from `@extern_name`.
+    pub extern_name: Option<Symbol>,
+    /// Whether the item is deprecated, with an optional note.
+    pub deprecated: Option<Option<Symbol>>,
+    /// Predicates which must all hold for the item to be compiled.
+    pub cfg: Vec<Cfg>,
+    /// Whether the function is a test.
+    pub test: bool,
+    /// Alignment in bytes, from `@align`.
+    pub align: Option<u64>,
+    /// Whether the fields are laid out without padding.
+    pub packed: bool,
+}
+
+impl Attrs {
+    /// Validates the attributes of an item and collects the built-in ones.
+    /// Invalid attributes are reported and left out.
+    pub fn lower(attrs: &[Attribute], target: Target) -> (Attrs, Vec<AttrError>) {
+        let mut lowered = Attrs::default();
+        let mut errors = Vec::new();
+        let mut seen: Vec<&Attribute> = Vec::new();
+
+        for attr in attrs {
+            let name = attr.name.name;
+            let Some(builtin) = lookup(name) else {
+                errors.push(AttrError::new(AttrErrorKind::Unknown(name), attr.name.span));
+                continue;
+            };
+            if !builtin.targets.contains(&target) {
+                let kind = AttrErrorKind::Misplaced { name, target };
+                errors.push(AttrError::new(kind, attr.span));
+                continue;
+            }
+            if let Some(first) = seen.iter().find(|seen| seen.name.name == name) {
+                if !builtin.repeatable {
+                    let kind = AttrErrorKind::Duplicate { name, first: first.span };
+                    errors.push(AttrError::new(kind, attr.span));
+                    continue;
+                }
+            }
+            let conflict = match name {
+                sym::inline => Some(sym::noinline),
+                sym::noinline => Some(sym::inline),
+                _ => None,
+            };
+            if let Some(other) = seen.iter().find(|seen| Some(seen.name.name) == conflict) {
+                let kind = AttrErrorKind::Conflicting {
+                    name,
+                    other: other.name.name,
+                    other_span: other.span,
+                };
+                errors.push(AttrError::new(kind, attr.span));
+                continue;
+            }
+            seen.push(attr);
+
+            if let Err(span) = lowered.add(attr, builtin.args) {
+                let kind = AttrErrorKind::InvalidArgs { name, expected: builtin.args };
+                errors.push(AttrError::new(kind, span));
+            }
+        }
+
+        (lowered, errors)
+    }
+
+    /// Adds a built-in attribute after checking its arguments. Returns the
+    /// span of the offending arguments if they are invalid.
+    fn add(&mut self, attr: &Attribute, args: Args) -> Result<(), Span> {
+        let span = match (attr.args.first(), attr.args.last()) {
+            (Some(first), Some(last)) => first.span.to(last.span),
+            _ => attr.span,
+        };
+        let arg = match (args, attr.args.as_slice()) {
+            (Args::None | Args::OptionalStr, []) => None,
+            (Args::None, _) => return Err(span),
+            (_, [arg]) => Some(arg),
+            _ => return Err(span),
+        };
+
+        match attr.name.name {
+            sym::link => self.link.push(string(arg).ok_or(span)?),
+            sym::inline => self.inline = Some(Inline::Always),
+            sym::noinline => self.inline = Some(Inline::Never),
+            sym::export => self.export = true,
+            sym::extern_name => self.extern_name = Some(string(arg).ok_or(span)?),
+            sym::deprecated => {
+                let note = match arg {
+                    Some(_) => Some(string(arg).ok_or(span)?),
+                    None => None,
+                };
+                self.deprecated = Some(note);
+            }
+            sym::cfg => self.cfg.push(arg.and_then(Cfg::parse).ok_or(span)?),
+            sym::test => self.test = true,
+            sym::align => {
+                let align = match arg.map(|arg| &arg.kind) {
+                    Some(ExprKind::Lit(Lit::Int(lit))) => lit.value(),
+                    _ => None,
+                };
+                match align.and_then(|align| u64::try_from(align).ok()) {
+                    Some(align) if align.is_power_of_two() => self.align = Some(align),
+                    _ => return Err(span),
+                }
+            }
+            sym::packed => self.packed = true,
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Returns the value of a string literal argument.
+fn string(arg: Option<&Expr>) -> Option<Symbol> {
+    match &arg?.kind {
+        ExprKind::Lit(Lit::Str(s)) => Some(*s),
+        _ => None,
+    }
+}
+
+/// A `cfg` predicate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Cfg {
+    /// `name`, holds if the flag is set.
+    Flag(Symbol),
+    /// `key = "value"`, holds if the key has the value.
+    KeyValue(Symbol, Symbol),
+    /// `not(p)`
+    Not(Box<Cfg>),
+    /// `all(p, ...)`, holds if every predicate holds, or there are none.
+    All(Vec<Cfg>),
+    /// `any(p, ...)`, holds if one predicate holds.
+    Any(Vec<Cfg>),
+}
+
+impl Cfg {
+    /// Parses a predicate from an attribute argument, `None` if it is not a
+    /// predicate.
+    pub fn parse(expr: &Expr) -> Option<Cfg> {
+        match &expr.kind {
+            ExprKind::Path(path) => Some(Cfg::Flag(path.as_ident()?.name)),
+            ExprKind::Assign(key, value) => {
+                let ExprKind::Path(key) = &key.kind else {
+                    return None;
+                };
+                Some(Cfg::KeyValue(key.as_ident()?.name, string(Some(value))?))
+            }
+            ExprKind::Call(callee, args) => {
+                let ExprKind::Path(callee) = &callee.kind else {
+                    return None;
+                };
+                let args = args.iter().map(Cfg::parse).collect::<Option<Vec<_>>>()?;
+                match (callee.as_ident()?.name, args.as_slice()) {
+                    (sym::not, [arg]) => Some(Cfg::Not(Box::new(arg.clone()))),
+                    (sym::all, _) => Some(Cfg::All(args)),
+                    (sym::any, _) => Some(Cfg::Any(args)),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Evaluates the predicate against a configuration.
+    pub fn eval(&self, set: &CfgSet) -> bool {
+        match self {
+            Cfg::Flag(flag) => set.flags.contains(flag),
+            Cfg::KeyValue(key, value) => set.values.contains(&(*key, *value)),
+            Cfg::Not(cfg) => !cfg.eval(set),
+            Cfg::All(cfgs) => cfgs.iter().all(|cfg| cfg.eval(set)),
+            Cfg::Any(cfgs) => cfgs.iter().any(|cfg| cfg.eval(set)),
+        }
+    }
+}
+
+/// CfgSet is the configuration `cfg` predicates are evaluated against.
+#[derive(Debug, Clone, Default)]
+pub struct CfgSet {
+    /// Flags that are set, e.g. `unix`.
+    flags: HashSet<Symbol>,
+    /// Keys and their values, e.g. `target_os = "linux"`.
+    values: HashSet<(Symbol, Symbol)>,
+}
+
+impl CfgSet {
+    /// Creates a configuration describing the host the compiler runs on.
+    pub fn host() -> CfgSet {
+        let mut set = CfgSet::default()
+            .value(sym::target_os, consts::OS)
+            .value(sym::target_arch, consts::ARCH);
+        if !consts::FAMILY.is_empty() {
+            set = set.value(sym::target_family, consts::FAMILY);
+            set = set.flag(Symbol::intern(consts::FAMILY));
+        }
+        set
+    }
+
+    /// Sets a flag.
+    pub fn flag(mut self, flag: Symbol) -> CfgSet {
+        self.flags.insert(flag);
+        self
+    }
+
+    /// Sets a key to a value. A key can have several values.
+    pub fn value(mut self, key: Symbol, value: &str) -> CfgSet {
+        self.values.insert((key, Symbol::intern(value)));
+        self
+    }
+}
+
+/// Checks the attributes of every item of the module.
+pub fn check_module(module: &Module) -> Vec<AttrError> {
+    let mut errors = Vec::new();
+    check_items(&module.items, None, &mut errors);
+    errors
+}
+
+/// Checks the attributes of items nested in the given target.
+fn check_items(items: &[Item], parent: Option<Target>, errors: &mut Vec<AttrError>) {
+    for item in items {
+        let target = Target::of(item, parent);
+        errors.extend(Attrs::lower(&item.attrs, target).1);
+        if let Some(items) = nested_items(&item.kind) {
+            check_items(items, Some(target), errors);
+        }
+    }
+}
+
+/// Removes the items whose `cfg` predicates do not hold, including items
+/// nested in extern blocks, impl blocks and traits. Invalid predicates are
+/// left to `check_module` to report and do not remove the item.
+pub fn strip_cfg(module: &mut Module, set: &CfgSet) {
+    strip_items(&mut module.items, set);
+}
+
+/// Removes the disabled items of the list.
+fn strip_items(items: &mut Vec<Item>, set: &CfgSet) {
+    items.retain(|item| {
+        item.attrs
+            .iter()
+            .filter(|attr| attr.name.name == sym::cfg)
+            .filter_map(|attr| Cfg::parse(attr.args.first()?))
+            .all(|cfg| cfg.eval(set))
+    });
+    for item in items {
+        match &mut item.kind {
+            ItemKind::Extern(block) => strip_items(&mut block.items, set),
+            ItemKind::Impl(block) => strip_items(&mut block.items, set),
+            ItemKind::Trait(decl) => strip_items(&mut decl.items, set),
+            _ => {}
+        }
+    }
+}
+
+/// Returns the items nested in an item, if it has any.
+fn nested_items(kind: &ItemKind) -> Option<&[Item]> {
+    match kind {
+        ItemKind::Extern(block) => Some(&block.items),
+        ItemKind::Impl(block) => Some(&block.items),
+        ItemKind::Trait(decl) => Some(&decl.items),
+        _ => None,
+    }
+}
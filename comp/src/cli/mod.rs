@@ -1,7 +1,13 @@
 //! Simple CLI parser for dal compiler.
 
+use crate::diag::{Diagnostic, Emitter};
 use std::{cmp, collections::HashMap, env, process::exit};
 
+/// Prints a CLI usage error.
+fn error(message: String) {
+    Emitter::auto().emit(&Diagnostic::error(message), None);
+}
+
 /// App is the main struct for CLI parser.
 /// It contains all commands and arguments.
 pub struct App {
@@ -32,7 +38,7 @@ impl App {
 
     /// Error handler for unknown command.
     fn err_unknown_command(&self, command_name: &str) -> ! {
-        println!("error: Unknown command `{}`", command_name);
+        error(format!("unknown command `{}`", command_name));
         self.show_help(1)
     }
 
@@ -135,7 +141,16 @@ pub struct Command {
     /// find the argument by its short name.
     shorts: HashMap<String, String>,
     /// Handler of the command.
-    handler: Option<Box<dyn Fn(&CliContext) -> i32>>,
+    handler: Option<Handler>,
+}
+
+/// Handler of a command, returns the exit code.
+type Handler = Box<dyn Fn(&CliContext) -> i32>;
+
+impl Default for Command {
+    fn default() -> Self {
+        Command::new()
+    }
 }
 
 impl Command {
@@ -176,8 +191,8 @@ impl Command {
         F: FnOnce(Arg) -> Arg,
     {
         let arg = f(Arg::new());
-        if arg.short.is_some() {
-            self.shorts.insert(arg.short.as_ref().unwrap().to_string(), arg.name.clone());
+        if let Some(short) = &arg.short {
+            self.shorts.insert(short.to_string(), arg.name.clone());
         }
         self.args.insert(arg.name.clone(), arg);
         self
@@ -217,11 +232,11 @@ impl Command {
             let mut longest_opt = details[0].0.len();
             let mut longest_hint = details[0].1.len();
 
-            for (_, arg) in &self.args {
+            for arg in self.args.values() {
                 let mut opt = format!("--{}", arg.name);
-                if arg.short.is_some() {
+                if let Some(short) = &arg.short {
                     opt.push_str(", -");
-                    opt.push_str(arg.short.as_ref().unwrap());
+                    opt.push_str(short);
                 }
 
                 longest_opt = cmp::max(longest_opt, opt.len());
@@ -247,7 +262,7 @@ impl Command {
     /// Parse the command arguments and run the handler.
     fn run(&self, lists: &[String]) -> ! {
         if self.handler.is_none() {
-            println!("error: no handler for command '{}'", self.name);
+            error(format!("no handler for command `{}`", self.name));
             exit(1)
         }
 
@@ -264,6 +279,12 @@ impl Command {
                         (s.trim_start_matches("-"), false)
                     };
 
+                    // Long arguments may carry their value inline, e.g. `--emit=obj`.
+                    let (name, inline) = match name.split_once('=') {
+                        Some((name, val)) if is_long => (name, Some(val)),
+                        _ => (name, None),
+                    };
+
                     let arg = if is_long {
                         self.args.get(name)
                     } else {
@@ -271,13 +292,20 @@ impl Command {
                     };
 
                     if let Some(arg) = arg {
-                        match arg.kind {
-                            ArgKind::Bool => {
+                        match (arg.kind, inline) {
+                            (ArgKind::Bool, None) => {
                                 ctx.insert_bool(arg);
                             }
-                            ArgKind::String => {
+                            (ArgKind::Bool, Some(_)) => {
+                                error(format!("argument `{}` takes no value", arg.name));
+                                exit(1)
+                            }
+                            (ArgKind::String, Some(val)) => {
+                                ctx.insert_string(arg, val);
+                            }
+                            (ArgKind::String, None) => {
                                 let val = lists.get(i + 1).unwrap_or_else(|| {
-                                    println!("error: missing value for argument '{}'", arg.name);
+                                    error(format!("missing value for argument `{}`", arg.name));
                                     exit(1)
                                 });
                                 ctx.insert_string(arg, val);
@@ -285,7 +313,7 @@ impl Command {
                             }
                         }
                     } else {
-                        println!("error: unknown argument '{}'", name);
+                        error(format!("unknown argument `{}`", name));
                         exit(1)
                     }
                 }
@@ -360,6 +388,12 @@ impl Arg {
     }
 }
 
+impl Default for Arg {
+    fn default() -> Self {
+        Arg::new()
+    }
+}
+
 /// The argument kind.
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum ArgKind {
@@ -394,16 +428,16 @@ impl CliContext {
     /// Insert a boolean argument.
     fn insert_bool(&mut self, arg: &Arg) {
         self.long_keys.insert(arg.name.to_string(), 0);
-        if arg.short.is_some() {
-            self.short_keys.insert(arg.short.as_ref().unwrap().to_string(), 0);
+        if let Some(short) = &arg.short {
+            self.short_keys.insert(short.to_string(), 0);
         }
     }
 
     /// Insert a string argument.
     fn insert_string(&mut self, arg: &Arg, val: &str) {
         self.long_keys.insert(arg.name.to_string(), self.string_args.len());
-        if arg.short.is_some() {
-            self.short_keys.insert(arg.short.as_ref().unwrap().to_string(), self.string_args.len());
+        if let Some(short) = &arg.short {
+            self.short_keys.insert(short.to_string(), self.string_args.len());
         }
         self.string_args.push(val.to_string());
     }
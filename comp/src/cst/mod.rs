@@ -0,0 +1,449 @@
+//! Lossless concrete syntax tree.
+//!
+//! The tree is split in two layers. Green nodes are immutable, only know
+//! their kind, text length and children, and can be shared between trees.
+//! Red nodes (`SyntaxNode` and `SyntaxToken`) are built on demand on top of
+//! them and add the absolute position and a parent pointer.
+//!
+//! Every byte of the source file, including whitespace and comments, is
+//! stored in a token, so printing the root node gives back the file exactly.
+//! The tree is built by `parser::cst::parse_cst` from the tokens of a
+//! lossless lexer.
+
+use crate::{
+    lexer::token::{TokenKind, TriviaKind},
+    span::Span,
+};
+use std::{fmt, rc::Rc};
+
+/// Kind of a syntax node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SyntaxKind {
+    /// The whole file.
+    SourceFile,
+    /// Tokens the parser could not make sense of.
+    Error,
+    /// `module name`
+    ModuleDecl,
+    /// `@name(args)` or `@[a, b]`
+    Attribute,
+    /// `import "path"`
+    Import,
+    /// `extern { ... }`
+    ExternBlock,
+    /// A function or method.
+    Fn,
+    /// `[T, U]`
+    GenericParams,
+    /// `(a: T, b: U)`
+    ParamList,
+    /// `a: T` or `self`
+    Param,
+    /// `struct Name { ... }`
+    Struct,
+    /// `union Name { ... }`
+    Union,
+    /// `name: T` in a struct or union.
+    FieldDef,
+    /// `enum Name { ... }`
+    Enum,
+    /// A variant of an enum.
+    Variant,
+    /// `type Name = T`
+    TypeAlias,
+    /// `impl T { ... }`
+    Impl,
+    /// `trait Name { ... }`
+    Trait,
+    /// `a::b`
+    Path,
+    /// A named type.
+    PathType,
+    /// `*const T` or `*mut T`
+    PtrType,
+    /// `!`
+    NeverType,
+    /// `fn(T) -> R`
+    FnType,
+    /// `{ stmts }`
+    Block,
+    /// `let` or `const`
+    LetStmt,
+    /// `return expr`
+    ReturnStmt,
+    /// `defer expr`
+    DeferStmt,
+    /// An expression used as a statement.
+    ExprStmt,
+    /// A literal.
+    Literal,
+    /// A path used as an expression.
+    PathExpr,
+    /// `-x`, `!x` or `~x`
+    UnaryExpr,
+    /// `a + b`
+    BinaryExpr,
+    /// `a = b` or `a += b`
+    AssignExpr,
+    /// `a..b`
+    RangeExpr,
+    /// `f(args)`
+    CallExpr,
+    /// `(args)` of a call.
+    ArgList,
+    /// `f[T, U]`
+    TypeArgsExpr,
+    /// `a.b`
+    FieldExpr,
+    /// `a as T`
+    CastExpr,
+    /// `(a)`
+    ParenExpr,
+    /// A block used as an expression.
+    BlockExpr,
+    /// `if cond { ... } else { ... }`
+    IfExpr,
+    /// `while cond { ... }`
+    WhileExpr,
+    /// `for x in iter { ... }`
+    ForExpr,
+    /// `loop { ... }`
+    LoopExpr,
+    /// `break value`
+    BreakExpr,
+    /// `continue`
+    ContinueExpr,
+    /// `match x { arms }`
+    MatchExpr,
+    /// `pat => expr`
+    MatchArm,
+    /// `Point { x: 1 }`
+    StructLit,
+    /// `x: 1` in a struct literal.
+    FieldInit,
+    /// `sizeof(T)`
+    SizeofExpr,
+    /// `_`
+    WildPat,
+    /// A literal pattern.
+    LitPat,
+    /// A name binding the matched value.
+    BindingPat,
+    /// A path pattern.
+    PathPat,
+    /// `Path(pats)`
+    TupleStructPat,
+    /// `a | b`
+    OrPat,
+}
+
+/// Kind of a syntax token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyntaxTokenKind {
+    /// A token seen by the parser, including doc comments.
+    Token(TokenKind),
+    /// Whitespace or a plain comment.
+    Trivia(TriviaKind),
+}
+
+/// An immutable, position independent node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GreenNode {
+    /// Kind of the node.
+    kind: SyntaxKind,
+    /// Length of the text of the node in bytes.
+    len: usize,
+    /// Children of the node.
+    children: Vec<GreenElement>,
+}
+
+impl GreenNode {
+    /// Creates a node from its children.
+    pub fn new(kind: SyntaxKind, children: Vec<GreenElement>) -> GreenNode {
+        let len = children.iter().map(GreenElement::len).sum();
+        GreenNode { kind, len, children }
+    }
+
+    /// Returns the kind of the node.
+    pub fn kind(&self) -> SyntaxKind {
+        self.kind
+    }
+
+    /// Returns the length of the text of the node in bytes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Checks whether the node has no text.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the children of the node.
+    pub fn children(&self) -> &[GreenElement] {
+        &self.children
+    }
+}
+
+impl fmt::Display for GreenNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.children.iter().try_for_each(|child| write!(f, "{}", child))
+    }
+}
+
+/// An immutable, position independent token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GreenToken {
+    /// Kind of the token.
+    kind: SyntaxTokenKind,
+    /// Source text of the token.
+    text: String,
+}
+
+impl GreenToken {
+    /// Creates a token.
+    pub fn new(kind: SyntaxTokenKind, text: &str) -> GreenToken {
+        GreenToken { kind, text: text.to_string() }
+    }
+
+    /// Returns the kind of the token.
+    pub fn kind(&self) -> &SyntaxTokenKind {
+        &self.kind
+    }
+
+    /// Returns the source text of the token.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+/// A child of a green node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GreenElement {
+    Node(Rc<GreenNode>),
+    Token(Rc<GreenToken>),
+}
+
+impl GreenElement {
+    /// Returns the length of the text of the element in bytes.
+    pub fn len(&self) -> usize {
+        match self {
+            GreenElement::Node(node) => node.len,
+            GreenElement::Token(token) => token.text.len(),
+        }
+    }
+
+    /// Checks whether the element has no text.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl fmt::Display for GreenElement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GreenElement::Node(node) => write!(f, "{}", node),
+            GreenElement::Token(token) => f.write_str(&token.text),
+        }
+    }
+}
+
+/// Builds a green tree from a flat sequence of node starts, tokens and
+/// node ends.
+#[derive(Debug, Default)]
+pub struct GreenBuilder {
+    /// Nodes started but not finished yet, with their children so far.
+    stack: Vec<(SyntaxKind, Vec<GreenElement>)>,
+}
+
+impl GreenBuilder {
+    /// Creates a new builder.
+    pub fn new() -> GreenBuilder {
+        GreenBuilder::default()
+    }
+
+    /// Starts a node, the following elements are its children.
+    pub fn start_node(&mut self, kind: SyntaxKind) {
+        self.stack.push((kind, Vec::new()));
+    }
+
+    /// Adds a token to the current node.
+    pub fn token(&mut self, kind: SyntaxTokenKind, text: &str) {
+        let token = GreenElement::Token(Rc::new(GreenToken::new(kind, text)));
+        self.stack.last_mut().expect("no node started").1.push(token);
+    }
+
+    /// Finishes the current node.
+    pub fn finish_node(&mut self) {
+        let (kind, children) = self.stack.pop().expect("no node started");
+        let node = GreenElement::Node(Rc::new(GreenNode::new(kind, children)));
+        self.stack.last_mut().expect("root node finished early").1.push(node);
+    }
+
+    /// Returns the number of nodes started but not finished.
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Finishes all open nodes and returns the root.
+    pub fn finish(mut self) -> GreenNode {
+        while self.stack.len() > 1 {
+            self.finish_node();
+        }
+        let (kind, children) = self.stack.pop().expect("no node started");
+        GreenNode::new(kind, children)
+    }
+}
+
+/// A node with its absolute position and parent.
+#[derive(Debug, Clone)]
+pub struct SyntaxNode(Rc<NodeData>);
+
+/// Data of a red node.
+#[derive(Debug)]
+struct NodeData {
+    /// The underlying green node.
+    green: Rc<GreenNode>,
+    /// Global byte offset of the node.
+    offset: usize,
+    /// Parent of the node, `None` for the root.
+    parent: Option<SyntaxNode>,
+}
+
+impl SyntaxNode {
+    /// Creates the root node of a tree starting at the given global offset.
+    pub fn new_root(green: GreenNode, offset: usize) -> SyntaxNode {
+        SyntaxNode(Rc::new(NodeData { green: Rc::new(green), offset, parent: None }))
+    }
+
+    /// Returns the kind of the node.
+    pub fn kind(&self) -> SyntaxKind {
+        self.0.green.kind
+    }
+
+    /// Returns the span of the node.
+    pub fn span(&self) -> Span {
+        Span::new(self.0.offset, self.0.offset + self.0.green.len)
+    }
+
+    /// Returns the underlying green node.
+    pub fn green(&self) -> &GreenNode {
+        &self.0.green
+    }
+
+    /// Returns the parent of the node.
+    pub fn parent(&self) -> Option<&SyntaxNode> {
+        self.0.parent.as_ref()
+    }
+
+    /// Returns the children of the node, tokens included.
+    pub fn children(&self) -> Vec<SyntaxElement> {
+        let mut offset = self.0.offset;
+        let mut children = Vec::with_capacity(self.0.green.children.len());
+        for child in &self.0.green.children {
+            children.push(match child {
+                GreenElement::Node(green) => SyntaxElement::Node(SyntaxNode(Rc::new(NodeData {
+                    green: green.clone(),
+                    offset,
+                    parent: Some(self.clone()),
+                }))),
+                GreenElement::Token(green) => SyntaxElement::Token(SyntaxToken {
+                    green: green.clone(),
+                    offset,
+                    parent: self.clone(),
+                }),
+            });
+            offset += child.len();
+        }
+        children
+    }
+
+    /// Returns the child nodes of the node.
+    pub fn child_nodes(&self) -> Vec<SyntaxNode> {
+        self.children()
+            .into_iter()
+            .filter_map(|child| match child {
+                SyntaxElement::Node(node) => Some(node),
+                SyntaxElement::Token(_) => None,
+            })
+            .collect()
+    }
+
+    /// Returns all the tokens of the node in source order, trivia included.
+    pub fn tokens(&self) -> Vec<SyntaxToken> {
+        let mut tokens = Vec::new();
+        for child in self.children() {
+            match child {
+                SyntaxElement::Node(node) => tokens.extend(node.tokens()),
+                SyntaxElement::Token(token) => tokens.push(token),
+            }
+        }
+        tokens
+    }
+}
+
+impl fmt::Display for SyntaxNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.green)
+    }
+}
+
+/// A token with its absolute position and parent.
+#[derive(Debug, Clone)]
+pub struct SyntaxToken {
+    /// The underlying green token.
+    green: Rc<GreenToken>,
+    /// Global byte offset of the token.
+    offset: usize,
+    /// Node containing the token.
+    parent: SyntaxNode,
+}
+
+impl SyntaxToken {
+    /// Returns the kind of the token.
+    pub fn kind(&self) -> &SyntaxTokenKind {
+        &self.green.kind
+    }
+
+    /// Returns the source text of the token.
+    pub fn text(&self) -> &str {
+        &self.green.text
+    }
+
+    /// Checks whether the token is whitespace or a plain comment.
+    pub fn is_trivia(&self) -> bool {
+        matches!(self.green.kind, SyntaxTokenKind::Trivia(_))
+    }
+
+    /// Returns the span of the token.
+    pub fn span(&self) -> Span {
+        Span::new(self.offset, self.offset + self.green.text.len())
+    }
+
+    /// Returns the node containing the token.
+    pub fn parent(&self) -> &SyntaxNode {
+        &self.parent
+    }
+}
+
+impl fmt::Display for SyntaxToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.green.text)
+    }
+}
+
+/// A node or a token.
+#[derive(Debug, Clone)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+impl fmt::Display for SyntaxElement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyntaxElement::Node(node) => write!(f, "{}", node),
+            SyntaxElement::Token(token) => write!(f, "{}", token),
+        }
+    }
+}
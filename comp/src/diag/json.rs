@@ -0,0 +1,116 @@
+//! JSON rendering of diagnostics, selected with `--error-format=json`.
+//!
+//! Every diagnostic is printed as one JSON object on its own line:
+//!
+//! ```text
+//! {
+//!   "code": "E0001" | null,
+//!   "severity": "error" | "warning" | "note" | "help",
+//!   "message": "unknown character `$`",
+//!   "file": "main.dal" | null,       // file of the primary label
+//!   "span": <span> | null,           // first primary label
+//!   "labels": [{ "primary": true, "message": "...", "span": <span> }],
+//!   "notes": ["..."],
+//!   "help": ["..."],
+//!   "fixes": [{ "message": "...", "span": <span>, "replacement": "..." }]
+//! }
+//! ```
+//!
+//! where `<span>` is
+//!
+//! ```text
+//! {
+//!   "file": "main.dal",
+//!   "byte_start": 10, "byte_end": 11,
+//!   "line_start": 2, "column_start": 5, "line_end": 2, "column_end": 6
+//! }
+//! ```
+//!
+//! Byte offsets are relative to the start of the file, start from 0 and the
+//! end is exclusive. Lines and columns start from 1 and columns count
+//! characters.
+
+use super::Diagnostic;
+use crate::span::{SourceFile, SourceMap, Span};
+
+/// Renders the diagnostic as a single line JSON object.
+pub fn to_json(diag: &Diagnostic, sm: Option<&SourceMap>) -> String {
+    let span = |span: Span| match sm.and_then(|sm| sm.lookup_file(span.lo)) {
+        Some(file) => span_json(span, file),
+        None => "null".to_string(),
+    };
+    let file = diag.primary_span().and_then(|span| sm?.lookup_file(span.lo));
+
+    let labels = diag.labels.iter().map(|label| {
+        format!(
+            "{{\"primary\":{},\"message\":{},\"span\":{}}}",
+            label.primary,
+            string(&label.message),
+            span(label.span)
+        )
+    });
+    let fixes = diag.help.iter().filter_map(|help| {
+        let suggestion = help.suggestion.as_ref()?;
+        Some(format!(
+            "{{\"message\":{},\"span\":{},\"replacement\":{}}}",
+            string(&help.message),
+            span(suggestion.span),
+            string(&suggestion.replacement)
+        ))
+    });
+    let help = diag.help.iter().map(|help| string(&help.message));
+    let notes = diag.notes.iter().map(|note| string(note));
+
+    format!(
+        "{{\"code\":{},\"severity\":{},\"message\":{},\"file\":{},\"span\":{},\"labels\":{},\"notes\":{},\"help\":{},\"fixes\":{}}}",
+        diag.code.map_or("null".to_string(), string),
+        string(&diag.severity.to_string()),
+        string(&diag.message),
+        file.map_or("null".to_string(), |file| string(&file.path)),
+        diag.primary_span().map_or("null".to_string(), span),
+        array(labels),
+        array(notes),
+        array(help),
+        array(fixes)
+    )
+}
+
+/// Renders a span with its byte offsets and line/column range.
+pub(crate) fn span_json(span: Span, file: &SourceFile) -> String {
+    let lo = file.line_col(span.lo);
+    let hi = file.line_col(span.hi);
+    format!(
+        "{{\"file\":{},\"byte_start\":{},\"byte_end\":{},\"line_start\":{},\"column_start\":{},\"line_end\":{},\"column_end\":{}}}",
+        string(&file.path),
+        span.lo - file.start,
+        span.hi - file.start,
+        lo.line,
+        lo.col,
+        hi.line,
+        hi.col
+    )
+}
+
+/// Renders a JSON array from already rendered values.
+fn array(values: impl Iterator<Item = String>) -> String {
+    format!("[{}]", values.collect::<Vec<_>>().join(","))
+}
+
+/// Renders a JSON string, escaping it as needed.
+pub(crate) fn string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
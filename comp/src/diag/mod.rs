@@ -0,0 +1,397 @@
+//! Diagnostics reported by the compiler and their rendering.
+
+use crate::span::{SourceMap, Span, is_bidi_control};
+use std::{
+    env, fmt,
+    io::{self, IsTerminal},
+};
+
+pub mod json;
+
+/// Severity of a diagnostic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Help,
+    Note,
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Severity::Help => "help",
+            Severity::Note => "note",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// A labelled span of a diagnostic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Label {
+    /// Location the label points at.
+    pub span: Span,
+    /// Text printed next to the underline, may be empty.
+    pub message: String,
+    /// Primary labels point at the cause of the diagnostic and are
+    /// underlined with `^`, secondary labels with `-`.
+    pub primary: bool,
+}
+
+/// Diagnostic is an error, warning or note reported by any stage of the
+/// compiler.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// Severity of the diagnostic.
+    pub severity: Severity,
+    /// Error code, e.g. `E0001`.
+    pub code: Option<&'static str>,
+    /// Main message of the diagnostic.
+    pub message: String,
+    /// Labelled spans, in the order they were added.
+    pub labels: Vec<Label>,
+    /// Notes printed after the source snippet.
+    pub notes: Vec<String>,
+    /// Help messages printed after the notes.
+    pub help: Vec<Help>,
+}
+
+/// A help message, optionally with a fix that can be applied mechanically.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Help {
+    /// Text of the help message.
+    pub message: String,
+    /// Suggested fix, if any.
+    pub suggestion: Option<Suggestion>,
+}
+
+/// A suggested fix replacing the source covered by `span`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    /// Location to replace, empty to insert.
+    pub span: Span,
+    /// Text to put in place of the span.
+    pub replacement: String,
+}
+
+impl Diagnostic {
+    /// Creates a new diagnostic with the given severity and message.
+    pub fn new(severity: Severity, message: impl Into<String>) -> Diagnostic {
+        Diagnostic {
+            severity,
+            code: None,
+            message: message.into(),
+            labels: Vec::new(),
+            notes: Vec::new(),
+            help: Vec::new(),
+        }
+    }
+
+    /// Creates a new error.
+    pub fn error(message: impl Into<String>) -> Diagnostic {
+        Diagnostic::new(Severity::Error, message)
+    }
+
+    /// Creates a new warning.
+    pub fn warning(message: impl Into<String>) -> Diagnostic {
+        Diagnostic::new(Severity::Warning, message)
+    }
+
+    /// Sets the error code.
+    pub fn code(mut self, code: &'static str) -> Diagnostic {
+        self.code = Some(code);
+        self
+    }
+
+    /// Adds a primary label.
+    pub fn primary(mut self, span: Span, message: impl Into<String>) -> Diagnostic {
+        self.labels.push(Label { span, message: message.into(), primary: true });
+        self
+    }
+
+    /// Adds a secondary label.
+    pub fn secondary(mut self, span: Span, message: impl Into<String>) -> Diagnostic {
+        self.labels.push(Label { span, message: message.into(), primary: false });
+        self
+    }
+
+    /// Adds a note.
+    pub fn note(mut self, note: impl Into<String>) -> Diagnostic {
+        self.notes.push(note.into());
+        self
+    }
+
+    /// Adds a help message.
+    pub fn help(mut self, help: impl Into<String>) -> Diagnostic {
+        self.help.push(Help { message: help.into(), suggestion: None });
+        self
+    }
+
+    /// Adds a help message suggesting to replace `span` with `replacement`.
+    pub fn suggest(
+        mut self,
+        help: impl Into<String>,
+        span: Span,
+        replacement: impl Into<String>,
+    ) -> Diagnostic {
+        let suggestion = Some(Suggestion { span, replacement: replacement.into() });
+        self.help.push(Help { message: help.into(), suggestion });
+        self
+    }
+
+    /// Returns the span of the first primary label, if any.
+    pub fn primary_span(&self) -> Option<Span> {
+        self.labels.iter().find(|label| label.primary).map(|label| label.span)
+    }
+
+    /// Checks whether the diagnostic is an error.
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+/// ANSI escape codes used by the emitter.
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const GREEN: &str = "\x1b[1;32m";
+const CYAN: &str = "\x1b[1;36m";
+const BLUE: &str = "\x1b[1;34m";
+
+/// Width of a tab when rendering source lines.
+const TAB_WIDTH: usize = 4;
+
+/// Emitter renders diagnostics in a human readable, rustc like format:
+///
+/// ```text
+/// error[E0001]: unknown character `$`
+///  --> main.dal:2:20
+///   |
+/// 2 |     let x = 10 $
+///   |                ^ unknown character
+/// ```
+pub struct Emitter {
+    /// Output format.
+    format: ErrorFormat,
+    /// Whether to use ANSI colors.
+    color: bool,
+}
+
+/// Output format of the emitter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorFormat {
+    /// Rendered source snippets for humans.
+    Human,
+    /// One JSON object per line, see [`json`].
+    Json,
+}
+
+impl ErrorFormat {
+    /// Parses the value of `--error-format`.
+    pub fn from_name(name: &str) -> Option<ErrorFormat> {
+        match name {
+            "human" => Some(ErrorFormat::Human),
+            "json" => Some(ErrorFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+impl Emitter {
+    /// Creates a new emitter.
+    pub fn new(format: ErrorFormat, color: bool) -> Emitter {
+        Emitter { format, color }
+    }
+
+    /// Creates a human readable emitter that uses colors only when stderr is
+    /// a terminal and `NO_COLOR` is not set.
+    pub fn auto() -> Emitter {
+        Emitter::with_format(ErrorFormat::Human)
+    }
+
+    /// Creates an emitter with the given format, picking colors like `auto`.
+    pub fn with_format(format: ErrorFormat) -> Emitter {
+        Emitter::new(format, io::stderr().is_terminal() && env::var_os("NO_COLOR").is_none())
+    }
+
+    /// Wraps `s` in the given ANSI style if colors are enabled.
+    fn paint(&self, style: &str, s: &str) -> String {
+        if self.color { format!("{}{}{}", style, s, RESET) } else { s.to_string() }
+    }
+
+    /// Returns the style of the given severity.
+    fn severity_style(severity: Severity) -> &'static str {
+        match severity {
+            Severity::Error => RED,
+            Severity::Warning => YELLOW,
+            Severity::Note => GREEN,
+            Severity::Help => CYAN,
+        }
+    }
+
+    /// Prints the diagnostic to stderr, keeping stdout for the outputs of the
+    /// compiler. Labels are resolved against the source map; diagnostics that
+    /// are not tied to any source pass `None`.
+    pub fn emit(&self, diag: &Diagnostic, sm: Option<&SourceMap>) {
+        match self.format {
+            ErrorFormat::Human => eprint!("{}", self.render(diag, sm)),
+            ErrorFormat::Json => eprintln!("{}", json::to_json(diag, sm)),
+        }
+    }
+
+    /// Renders the diagnostic to a string.
+    pub fn render(&self, diag: &Diagnostic, sm: Option<&SourceMap>) -> String {
+        let mut out = String::new();
+
+        let style = Emitter::severity_style(diag.severity);
+        let header = match &diag.code {
+            Some(code) => format!("{}[{}]", diag.severity, code),
+            None => diag.severity.to_string(),
+        };
+        out.push_str(&self.paint(style, &header));
+        out.push_str(&self.paint(BOLD, &format!(": {}", diag.message)));
+        out.push('\n');
+
+        // Labels whose span is not in the source map cannot be shown.
+        let mut labels = diag
+            .labels
+            .iter()
+            .filter_map(|label| Some((sm?.lookup_file(label.span.lo)?, label)))
+            .collect::<Vec<_>>();
+        if labels.is_empty() {
+            self.render_footer(diag, &mut out, 0);
+            return out;
+        }
+
+        // Labels in the file of the primary label come first, then the others
+        // in source order.
+        let (main, main_label) =
+            *labels.iter().find(|(_, label)| label.primary).unwrap_or(&labels[0]);
+        let main_pos = main.line_col(main_label.span.lo);
+        let main = main.start;
+        labels.sort_by_key(|(file, label)| (file.start != main, label.span.lo, !label.primary));
+
+        let gutter = labels
+            .iter()
+            .map(|(file, label)| file.line_col(label.span.lo).line.to_string().len())
+            .max()
+            .unwrap_or(1);
+        let pad = " ".repeat(gutter);
+
+        let mut last: Option<(usize, usize)> = None;
+        for (file, label) in labels {
+            let lo = file.line_col(label.span.lo);
+            let text = file.line_text(lo.line).unwrap_or("");
+
+            match last {
+                Some((start, _)) if start == file.start => {}
+                _ => {
+                    let (arrow, pos) = if last.is_none() { ("-->", main_pos) } else { (":::", lo) };
+                    out.push_str(&format!(
+                        "{}{} {}:{}:{}\n",
+                        pad,
+                        self.paint(BLUE, arrow),
+                        file.path,
+                        pos.line,
+                        pos.col
+                    ));
+                    out.push_str(&format!("{} {}\n", pad, self.paint(BLUE, "|")));
+                    last = None;
+                }
+            }
+
+            if last.map(|(_, line)| line) != Some(lo.line) {
+                if last.is_some_and(|(_, line)| lo.line > line + 1) {
+                    out.push_str(&self.paint(BLUE, "...\n"));
+                }
+                let num = format!("{:>width$} |", lo.line, width = gutter);
+                out.push_str(&format!("{} {}\n", self.paint(BLUE, &num), display_line(text)));
+                last = Some((file.start, lo.line));
+            }
+
+            // Spans running past the end of the line are underlined up to its
+            // end, empty spans get a single marker.
+            let start = display_width(&text[..byte_offset(text, lo.col - 1)]);
+            let hi = file.line_col(label.span.hi);
+            let hi = if hi.line == lo.line { hi.col - 1 } else { text.chars().count() };
+            let end = display_width(&text[..byte_offset(text, hi)]);
+            let width = end.saturating_sub(start).max(1);
+
+            let (marker, style) = if label.primary {
+                ("^", Emitter::severity_style(diag.severity))
+            } else {
+                ("-", BLUE)
+            };
+            let mut underline = marker.repeat(width);
+            if !label.message.is_empty() {
+                underline.push(' ');
+                underline.push_str(&label.message);
+            }
+            out.push_str(&format!(
+                "{} {}{}\n",
+                self.paint(BLUE, &format!("{} |", pad)),
+                " ".repeat(start),
+                self.paint(style, &underline)
+            ));
+        }
+
+        self.render_footer(diag, &mut out, gutter);
+        out
+    }
+
+    /// Renders notes and help messages.
+    fn render_footer(&self, diag: &Diagnostic, out: &mut String, gutter: usize) {
+        let pad = " ".repeat(gutter);
+        if gutter > 0 && (!diag.notes.is_empty() || !diag.help.is_empty()) {
+            out.push_str(&format!("{} {}\n", pad, self.paint(BLUE, "|")));
+        }
+        let notes = diag.notes.iter().map(|note| ("note", note.clone()));
+        let help = diag.help.iter().map(|help| match &help.suggestion {
+            Some(suggestion) => ("help", format!("{}: `{}`", help.message, suggestion.replacement)),
+            None => ("help", help.message.clone()),
+        });
+        for (kind, message) in notes.chain(help) {
+            if gutter == 0 {
+                out.push_str(&format!("{}: {}\n", self.paint(BOLD, kind), message));
+            } else {
+                out.push_str(&format!(
+                    "{} {} {}: {}\n",
+                    pad,
+                    self.paint(BLUE, "="),
+                    self.paint(BOLD, kind),
+                    message
+                ));
+            }
+        }
+    }
+}
+
+/// Returns the byte offset of the `n`th character of `s`.
+fn byte_offset(s: &str, n: usize) -> usize {
+    s.char_indices().nth(n).map_or(s.len(), |(i, _)| i)
+}
+
+/// Returns how a character of a source line is printed. Tabs are expanded
+/// and bidirectional control characters are escaped, they would otherwise
+/// reorder the printed line.
+fn display_char(c: char) -> String {
+    match c {
+        '\t' => " ".repeat(TAB_WIDTH),
+        c if is_bidi_control(c) => format!("<U+{:04X}>", c as u32),
+        c => c.to_string(),
+    }
+}
+
+/// Returns the number of columns `s` takes when printed.
+fn display_width(s: &str) -> usize {
+    s.chars().map(|c| display_char(c).chars().count()).sum()
+}
+
+/// Prints a source line so it lines up with its underlines.
+fn display_line(s: &str) -> String {
+    s.chars().map(display_char).collect()
+}
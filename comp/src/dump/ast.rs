@@ -0,0 +1,258 @@
+//! Conversion of the AST into a dump tree.
+
+use super::Node;
+use crate::{
+    ast::{
+        Arm, Attribute, Block, Expr, ExprKind, FnDecl, Ident, Item, ItemKind, Lit, LocalKind,
+        Module, Mutability, Pat, PatKind, Path, Stmt, StmtKind, Ty, TyKind, Visibility,
+    },
+    lexer::token::Base,
+};
+
+/// Converts a module into a dump tree.
+pub fn module(module: &Module) -> Node {
+    let mut node = Node::new("Module", module.span);
+    if let Some(decl) = &module.decl {
+        node = node.detail(decl.name);
+    }
+    node.children(module.items.iter().map(item))
+}
+
+/// Converts an item, with its attributes.
+fn item(item: &Item) -> Node {
+    let (kind, name) = match &item.kind {
+        ItemKind::Import(import) => ("Import", Some(format!("{:?}", import.path.as_str()))),
+        ItemKind::Extern(_) => ("Extern", None),
+        ItemKind::Fn(decl) if decl.variadic => ("Fn", Some(format!("{} ...", decl.name.name))),
+        ItemKind::Fn(decl) => ("Fn", Some(decl.name.name.to_string())),
+        ItemKind::Struct(decl) => ("Struct", Some(decl.name.name.to_string())),
+        ItemKind::Union(decl) => ("Union", Some(decl.name.name.to_string())),
+        ItemKind::Enum(decl) => ("Enum", Some(decl.name.name.to_string())),
+        ItemKind::TypeAlias(alias) => ("TypeAlias", Some(alias.name.name.to_string())),
+        ItemKind::Impl(block) => ("Impl", block.trait_ref.as_ref().map(path)),
+        ItemKind::Trait(decl) => ("Trait", Some(decl.name.name.to_string())),
+    };
+    let detail = match (item.vis, name) {
+        (Visibility::Public, Some(name)) => Some(format!("pub {}", name)),
+        (Visibility::Public, None) => Some("pub".to_string()),
+        (Visibility::Private, name) => name,
+    };
+
+    let mut node = Node::new(kind, item.span).children(item.attrs.iter().map(attribute));
+    if let Some(detail) = detail {
+        node = node.detail(detail);
+    }
+    match &item.kind {
+        ItemKind::Import(_) => node,
+        ItemKind::Extern(block) => node.children(block.items.iter().map(self::item)),
+        ItemKind::Fn(decl) => fn_decl(node, decl),
+        ItemKind::Struct(decl) | ItemKind::Union(decl) => {
+            node.children(generics(&decl.generics)).children(decl.fields.iter().map(|field| {
+                let name = match field.vis {
+                    Visibility::Public => format!("pub {}", field.name.name),
+                    Visibility::Private => field.name.name.to_string(),
+                };
+                Node::new("Field", field.span).detail(name).child(ty(&field.ty))
+            }))
+        }
+        ItemKind::Enum(decl) => {
+            node.children(generics(&decl.generics)).children(decl.variants.iter().map(|variant| {
+                Node::new("Variant", variant.span)
+                    .detail(variant.name.name)
+                    .children(variant.fields.iter().map(ty))
+                    .children(variant.discriminant.as_ref().map(expr))
+            }))
+        }
+        ItemKind::TypeAlias(alias) => node.children(generics(&alias.generics)).child(ty(&alias.ty)),
+        ItemKind::Impl(block) => node
+            .children(generics(&block.generics))
+            .child(ty(&block.self_ty))
+            .children(block.items.iter().map(self::item)),
+        ItemKind::Trait(decl) => {
+            node.children(generics(&decl.generics)).children(decl.items.iter().map(self::item))
+        }
+    }
+}
+
+/// Converts an attribute with its arguments.
+fn attribute(attr: &Attribute) -> Node {
+    Node::new("Attribute", attr.span).detail(attr.name.name).children(attr.args.iter().map(expr))
+}
+
+/// Converts generic parameters.
+fn generics(generics: &[Ident]) -> impl Iterator<Item = Node> + '_ {
+    generics.iter().map(|param| Node::new("Generic", param.span).detail(param.name))
+}
+
+/// Adds the signature and body of a function to its node.
+fn fn_decl(node: Node, decl: &FnDecl) -> Node {
+    let mut node =
+        node.children(generics(&decl.generics)).children(decl.params.iter().map(|param| {
+            Node::new("Param", param.span).detail(param.name.name).child(ty(&param.ty))
+        }));
+    if let Some(ret) = &decl.ret {
+        node = node.child(Node::new("Ret", ret.span).child(ty(ret)));
+    }
+    node.children(decl.body.as_ref().map(block))
+}
+
+/// Formats a path as written, e.g. `Color::Red`.
+fn path(path: &Path) -> String {
+    let segments: Vec<_> = path.segments.iter().map(|segment| segment.name.as_str()).collect();
+    segments.join("::")
+}
+
+/// Converts a type.
+fn ty(ty: &Ty) -> Node {
+    match &ty.kind {
+        TyKind::Path(p) => Node::new("PathTy", ty.span).detail(path(p)),
+        TyKind::Ptr(mutability, pointee) => {
+            let detail = match mutability {
+                Mutability::Const => "const",
+                Mutability::Mut => "mut",
+            };
+            Node::new("PtrTy", ty.span).detail(detail).child(self::ty(pointee))
+        }
+        TyKind::Never => Node::new("NeverTy", ty.span),
+        TyKind::Fn(params, ret) => {
+            let node = Node::new("FnTy", ty.span).children(params.iter().map(self::ty));
+            match ret {
+                Some(ret) => node.child(Node::new("Ret", ret.span).child(self::ty(ret))),
+                None => node,
+            }
+        }
+    }
+}
+
+/// Converts a block.
+fn block(block: &Block) -> Node {
+    Node::new("Block", block.span).children(block.stmts.iter().map(stmt))
+}
+
+/// Converts a statement. Expression statements are shown as their
+/// expression.
+fn stmt(stmt: &Stmt) -> Node {
+    match &stmt.kind {
+        StmtKind::Local(local) => {
+            let kind = match local.kind {
+                LocalKind::Let => "Let",
+                LocalKind::Const => "Const",
+            };
+            let name = match local.mutability {
+                Mutability::Mut => format!("mut {}", local.name.name),
+                Mutability::Const => local.name.name.to_string(),
+            };
+            Node::new(kind, stmt.span)
+                .detail(name)
+                .children(local.ty.as_ref().map(ty))
+                .children(local.init.as_ref().map(expr))
+        }
+        StmtKind::Return(value) => {
+            Node::new("Return", stmt.span).children(value.as_ref().map(expr))
+        }
+        StmtKind::Defer(value) => Node::new("Defer", stmt.span).child(expr(value)),
+        StmtKind::Expr(value) => expr(value),
+    }
+}
+
+/// Converts an expression.
+fn expr(e: &Expr) -> Node {
+    let node = |kind| Node::new(kind, e.span);
+    match &e.kind {
+        ExprKind::Lit(lit) => node("Lit").detail(self::lit(lit)),
+        ExprKind::Path(p) => node("Path").detail(path(p)),
+        ExprKind::Unary(op, operand) => node("Unary").detail(op.as_str()).child(expr(operand)),
+        ExprKind::Binary(op, lhs, rhs) => {
+            node("Binary").detail(op.as_str()).child(expr(lhs)).child(expr(rhs))
+        }
+        ExprKind::Assign(lhs, rhs) => node("Assign").child(expr(lhs)).child(expr(rhs)),
+        ExprKind::AssignOp(op, lhs, rhs) => {
+            node("AssignOp").detail(format!("{}=", op.as_str())).child(expr(lhs)).child(expr(rhs))
+        }
+        ExprKind::Range(lo, hi, inclusive) => node("Range")
+            .detail(if *inclusive { "..=" } else { ".." })
+            .children(lo.as_deref().map(expr))
+            .children(hi.as_deref().map(expr)),
+        ExprKind::Call(callee, args) => {
+            node("Call").child(expr(callee)).children(args.iter().map(expr))
+        }
+        ExprKind::TypeArgs(callee, args) => {
+            node("TypeArgs").child(expr(callee)).children(args.iter().map(ty))
+        }
+        ExprKind::Field(base, name) => node("Field").detail(name.name).child(expr(base)),
+        ExprKind::Cast(value, target) => node("Cast").child(expr(value)).child(ty(target)),
+        ExprKind::Paren(inner) => node("Paren").child(expr(inner)),
+        ExprKind::Block(b) => block(b),
+        ExprKind::If(cond, then, els) => {
+            node("If").child(expr(cond)).child(block(then)).children(els.as_deref().map(expr))
+        }
+        ExprKind::While(cond, body) => node("While").child(expr(cond)).child(block(body)),
+        ExprKind::For(var, iter, body) => {
+            node("For").detail(var.name).child(expr(iter)).child(block(body))
+        }
+        ExprKind::Loop(body) => node("Loop").child(block(body)),
+        ExprKind::Break(value) => node("Break").children(value.as_deref().map(expr)),
+        ExprKind::Continue => node("Continue"),
+        ExprKind::Match(scrutinee, arms) => {
+            node("Match").child(expr(scrutinee)).children(arms.iter().map(arm))
+        }
+        ExprKind::Struct(p, fields) => {
+            node("StructLit").detail(path(p)).children(fields.iter().map(|field| {
+                Node::new("FieldInit", field.span).detail(field.name.name).child(expr(&field.expr))
+            }))
+        }
+        ExprKind::SizeOf(target) => node("SizeOf").child(ty(target)),
+    }
+}
+
+/// Converts a match arm.
+fn arm(arm: &Arm) -> Node {
+    Node::new("Arm", arm.span)
+        .child(pat(&arm.pat))
+        .children(arm.guard.as_ref().map(|guard| Node::new("Guard", guard.span).child(expr(guard))))
+        .child(expr(&arm.body))
+}
+
+/// Converts a pattern.
+fn pat(pat: &Pat) -> Node {
+    let node = |kind| Node::new(kind, pat.span);
+    match &pat.kind {
+        PatKind::Wild => node("WildPat"),
+        PatKind::Lit(value) => node("LitPat").child(expr(value)),
+        PatKind::Binding(name) => node("BindingPat").detail(name.name),
+        PatKind::Path(p) => node("PathPat").detail(path(p)),
+        PatKind::TupleStruct(p, fields) => {
+            node("TupleStructPat").detail(path(p)).children(fields.iter().map(self::pat))
+        }
+        PatKind::Or(pats) => node("OrPat").children(pats.iter().map(self::pat)),
+    }
+}
+
+/// Formats a literal as source code, with escapes normalized.
+fn lit(lit: &Lit) -> String {
+    match lit {
+        Lit::Int(lit) => {
+            let prefix = match lit.base {
+                Base::Binary => "0b",
+                Base::Octal => "0o",
+                Base::Decimal => "",
+                Base::Hexadecimal => "0x",
+            };
+            let suffix = lit.suffix.map_or("", |suffix| suffix.as_str());
+            format!("{}{}{}", prefix, lit.digits, suffix)
+        }
+        Lit::Float(lit) => {
+            format!("{}{}", lit.digits, lit.suffix.map_or("", |suffix| suffix.as_str()))
+        }
+        Lit::Str(s) => format!("{:?}", s.as_str()),
+        Lit::Char(c) => format!("{:?}", c),
+        Lit::ByteStr(bytes) => format!("b\"{}\"", bytes.escape_ascii()),
+        Lit::CStr(bytes) => {
+            // The terminating NUL is added by the lexer, not written.
+            let bytes = bytes.strip_suffix(&[0]).unwrap_or(bytes);
+            format!("c\"{}\"", bytes.escape_ascii())
+        }
+        Lit::Bool(b) => b.to_string(),
+        Lit::Null => "null".to_string(),
+    }
+}
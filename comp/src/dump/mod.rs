@@ -0,0 +1,147 @@
+//! Debug dumps of the compiler's intermediate results, printed by
+//! `--emit=tokens` and `--emit=ast`.
+//!
+//! Tokens are printed one per line with the line and column they start at:
+//!
+//! ```text
+//! 1:1    Module       "module"
+//! 1:8    Identifier   "main"
+//! ```
+//!
+//! The AST is printed as an indented tree, every node with its span as
+//! `line:col..line:col`:
+//!
+//! ```text
+//! Module 1:1..4:2
+//!   Fn main 3:1..4:2
+//!     Block 3:11..4:2
+//! ```
+//!
+//! With `--emit=tokens-json` and `--emit=ast-json`, every token is printed as a
+//! JSON object on its own line, `{"kind":"Identifier","text":"main","span":...}`,
+//! and the AST as a single JSON object with nested nodes,
+//! `{"kind":"Fn","detail":"main","span":...,"children":[...]}`. Spans use the
+//! format of `diag::json`.
+//!
+//! The output only depends on the source, so it can be used for snapshot
+//! tests.
+
+use crate::{
+    diag::json::{self, span_json},
+    lexer::token::Token,
+    span::{SourceFile, Span},
+};
+use std::fmt::Write;
+
+pub mod ast;
+
+/// Node of a dumped tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Node {
+    /// Kind of the node, e.g. `Fn`.
+    pub kind: &'static str,
+    /// Extra information about the node, e.g. the name of a function.
+    pub detail: Option<String>,
+    /// Span of the node.
+    pub span: Span,
+    /// Child nodes, in source order.
+    pub children: Vec<Node>,
+}
+
+impl Node {
+    /// Creates a node without detail or children.
+    pub fn new(kind: &'static str, span: Span) -> Node {
+        Node { kind, detail: None, span, children: Vec::new() }
+    }
+
+    /// Sets the detail of the node.
+    pub fn detail(mut self, detail: impl ToString) -> Node {
+        self.detail = Some(detail.to_string());
+        self
+    }
+
+    /// Adds a child node.
+    pub fn child(mut self, child: Node) -> Node {
+        self.children.push(child);
+        self
+    }
+
+    /// Adds child nodes.
+    pub fn children(mut self, children: impl IntoIterator<Item = Node>) -> Node {
+        self.children.extend(children);
+        self
+    }
+
+    /// Renders the tree as indented text, one node per line.
+    pub fn render(&self, file: &SourceFile) -> String {
+        let mut out = String::new();
+        self.render_into(file, 0, &mut out);
+        out
+    }
+
+    /// Renders the node and its children at the given depth.
+    fn render_into(&self, file: &SourceFile, depth: usize, out: &mut String) {
+        let _ = write!(out, "{:indent$}{}", "", self.kind, indent = depth * 2);
+        if let Some(detail) = &self.detail {
+            let _ = write!(out, " {}", detail);
+        }
+        let _ = writeln!(out, " {}", line_cols(self.span, file));
+        for child in &self.children {
+            child.render_into(file, depth + 1, out);
+        }
+    }
+
+    /// Renders the tree as a single line JSON object.
+    pub fn to_json(&self, file: &SourceFile) -> String {
+        let children: Vec<_> = self.children.iter().map(|child| child.to_json(file)).collect();
+        format!(
+            "{{\"kind\":{},\"detail\":{},\"span\":{},\"children\":[{}]}}",
+            json::string(self.kind),
+            self.detail.as_deref().map_or("null".to_string(), json::string),
+            span_json(self.span, file),
+            children.join(",")
+        )
+    }
+}
+
+/// Formats a span as `line:col..line:col`.
+fn line_cols(span: Span, file: &SourceFile) -> String {
+    let lo = file.line_col(span.lo);
+    let hi = file.line_col(span.hi);
+    format!("{}:{}..{}:{}", lo.line, lo.col, hi.line, hi.col)
+}
+
+/// Returns the name of the kind of a token, without its payload.
+fn token_kind(tok: &Token) -> String {
+    let kind = format!("{:?}", tok.kind);
+    match kind.find('(') {
+        Some(paren) => kind[..paren].to_string(),
+        None => kind,
+    }
+}
+
+/// Renders tokens as text, one per line.
+pub fn tokens(tokens: &[Token], file: &SourceFile) -> String {
+    let mut out = String::new();
+    for tok in tokens {
+        let lo = file.line_col(tok.span.lo);
+        let pos = format!("{}:{}", lo.line, lo.col);
+        let _ = writeln!(out, "{:<7}{:<13}{:?}", pos, token_kind(tok), file.snippet(tok.span));
+    }
+    out
+}
+
+/// Renders tokens as JSON objects, one per line.
+pub fn tokens_json(tokens: &[Token], file: &SourceFile) -> String {
+    let mut out = String::new();
+    for tok in tokens {
+        let _ = writeln!(
+            out,
+            "{{\"kind\":{},\"text\":{},\"span\":{}}}",
+            json::string(&token_kind(tok)),
+            json::string(file.snippet(tok.span)),
+            span_json(tok.span, file)
+        );
+    }
+    out
+}
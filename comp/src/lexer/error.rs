@@ -0,0 +1,172 @@
+use crate::{diag::Diagnostic, span::Span, symbol::Symbol};
+use std::fmt;
+
+/// LexError is an error or warning found while lexing, the lexer keeps going
+/// after reporting it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LexError {
+    pub kind: LexErrorKind,
+    pub span: Span,
+}
+
+impl LexError {
+    /// Creates a new lex error.
+    pub fn new(kind: LexErrorKind, span: Span) -> LexError {
+        LexError { kind, span }
+    }
+
+    /// Checks whether the error is only a warning, which does not stop the
+    /// compilation.
+    pub fn is_warning(&self) -> bool {
+        matches!(
+            self.kind,
+            LexErrorKind::BidiControl(_)
+                | LexErrorKind::MixedScript(_)
+                | LexErrorKind::Confusable { .. }
+        )
+    }
+
+    /// Converts the error into a diagnostic.
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let (code, label) = match &self.kind {
+            LexErrorKind::UnknownChar(_) => ("E0001", "unknown character"),
+            LexErrorKind::UnterminatedBlockComment => ("E0002", "comment starts here"),
+            LexErrorKind::UnterminatedString => ("E0003", "string starts here"),
+            LexErrorKind::UnterminatedRawString => ("E0004", "raw string starts here"),
+            LexErrorKind::UnterminatedChar => ("E0005", "character literal starts here"),
+            LexErrorKind::EmptyChar => ("E0006", "empty character literal"),
+            LexErrorKind::MultipleChars => ("E0007", "more than one character"),
+            LexErrorKind::UnknownEscape(_) => ("E0008", "unknown escape"),
+            LexErrorKind::InvalidHexEscape => ("E0009", "invalid escape"),
+            LexErrorKind::InvalidUnicodeEscape => ("E0010", "invalid escape"),
+            LexErrorKind::NonAsciiByte(_) => ("E0011", "must be ASCII"),
+            LexErrorKind::InteriorNul => ("E0012", "contains a NUL character"),
+            LexErrorKind::InvalidDigit(..) => ("E0013", "invalid digit"),
+            LexErrorKind::MissingDigits => ("E0014", "missing digits"),
+            LexErrorKind::MissingExponent => ("E0015", "missing exponent digits"),
+            LexErrorKind::InvalidSuffix(_) => ("E0016", "invalid suffix"),
+            LexErrorKind::BidiControl(_) => ("W0001", "bidirectional control character"),
+            LexErrorKind::MixedScript(_) => ("W0002", "mixes scripts"),
+            LexErrorKind::Confusable { .. } => ("W0003", "looks like another identifier"),
+        };
+
+        let diag = if self.is_warning() {
+            Diagnostic::warning(self.kind.to_string())
+        } else {
+            Diagnostic::error(self.kind.to_string())
+        };
+        let diag = diag.code(code).primary(self.span, label);
+        match &self.kind {
+            LexErrorKind::UnknownEscape(_) => diag.help(
+                "valid escapes are `\\n`, `\\t`, `\\r`, `\\0`, `\\\\`, `\\\"`, `\\'`, `\\x7f` and `\\u{7fff}`",
+            ),
+            LexErrorKind::NonAsciiByte(_) => {
+                diag.help("use a `\\x` escape for bytes that are not ASCII")
+            }
+            LexErrorKind::InteriorNul => {
+                diag.note("C string literals are NUL terminated automatically")
+            }
+            LexErrorKind::InvalidSuffix(_) => diag.help(
+                "valid suffixes are `i8`, `i16`, `i32`, `i64`, `isize`, `u8`, `u16`, `u32`, `u64`, `usize`, `f32` and `f64`",
+            ),
+            LexErrorKind::BidiControl(_) => diag
+                .note("these characters can make the code look different from how it is compiled")
+                .help("remove it, or write it as a `\\u{...}` escape in a literal"),
+            LexErrorKind::MixedScript(_) => {
+                diag.note("identifiers mixing scripts can look like other identifiers")
+            }
+            LexErrorKind::Confusable { other_span, .. } => {
+                diag.secondary(*other_span, "the other identifier is here")
+            }
+            _ => diag,
+        }
+    }
+}
+
+/// LexErrorKind is the kind of lex error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LexErrorKind {
+    /// A character that does not start any token.
+    UnknownChar(char),
+    /// A string literal without a closing quote.
+    UnterminatedString,
+    /// A raw string literal without a closing quote and `#`s.
+    UnterminatedRawString,
+    /// A character literal without a closing quote.
+    UnterminatedChar,
+    /// A character literal without a character, e.g. `''`.
+    EmptyChar,
+    /// A character literal with more than one character, e.g. `'ab'`.
+    MultipleChars,
+    /// An unknown escape sequence, e.g. `\q`.
+    UnknownEscape(char),
+    /// A malformed or out of range `\x` escape.
+    InvalidHexEscape,
+    /// A malformed or out of range `\u{...}` escape.
+    InvalidUnicodeEscape,
+    /// A non-ASCII character in a byte string.
+    NonAsciiByte(char),
+    /// A NUL character inside a C string literal.
+    InteriorNul,
+    /// A block comment without a closing `*/`.
+    UnterminatedBlockComment,
+    /// A digit that is not valid in the base of the literal, e.g. `0b12`.
+    InvalidDigit(char, u32),
+    /// An integer literal with a base prefix but no digits, e.g. `0x`.
+    MissingDigits,
+    /// An exponent without digits, e.g. `1e` or `1e+`.
+    MissingExponent,
+    /// An unknown literal suffix, e.g. `12ab`.
+    InvalidSuffix(String),
+    /// A Unicode bidirectional control character, which can hide what the
+    /// code does.
+    BidiControl(char),
+    /// An identifier made of characters from several scripts.
+    MixedScript(Symbol),
+    /// An identifier that looks the same as another, different identifier.
+    Confusable { name: Symbol, other: Symbol, other_span: Span },
+}
+
+impl fmt::Display for LexErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LexErrorKind::UnknownChar(c) => write!(f, "unknown character `{}`", c.escape_debug()),
+            LexErrorKind::UnterminatedString => write!(f, "unterminated string literal"),
+            LexErrorKind::UnterminatedRawString => write!(f, "unterminated raw string literal"),
+            LexErrorKind::UnterminatedChar => write!(f, "unterminated character literal"),
+            LexErrorKind::EmptyChar => write!(f, "empty character literal"),
+            LexErrorKind::MultipleChars => {
+                write!(f, "character literal may only contain one character")
+            }
+            LexErrorKind::UnknownEscape(c) => {
+                write!(f, "unknown character escape `\\{}`", c.escape_debug())
+            }
+            LexErrorKind::InvalidHexEscape => {
+                write!(f, "invalid `\\x` escape, expected two hex digits up to `7f` in strings")
+            }
+            LexErrorKind::InvalidUnicodeEscape => write!(f, "invalid unicode escape"),
+            LexErrorKind::NonAsciiByte(c) => {
+                write!(f, "non-ASCII character `{}` in byte string literal", c)
+            }
+            LexErrorKind::InteriorNul => write!(f, "C string literal contains a NUL character"),
+            LexErrorKind::UnterminatedBlockComment => write!(f, "unterminated block comment"),
+            LexErrorKind::InvalidDigit(c, base) => {
+                write!(f, "invalid digit `{}` in base {} literal", c, base)
+            }
+            LexErrorKind::MissingDigits => write!(f, "missing digits after integer base prefix"),
+            LexErrorKind::MissingExponent => write!(f, "expected at least one digit in exponent"),
+            LexErrorKind::InvalidSuffix(s) => {
+                write!(f, "invalid suffix `{}` for number literal", s)
+            }
+            LexErrorKind::BidiControl(c) => {
+                write!(f, "unicode bidirectional control character `U+{:04X}` in source", *c as u32)
+            }
+            LexErrorKind::MixedScript(name) => {
+                write!(f, "identifier `{}` mixes characters from different scripts", name)
+            }
+            LexErrorKind::Confusable { name, other, .. } => {
+                write!(f, "identifier `{}` is confusable with `{}`", name, other)
+            }
+        }
+    }
+}
@@ -1,41 +1,131 @@
-use self::token::{Token, TokenKind};
-use crate::span::{SourceFile, Span};
-use std::{process::exit, str::Chars};
+use self::{
+    error::{LexError, LexErrorKind},
+    token::{
+        Base, DocStyle, FLOAT_SUFFIXES, FloatLit, INT_SUFFIXES, IntLit, Token, TokenKind, Trivia,
+        TriviaKind,
+    },
+};
+use crate::{
+    span::{BOM, Edit, SourceFile, Span, is_bidi_control, is_line_break},
+    symbol::Symbol,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    mem,
+    str::Chars,
+};
+use unicode_normalization::UnicodeNormalization;
+use unicode_security::{MixedScript, confusable_detection::skeleton};
 
+pub mod error;
 pub mod token;
 
-/// Lexer for the language.
+/// Lexer for the language. The lexer is an iterator over the tokens of a
+/// source file, `lex` collects all of them at once.
 pub struct Lexer<'a> {
-    /// The source file.
+    /// The source file being lexed.
     source: &'a SourceFile,
-    /// The characters of the source file.
+    /// The remaining characters of the source file.
     chars: Chars<'a>,
-    /// Resulting tokens.
-    tokens: Vec<Token>,
-    /// End of file token.
-    eof: Token,
-    /// Last position of the lexer.
+    /// Errors found while lexing.
+    errors: Vec<LexError>,
+    /// Whether the end of file was reached.
+    done: bool,
+    /// Byte offset of the start of the current token.
     lo: usize,
-    /// Current position of the lexer.
+    /// Byte offset of the current position of the lexer.
     hi: usize,
+    /// Whether whitespace and comments are recorded as trivia.
+    lossless: bool,
+    /// Trivia recorded since the last token.
+    trivia: Vec<Trivia>,
+    /// Whether a line break was skipped since the last token.
+    newline: bool,
+    /// Identifiers seen so far, to warn about confusable ones.
+    confusables: Confusables,
+}
+
+/// Confusables tracks the identifiers of a file to find ones that look the
+/// same as another identifier, e.g. a Latin `a` and a Cyrillic `а`.
+#[derive(Default)]
+struct Confusables {
+    /// Distinct identifiers seen so far.
+    seen: HashSet<Symbol>,
+    /// First occurrence of every identifier, kept until the first non-ASCII
+    /// identifier shows up. Files with only ASCII identifiers never compute
+    /// skeletons.
+    pending: Vec<(Symbol, Span)>,
+    /// First identifier with every skeleton, built once a non-ASCII
+    /// identifier shows up.
+    skeletons: Option<HashMap<String, (Symbol, Span)>>,
+}
+
+impl Confusables {
+    /// Records an identifier and returns the warning to report if it is
+    /// confusable with an earlier one. Only pairs where at least one
+    /// identifier is not ASCII are reported, once per identifier.
+    fn check(&mut self, name: Symbol, span: Span) -> Option<LexErrorKind> {
+        if !self.seen.insert(name) {
+            return None;
+        }
+        let skeletons = match &mut self.skeletons {
+            Some(skeletons) => skeletons,
+            None if name.as_str().is_ascii() => {
+                self.pending.push((name, span));
+                return None;
+            }
+            None => {
+                let mut skeletons = HashMap::new();
+                for (name, span) in mem::take(&mut self.pending) {
+                    skeletons.entry(skeleton(name.as_str()).collect()).or_insert((name, span));
+                }
+                self.skeletons.insert(skeletons)
+            }
+        };
+
+        let (other, other_span) =
+            *skeletons.entry(skeleton(name.as_str()).collect()).or_insert((name, span));
+        if other == name || (other.as_str().is_ascii() && name.as_str().is_ascii()) {
+            return None;
+        }
+        Some(LexErrorKind::Confusable { name, other, other_span })
+    }
 }
 
 const EOF_CHAR: char = '\0';
 
+/// Checks whether the character is whitespace, i.e. in the Unicode
+/// `Pattern_White_Space` set.
 fn is_whitespace(c: char) -> bool {
-    c == ' ' || c == '\n'
+    matches!(
+        c,
+        '\t' | '\n'
+            | '\u{b}'
+            | '\u{c}'
+            | '\r'
+            | ' '
+            | '\u{85}'
+            | '\u{200e}'
+            | '\u{200f}'
+            | '\u{2028}'
+            | '\u{2029}'
+    )
 }
 
 fn is_digit(c: char) -> bool {
-    c.is_digit(10)
+    c.is_ascii_digit()
 }
 
+/// Checks whether the character can start an identifier, following UAX #31
+/// with `_` added.
 fn is_id_start(c: char) -> bool {
-    c.is_alphabetic() || c == '_'
+    c == '_' || unicode_ident::is_xid_start(c)
 }
 
+/// Checks whether the character can continue an identifier, following
+/// UAX #31.
 fn is_id_continue(c: char) -> bool {
-    is_id_start(c) || is_digit(c)
+    unicode_ident::is_xid_continue(c)
 }
 
 impl<'a> Lexer<'a> {
@@ -44,18 +134,34 @@ impl<'a> Lexer<'a> {
         Lexer {
             source,
             chars: source.content.chars(),
-            tokens: Vec::new(),
-            eof: Token::new(token::TokenKind::Eof, source.eof_span()),
-            lo: 0,
-            hi: 0,
+            errors: Vec::new(),
+            done: false,
+            lo: source.start,
+            hi: source.start,
+            lossless: false,
+            trivia: Vec::new(),
+            newline: false,
+            confusables: Confusables::default(),
         }
     }
 
+    /// Makes the lexer lossless: whitespace and comments are attached to the
+    /// following token as trivia and the tokens end with an EOF token holding
+    /// the trailing trivia, so they cover every byte of the source file.
+    pub fn lossless(mut self) -> Lexer<'a> {
+        self.lossless = true;
+        self
+    }
+
     /// Bumps the lexer to the next character.
     fn bump(&mut self) -> char {
         let c = self.chars.next().unwrap_or(EOF_CHAR);
         if c != EOF_CHAR {
-            self.hi += 1;
+            self.hi += c.len_utf8();
+        }
+        if is_bidi_control(c) {
+            let span = Span::new(self.hi - c.len_utf8(), self.hi);
+            self.error_at(LexErrorKind::BidiControl(c), span);
         }
         c
     }
@@ -72,6 +178,16 @@ impl<'a> Lexer<'a> {
         chars.next().unwrap_or(EOF_CHAR)
     }
 
+    /// Bumps the lexer if the next character is `c`.
+    fn eat(&mut self, c: char) -> bool {
+        if self.first() == c {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
     /// Eats all the characters that satisfy the predicate or until the end of
     /// file.
     fn eat_while<F>(&mut self, mut f: F) -> String
@@ -93,10 +209,401 @@ impl<'a> Lexer<'a> {
         sp
     }
 
-    /// Gets the next token.
+    /// Records an error spanning from the start of the current token to the
+    /// current position.
+    fn error(&mut self, kind: LexErrorKind) {
+        self.error_at(kind, Span::new(self.lo, self.hi));
+    }
+
+    /// Records an error at the given span.
+    fn error_at(&mut self, kind: LexErrorKind, span: Span) {
+        self.errors.push(LexError::new(kind, span));
+    }
+
+    /// Lexes the rest of a quoted literal after its opening quote and returns
+    /// its characters with escapes decoded. In byte mode every character is
+    /// below 256 and stands for one byte.
+    fn quoted(&mut self, quote: char, byte: bool) -> Vec<char> {
+        let open = Span::new(self.hi - 1, self.hi);
+        let mut chars = Vec::new();
+        loop {
+            match self.bump() {
+                EOF_CHAR => {
+                    let kind = if quote == '"' {
+                        LexErrorKind::UnterminatedString
+                    } else {
+                        LexErrorKind::UnterminatedChar
+                    };
+                    self.error_at(kind, open);
+                    return chars;
+                }
+                c if c == quote => return chars,
+                // Line breaks in literals are `\n` whatever the file uses.
+                '\r' if self.first() == '\n' => {}
+                '\\' => {
+                    if let Some(c) = self.escape(byte) {
+                        chars.push(c);
+                    }
+                }
+                c => {
+                    if byte && !c.is_ascii() {
+                        let span = Span::new(self.hi - c.len_utf8(), self.hi);
+                        self.error_at(LexErrorKind::NonAsciiByte(c), span);
+                    }
+                    chars.push(c);
+                }
+            }
+        }
+    }
+
+    /// Decodes an escape sequence after the `\`. Returns `None` if it is
+    /// invalid, after reporting it.
+    fn escape(&mut self, byte: bool) -> Option<char> {
+        let lo = self.hi - 1;
+        let c = match self.bump() {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            '0' => '\0',
+            '\\' => '\\',
+            '"' => '"',
+            '\'' => '\'',
+            'x' => {
+                let mut value = 0;
+                for _ in 0..2 {
+                    match self.first().to_digit(16) {
+                        Some(d) => {
+                            self.bump();
+                            value = value * 16 + d;
+                        }
+                        None => {
+                            self.error_at(LexErrorKind::InvalidHexEscape, Span::new(lo, self.hi));
+                            return None;
+                        }
+                    }
+                }
+                if !byte && value > 0x7f {
+                    self.error_at(LexErrorKind::InvalidHexEscape, Span::new(lo, self.hi));
+                    return None;
+                }
+                char::from_u32(value)?
+            }
+            'u' => {
+                let value = self.unicode_escape();
+                match value.and_then(char::from_u32) {
+                    Some(c) if !byte => c,
+                    _ => {
+                        self.error_at(LexErrorKind::InvalidUnicodeEscape, Span::new(lo, self.hi));
+                        return None;
+                    }
+                }
+            }
+            c => {
+                self.error_at(LexErrorKind::UnknownEscape(c), Span::new(lo, self.hi));
+                return None;
+            }
+        };
+        Some(c)
+    }
+
+    /// Lexes the `{XXXX}` part of a `\u{XXXX}` escape.
+    fn unicode_escape(&mut self) -> Option<u32> {
+        if self.first() != '{' {
+            return None;
+        }
+        self.bump();
+        let digits = self.eat_while(|c| c.is_ascii_hexdigit() || c == '_');
+        if self.first() != '}' {
+            return None;
+        }
+        self.bump();
+        let digits = digits.replace('_', "");
+        if digits.is_empty() || digits.len() > 6 {
+            return None;
+        }
+        u32::from_str_radix(&digits, 16).ok()
+    }
+
+    /// Lexes a character literal after the opening quote.
+    fn char_literal(&mut self) -> TokenKind {
+        let errors = self.errors.len();
+        let chars = self.quoted('\'', false);
+        if chars.len() != 1 {
+            if self.errors.len() == errors {
+                let kind = if chars.is_empty() {
+                    LexErrorKind::EmptyChar
+                } else {
+                    LexErrorKind::MultipleChars
+                };
+                self.error(kind);
+            }
+            return TokenKind::Unknown;
+        }
+        TokenKind::Char(chars[0])
+    }
+
+    /// Checks whether an `r` starts a raw string, i.e. it is followed by any
+    /// number of `#` and a `"`.
+    fn is_raw_string_start(&self) -> bool {
+        let mut chars = self.chars.clone().skip_while(|c| *c == '#');
+        chars.next() == Some('"')
+    }
+
+    /// Lexes a raw string, `r"..."` or `r#"..."#`, after the `r`. Raw strings
+    /// have no escapes and end at a quote followed by as many `#` as they
+    /// started with.
+    fn raw_string(&mut self) -> TokenKind {
+        let hashes = self.eat_while(|c| c == '#').len();
+        self.bump();
+        let open = Span::new(self.lo, self.hi);
+
+        let mut s = String::new();
+        loop {
+            match self.bump() {
+                EOF_CHAR => {
+                    self.error_at(LexErrorKind::UnterminatedRawString, open);
+                    break;
+                }
+                '"' if self.chars.clone().take(hashes).filter(|c| *c == '#').count() == hashes => {
+                    for _ in 0..hashes {
+                        self.bump();
+                    }
+                    break;
+                }
+                '\r' if self.first() == '\n' => {}
+                c => s.push(c),
+            }
+        }
+        TokenKind::String(Symbol::intern(&s))
+    }
+
+    /// Lexes a C string, `c"..."`, after the `c`. The result is UTF-8 encoded
+    /// and terminated by a NUL byte.
+    fn c_string(&mut self) -> TokenKind {
+        self.bump();
+        let s: String = self.quoted('"', false).into_iter().collect();
+        if s.contains('\0') {
+            self.error(LexErrorKind::InteriorNul);
+        }
+        let mut bytes = s.into_bytes();
+        bytes.push(0);
+        TokenKind::CString(bytes)
+    }
+
+    /// Lexes a number literal starting with the digit `c`.
+    fn number(&mut self, c: char) -> TokenKind {
+        let base = match (c, self.first()) {
+            ('0', 'x') => Base::Hexadecimal,
+            ('0', 'o') => Base::Octal,
+            ('0', 'b') => Base::Binary,
+            _ => Base::Decimal,
+        };
+
+        if base != Base::Decimal {
+            self.bump();
+            let errors = self.errors.len();
+            let digits = self.digits(base, String::new());
+            if digits.is_empty() && self.errors.len() == errors {
+                self.error(LexErrorKind::MissingDigits);
+            }
+            let suffix = self.suffix(INT_SUFFIXES);
+            let digits = Symbol::intern(&digits);
+            return TokenKind::Integer(IntLit { digits, base, suffix });
+        }
+
+        let mut digits = self.digits(base, c.to_string());
+        let mut is_float = false;
+
+        // `1.foo` and `1..2` are not floats.
+        if self.first() == '.' && is_digit(self.second()) {
+            self.bump();
+            digits.push('.');
+            digits = self.digits(base, digits);
+            is_float = true;
+        }
+
+        // `1e` is a float missing its exponent, `1ex` an integer with a
+        // suffix.
+        if matches!(self.first(), 'e' | 'E')
+            && (is_digit(self.second())
+                || matches!(self.second(), '+' | '-')
+                || !is_id_continue(self.second()))
+        {
+            let e = self.hi;
+            digits.push(self.bump());
+            if matches!(self.first(), '+' | '-') {
+                digits.push(self.bump());
+            }
+            let len = digits.len();
+            digits = self.digits(base, digits);
+            if digits.len() == len {
+                self.error_at(LexErrorKind::MissingExponent, Span::new(e, e + 1));
+            }
+            is_float = true;
+        }
+
+        // An integer with a float suffix, e.g. `1f32`, is a float.
+        if is_float || FLOAT_SUFFIXES.iter().any(|s| self.starts_with(s)) {
+            let suffix = self.suffix(FLOAT_SUFFIXES);
+            return TokenKind::Float(FloatLit { digits: Symbol::intern(&digits), suffix });
+        }
+
+        let suffix = self.suffix(INT_SUFFIXES);
+        TokenKind::Integer(IntLit { digits: Symbol::intern(&digits), base, suffix })
+    }
+
+    /// Eats digits and `_` separators, appending digits to `s`. Decimal digits
+    /// outside of `base` are reported and skipped.
+    fn digits(&mut self, base: Base, mut s: String) -> String {
+        loop {
+            let c = self.first();
+            if c == '_' {
+                self.bump();
+            } else if c.is_digit(base as u32) {
+                s.push(self.bump());
+            } else if is_digit(c) {
+                self.bump();
+                let span = Span::new(self.hi - 1, self.hi);
+                self.error_at(LexErrorKind::InvalidDigit(c, base as u32), span);
+            } else {
+                return s;
+            }
+        }
+    }
+
+    /// Checks whether the remaining characters start with `s` followed by a
+    /// character that cannot continue an identifier.
+    fn starts_with(&self, s: &str) -> bool {
+        let mut chars = self.chars.clone();
+        s.chars().all(|c| chars.next() == Some(c)) && !chars.next().is_some_and(is_id_continue)
+    }
+
+    /// Eats a literal suffix, reporting it if it is not one of `valid`.
+    fn suffix(&mut self, valid: &[&str]) -> Option<Symbol> {
+        if !is_id_start(self.first()) {
+            return None;
+        }
+        let lo = self.hi;
+        let suffix = self.eat_while(is_id_continue);
+        if !valid.contains(&suffix.as_str()) {
+            self.error_at(LexErrorKind::InvalidSuffix(suffix.clone()), Span::new(lo, self.hi));
+        }
+        Some(Symbol::intern(&suffix))
+    }
+
+    /// Lexes a line comment after the first `/`. Doc comments (`///` and
+    /// `//!`) become tokens, other comments are skipped and give `None`.
+    fn line_comment(&mut self) -> Option<Token> {
+        self.bump();
+        let style = match (self.first(), self.second()) {
+            ('/', c) if c != '/' => Some(DocStyle::Outer),
+            ('!', _) => Some(DocStyle::Inner),
+            _ => None,
+        };
+        if style.is_some() {
+            self.bump();
+        }
+
+        let text = self.eat_while(|c| !is_line_break(c));
+        match style {
+            Some(style) => {
+                Some(self.push_token(TokenKind::DocComment(style, Symbol::intern(&text))))
+            }
+            None => {
+                self.skip(TriviaKind::LineComment);
+                None
+            }
+        }
+    }
+
+    /// Lexes a block comment after the first `/`. Block comments nest, and
+    /// `/** */` and `/*! */` are doc comments, other comments are skipped and
+    /// give `None`.
+    fn block_comment(&mut self) -> Option<Token> {
+        self.bump();
+        let style = match (self.first(), self.second()) {
+            ('*', c) if c != '*' && c != '/' => Some(DocStyle::Outer),
+            ('!', _) => Some(DocStyle::Inner),
+            _ => None,
+        };
+        if style.is_some() {
+            self.bump();
+        }
+
+        let mut text = String::new();
+        let mut depth = 1;
+        loop {
+            match self.bump() {
+                EOF_CHAR => {
+                    self.error(LexErrorKind::UnterminatedBlockComment);
+                    break;
+                }
+                '/' if self.first() == '*' => {
+                    self.bump();
+                    text.push_str("/*");
+                    depth += 1;
+                }
+                '*' if self.first() == '/' => {
+                    self.bump();
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                    text.push_str("*/");
+                }
+                c => text.push(c),
+            }
+        }
+
+        // A comment spanning several lines separates tokens like a line break.
+        let newline = text.contains('\n');
+        match style {
+            Some(style) => {
+                let tok = self.push_token(TokenKind::DocComment(style, Symbol::intern(&text)));
+                self.newline |= newline;
+                Some(tok)
+            }
+            None => {
+                self.newline |= newline;
+                self.skip(TriviaKind::BlockComment);
+                None
+            }
+        }
+    }
+
+    /// Skips the text from the start of the current token, recording it as
+    /// trivia in lossless mode.
+    fn skip(&mut self, kind: TriviaKind) {
+        let span = self.mk_span();
+        if self.lossless {
+            self.trivia.push(Trivia { kind, span });
+        }
+    }
+
+    /// Creates a token ending at the current position, carrying the trivia
+    /// recorded since the last token.
+    fn push_token(&mut self, kind: TokenKind) -> Token {
+        let mut tok = Token::new(kind, self.mk_span());
+        tok.trivia = mem::take(&mut self.trivia);
+        tok.newline_before = mem::take(&mut self.newline);
+        tok
+    }
+
+    /// Gets the next token, an EOF token at the end of the file. Whitespace
+    /// and comments before it are skipped in a loop, so that long runs of
+    /// them do not grow the stack.
     fn next_token(&mut self) -> Token {
+        loop {
+            if let Some(tok) = self.token_or_trivia() {
+                return tok;
+            }
+        }
+    }
+
+    /// Lexes a token, or skips whitespace or a comment and returns `None`.
+    fn token_or_trivia(&mut self) -> Option<Token> {
         let c = match self.bump() {
-            EOF_CHAR => return self.eof.clone(),
+            EOF_CHAR => return Some(self.push_token(TokenKind::Eof)),
             c => c,
         };
 
@@ -113,122 +620,304 @@ impl<'a> Lexer<'a> {
                     self.bump();
                     self.bump();
                     TokenKind::Ellipsis
+                } else if self.first() == '.' && self.second() == '=' {
+                    self.bump();
+                    self.bump();
+                    TokenKind::DotDotEqual
+                } else if self.eat('.') {
+                    TokenKind::DotDot
                 } else {
                     TokenKind::Dot
                 }
             }
             '-' => {
-                if self.first() == '>' {
-                    self.bump();
+                if self.eat('>') {
                     TokenKind::Arrow
+                } else if self.eat('=') {
+                    TokenKind::MinusEqual
                 } else {
                     TokenKind::Minus
                 }
             }
-            '+' => TokenKind::Plus,
+            '+' => {
+                if self.eat('=') {
+                    TokenKind::PlusEqual
+                } else {
+                    TokenKind::Plus
+                }
+            }
             ';' => TokenKind::Semicolon,
-            '*' => TokenKind::Star,
-            '/' => TokenKind::Slash,
-            '%' => TokenKind::Percent,
-            '^' => TokenKind::Caret,
-            ':' => TokenKind::Colon,
+            '*' => {
+                if self.eat('=') {
+                    TokenKind::StarEqual
+                } else {
+                    TokenKind::Star
+                }
+            }
+            '/' => match self.first() {
+                '/' => return self.line_comment(),
+                '*' => return self.block_comment(),
+                '=' => {
+                    self.bump();
+                    TokenKind::SlashEqual
+                }
+                _ => TokenKind::Slash,
+            },
+            '%' => {
+                if self.eat('=') {
+                    TokenKind::PercentEqual
+                } else {
+                    TokenKind::Percent
+                }
+            }
+            '^' => {
+                if self.eat('=') {
+                    TokenKind::CaretEqual
+                } else {
+                    TokenKind::Caret
+                }
+            }
+            '~' => TokenKind::Tilde,
+            '&' => {
+                if self.eat('&') {
+                    TokenKind::AmpAmp
+                } else if self.eat('=') {
+                    TokenKind::AmpEqual
+                } else {
+                    TokenKind::Amp
+                }
+            }
+            '|' => {
+                if self.eat('|') {
+                    TokenKind::PipePipe
+                } else if self.eat('=') {
+                    TokenKind::PipeEqual
+                } else {
+                    TokenKind::Pipe
+                }
+            }
+            ':' => {
+                if self.eat(':') {
+                    TokenKind::ColonColon
+                } else {
+                    TokenKind::Colon
+                }
+            }
             '?' => TokenKind::Question,
             '!' => {
-                if self.first() == '=' {
-                    self.bump();
+                if self.eat('=') {
                     TokenKind::BangEqual
                 } else {
                     TokenKind::Bang
                 }
             }
             '=' => {
-                if self.first() == '=' {
-                    self.bump();
+                if self.eat('=') {
                     TokenKind::EqualEqual
+                } else if self.eat('>') {
+                    TokenKind::FatArrow
                 } else {
                     TokenKind::Equal
                 }
             }
             '>' => {
-                if self.first() == '>' {
+                if self.first() == '>' && self.second() == '=' {
                     self.bump();
-                    TokenKind::ShiftRight
-                } else if self.first() == '=' {
                     self.bump();
+                    TokenKind::ShiftRightEqual
+                } else if self.eat('>') {
+                    TokenKind::ShiftRight
+                } else if self.eat('=') {
                     TokenKind::GreaterEqual
                 } else {
                     TokenKind::Greater
                 }
             }
             '<' => {
-                if self.first() == '<' {
+                if self.first() == '<' && self.second() == '=' {
                     self.bump();
-                    TokenKind::ShiftLeft
-                } else if self.first() == '=' {
                     self.bump();
+                    TokenKind::ShiftLeftEqual
+                } else if self.eat('<') {
+                    TokenKind::ShiftLeft
+                } else if self.eat('=') {
                     TokenKind::LessEqual
                 } else {
                     TokenKind::Less
                 }
             }
             '@' => TokenKind::At,
+            c if is_line_break(c) => {
+                if c == '\r' {
+                    self.eat('\n');
+                }
+                self.newline = true;
+                self.skip(TriviaKind::Newline);
+                return None;
+            }
             c if is_whitespace(c) => {
-                self.eat_while(|c| is_whitespace(c));
-                return self.next_token();
+                self.eat_while(|c| is_whitespace(c) && !is_line_break(c));
+                self.skip(TriviaKind::Whitespace);
+                return None;
             }
-            c if is_digit(c) => {
-                let s = self.eat_while(|c| is_digit(c));
-                TokenKind::Number(format!("{}{}", c, s))
+            c if is_digit(c) => self.number(c),
+            'r' if self.is_raw_string_start() => self.raw_string(),
+            'b' if self.first() == '"' => {
+                self.bump();
+                TokenKind::ByteString(self.quoted('"', true).into_iter().map(|c| c as u8).collect())
             }
+            'c' if self.first() == '"' => self.c_string(),
             c if is_id_start(c) => {
-                let s = self.eat_while(|c| is_id_continue(c));
-                let s = format!("{}{}", c, s);
-                match s.as_str() {
-                    "module" => TokenKind::Module,
-                    "import" => TokenKind::Import,
-                    "extern" => TokenKind::Extern,
-                    "pub" => TokenKind::Pub,
-                    "fn" => TokenKind::Fn,
-                    "let" => TokenKind::Let,
-                    "const" => TokenKind::Const,
-                    "mut" => TokenKind::Mut,
-                    "if" => TokenKind::If,
-                    "else" => TokenKind::Else,
-                    "return" => TokenKind::Return,
-                    "true" => TokenKind::True,
-                    "false" => TokenKind::False,
-                    "as" => TokenKind::As,
-                    _ => TokenKind::Identifier(s),
+                let s = self.eat_while(is_id_continue);
+                let mut s = format!("{}{}", c, s);
+                if !s.is_ascii() {
+                    // Identifiers are compared after NFC normalization, so
+                    // `é` is the same whether it is written precomposed or not.
+                    s = s.nfc().collect();
+                }
+                let name = Symbol::intern(&s);
+                if !s.is_ascii() && !s.as_str().is_single_script() {
+                    self.error(LexErrorKind::MixedScript(name));
+                }
+                match TokenKind::keyword(name) {
+                    Some(kind) => kind,
+                    None => {
+                        let span = Span::new(self.lo, self.hi);
+                        if let Some(kind) = self.confusables.check(name, span) {
+                            self.error(kind);
+                        }
+                        TokenKind::Identifier(name)
+                    }
                 }
             }
             '"' => {
-                let s = self.eat_while(|c| c != '"');
-                self.bump();
-                TokenKind::String(s)
+                let s: String = self.quoted('"', false).into_iter().collect();
+                TokenKind::String(Symbol::intern(&s))
+            }
+            '\'' => self.char_literal(),
+            c => {
+                self.error(LexErrorKind::UnknownChar(c));
+                TokenKind::Unknown
             }
-            _ => TokenKind::Unknown,
         };
 
-        let tok = Token::new(kind, self.mk_span());
-        self.tokens.push(tok.clone());
-        tok
+        Some(self.push_token(kind))
+    }
+
+    /// Moves the lexer to the global byte offset `pos`, which must be
+    /// between two tokens.
+    fn seek(&mut self, pos: usize) {
+        self.chars = self.source.content[pos - self.source.start..].chars();
+        self.lo = pos;
+        self.hi = pos;
+    }
+
+    /// Returns the errors found so far and clears them, to report errors
+    /// while iterating over the tokens.
+    pub fn take_errors(&mut self) -> Vec<LexError> {
+        mem::take(&mut self.errors)
     }
 
     /// Lexes the source file.
-    pub fn lex(&mut self) -> Vec<Token> {
-        loop {
-            let tok = self.next_token();
-            if tok.kind == TokenKind::Eof {
+    ///
+    /// Lexing never stops at an error: bad input is reported in the returned
+    /// errors and turned into a token so the caller can decide whether to
+    /// abort or keep going.
+    pub fn lex(mut self) -> (Vec<Token>, Vec<LexError>) {
+        let tokens = self.by_ref().collect();
+        (tokens, self.errors)
+    }
+
+    /// Lexes the source file again after `edit`, given the tokens lexed from
+    /// the file before the edit, with the same mode.
+    ///
+    /// Tokens ending before the line of the edit are reused as is. A token
+    /// closer to the edit may change with it, not only when it touches the
+    /// edit, since e.g. `ab` may become `abc`, but also when the lexer looked
+    /// past its end into the edit: inserting `5` in `1. y` turns `1` and `.`
+    /// into `1.5`. That lookahead is two characters, more for `r#"`, and never
+    /// crosses a line break. Lexing stops as soon as a token after the edit
+    /// lines up with an old token; the remaining old tokens are reused with
+    /// their spans shifted. The returned errors only cover the text lexed
+    /// again.
+    pub fn relex(mut self, tokens: &[Token], edit: Edit) -> (Vec<Token>, Vec<LexError>) {
+        let before = &self.source.content[..edit.lo - self.source.start];
+        let line = before
+            .char_indices()
+            .rev()
+            .find(|(_, c)| is_line_break(*c))
+            .map_or(0, |(i, c)| i + c.len_utf8());
+        let line = self.source.start + line;
+        let first = tokens.partition_point(|tok| tok.span.hi < line);
+        let mut new = tokens[..first].to_vec();
+        if let Some(prev) = new.last() {
+            self.seek(prev.span.hi);
+        }
+
+        let mut old = first;
+        for tok in self.by_ref() {
+            if tok.span.lo < edit.new_hi {
+                new.push(tok);
+                continue;
+            }
+
+            // Old tokens after the edit line up if they start at the same
+            // place in the new text.
+            while old < tokens.len()
+                && (tokens[old].span.lo < edit.old_hi
+                    || edit.shift(tokens[old].span.lo) < tok.span.lo)
+            {
+                old += 1;
+            }
+            let synced = tokens.get(old).is_some_and(|old| {
+                edit.shift(old.span.lo) == tok.span.lo
+                    && old.kind == tok.kind
+                    && old.newline_before == tok.newline_before
+            });
+            new.push(tok);
+            if synced {
+                new.extend(tokens[old + 1..].iter().map(|tok| shift_token(tok, edit)));
                 break;
             }
+        }
+
+        (new, self.errors)
+    }
+}
+
+/// Returns a copy of a token following an edit, moved to its place in the
+/// edited file.
+fn shift_token(tok: &Token, edit: Edit) -> Token {
+    let shift = |span: Span| Span::new(edit.shift(span.lo), edit.shift(span.hi));
+    let mut tok = tok.clone();
+    tok.span = shift(tok.span);
+    for trivia in &mut tok.trivia {
+        trivia.span = shift(trivia.span);
+    }
+    tok
+}
+
+impl Iterator for Lexer<'_> {
+    type Item = Token;
 
-            if tok.kind == TokenKind::Unknown {
-                let str = self.source.content[tok.span.lo..tok.span.hi].to_string();
-                println!("Unknown token: {}", str);
-                exit(1);
+    fn next(&mut self) -> Option<Token> {
+        if self.done {
+            return None;
+        }
+        if self.hi == self.source.start && self.eat(BOM) {
+            let span = self.mk_span();
+            if self.lossless {
+                self.trivia.push(Trivia { kind: TriviaKind::Whitespace, span });
             }
         }
 
-        self.tokens.clone()
+        let tok = self.next_token();
+        if tok.kind == TokenKind::Eof {
+            self.done = true;
+            if !self.lossless {
+                return None;
+            }
+        }
+        Some(tok)
     }
 }
@@ -1,19 +1,47 @@
-use crate::span::Span;
+use crate::{
+    span::Span,
+    symbol::{Symbol, kw},
+};
 
 /// Token holds the kind of token and the span of the token in the source file.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Token {
     pub kind: TokenKind,
     pub span: Span,
+    /// Whitespace and comments preceding the token, only recorded by a
+    /// lossless lexer.
+    pub trivia: Vec<Trivia>,
+    /// Whether a line break comes between the previous token and this one.
+    pub newline_before: bool,
 }
 
 impl Token {
     /// Creates a new token.
     pub fn new(kind: TokenKind, span: Span) -> Token {
-        Token { kind, span }
+        Token { kind, span, trivia: Vec::new(), newline_before: false }
     }
 }
 
+/// Source text skipped by the parser, i.e. whitespace and plain comments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trivia {
+    pub kind: TriviaKind,
+    pub span: Span,
+}
+
+/// Kind of trivia.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriviaKind {
+    /// A run of whitespace on a single line.
+    Whitespace,
+    /// A line break.
+    Newline,
+    /// `// comment`, without the line break.
+    LineComment,
+    /// `/* comment */`
+    BlockComment,
+}
+
 /// TokenKind is the kind of token.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum TokenKind {
@@ -36,6 +64,9 @@ pub enum TokenKind {
     Percent,
     Caret,
     At,
+    Tilde,
+    Amp,
+    Pipe,
 
     // One or two character tokens
     Bang,
@@ -49,12 +80,34 @@ pub enum TokenKind {
     LessEqual,
     ShiftLeft,
     Arrow,
+    FatArrow,
+    ColonColon,
+    DotDot,
+    DotDotEqual,
     Ellipsis,
+    AmpAmp,
+    PipePipe,
+
+    // Compound assignment
+    PlusEqual,
+    MinusEqual,
+    StarEqual,
+    SlashEqual,
+    PercentEqual,
+    CaretEqual,
+    AmpEqual,
+    PipeEqual,
+    ShiftLeftEqual,
+    ShiftRightEqual,
 
     // Literals
-    Identifier(String),
-    String(String),
-    Number(String),
+    Identifier(Symbol),
+    String(Symbol),
+    Char(char),
+    ByteString(Vec<u8>),
+    CString(Vec<u8>),
+    Integer(IntLit),
+    Float(FloatLit),
 
     // Keywords
     Module,
@@ -71,9 +124,236 @@ pub enum TokenKind {
     True,
     False,
     As,
+    While,
+    For,
+    In,
+    Loop,
+    Break,
+    Continue,
+    Struct,
+    Enum,
+    Union,
+    Match,
+    Type,
+    Impl,
+    Trait,
+    SelfValue,
+    SelfType,
+    Null,
+    Defer,
+    Sizeof,
+
+    // Doc comments, e.g. `/// text` or `//! text`
+    DocComment(DocStyle, Symbol),
 
     Unknown,
 
     // EOF
     Eof,
 }
+
+/// Base of an integer literal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Base {
+    Binary = 2,
+    Octal = 8,
+    Decimal = 10,
+    Hexadecimal = 16,
+}
+
+/// IntLit is an integer literal, e.g. `42`, `0xff_ff` or `10u8`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntLit {
+    /// Digits of the literal without base prefix, separators and suffix.
+    pub digits: Symbol,
+    /// Base of the literal.
+    pub base: Base,
+    /// Type suffix, e.g. `u8`.
+    pub suffix: Option<Symbol>,
+}
+
+impl IntLit {
+    /// Returns the value of the literal, `None` if it does not fit in a
+    /// `u128`.
+    pub fn value(&self) -> Option<u128> {
+        u128::from_str_radix(self.digits.as_str(), self.base as u32).ok()
+    }
+}
+
+/// FloatLit is a decimal floating point literal, e.g. `2.5`, `1e-9` or
+/// `2.5f32`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FloatLit {
+    /// Digits of the literal without separators and suffix.
+    pub digits: Symbol,
+    /// Type suffix, e.g. `f32`.
+    pub suffix: Option<Symbol>,
+}
+
+impl FloatLit {
+    /// Returns the value of the literal.
+    pub fn value(&self) -> f64 {
+        self.digits.as_str().parse().unwrap_or(f64::INFINITY)
+    }
+}
+
+/// Suffixes accepted on integer literals.
+pub const INT_SUFFIXES: &[&str] =
+    &["i8", "i16", "i32", "i64", "isize", "u8", "u16", "u32", "u64", "usize"];
+
+/// Suffixes accepted on float literals.
+pub const FLOAT_SUFFIXES: &[&str] = &["f32", "f64"];
+
+/// DocStyle tells which item a doc comment documents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocStyle {
+    /// `///` or `/** */`, documents the following item.
+    Outer,
+    /// `//!` or `/*! */`, documents the enclosing module.
+    Inner,
+}
+
+impl TokenKind {
+    /// Returns the keyword token for the symbol, `None` if it is not a
+    /// keyword.
+    pub fn keyword(sym: Symbol) -> Option<TokenKind> {
+        if !sym.is_keyword() {
+            return None;
+        }
+        let kind = match sym {
+            kw::Module => TokenKind::Module,
+            kw::Import => TokenKind::Import,
+            kw::Extern => TokenKind::Extern,
+            kw::Pub => TokenKind::Pub,
+            kw::Fn => TokenKind::Fn,
+            kw::Let => TokenKind::Let,
+            kw::Const => TokenKind::Const,
+            kw::Mut => TokenKind::Mut,
+            kw::If => TokenKind::If,
+            kw::Else => TokenKind::Else,
+            kw::Return => TokenKind::Return,
+            kw::True => TokenKind::True,
+            kw::False => TokenKind::False,
+            kw::As => TokenKind::As,
+            kw::While => TokenKind::While,
+            kw::For => TokenKind::For,
+            kw::In => TokenKind::In,
+            kw::Loop => TokenKind::Loop,
+            kw::Break => TokenKind::Break,
+            kw::Continue => TokenKind::Continue,
+            kw::Struct => TokenKind::Struct,
+            kw::Enum => TokenKind::Enum,
+            kw::Union => TokenKind::Union,
+            kw::Match => TokenKind::Match,
+            kw::Type => TokenKind::Type,
+            kw::Impl => TokenKind::Impl,
+            kw::Trait => TokenKind::Trait,
+            kw::SelfValue => TokenKind::SelfValue,
+            kw::SelfType => TokenKind::SelfType,
+            kw::Null => TokenKind::Null,
+            kw::Defer => TokenKind::Defer,
+            kw::Sizeof => TokenKind::Sizeof,
+            _ => return None,
+        };
+        Some(kind)
+    }
+
+    /// Returns a human readable description of the token kind, used in
+    /// error messages.
+    pub fn describe(&self) -> String {
+        let s = match self {
+            TokenKind::LeftParen => "(",
+            TokenKind::RightParen => ")",
+            TokenKind::LeftBrace => "{",
+            TokenKind::RightBrace => "}",
+            TokenKind::LeftBracket => "[",
+            TokenKind::RightBracket => "]",
+            TokenKind::Comma => ",",
+            TokenKind::Dot => ".",
+            TokenKind::Minus => "-",
+            TokenKind::Plus => "+",
+            TokenKind::Semicolon => ";",
+            TokenKind::Slash => "/",
+            TokenKind::Star => "*",
+            TokenKind::Colon => ":",
+            TokenKind::Question => "?",
+            TokenKind::Percent => "%",
+            TokenKind::Caret => "^",
+            TokenKind::At => "@",
+            TokenKind::Tilde => "~",
+            TokenKind::Amp => "&",
+            TokenKind::Pipe => "|",
+            TokenKind::Bang => "!",
+            TokenKind::BangEqual => "!=",
+            TokenKind::Equal => "=",
+            TokenKind::EqualEqual => "==",
+            TokenKind::Greater => ">",
+            TokenKind::GreaterEqual => ">=",
+            TokenKind::ShiftRight => ">>",
+            TokenKind::Less => "<",
+            TokenKind::LessEqual => "<=",
+            TokenKind::ShiftLeft => "<<",
+            TokenKind::Arrow => "->",
+            TokenKind::FatArrow => "=>",
+            TokenKind::ColonColon => "::",
+            TokenKind::DotDot => "..",
+            TokenKind::DotDotEqual => "..=",
+            TokenKind::Ellipsis => "...",
+            TokenKind::AmpAmp => "&&",
+            TokenKind::PipePipe => "||",
+            TokenKind::PlusEqual => "+=",
+            TokenKind::MinusEqual => "-=",
+            TokenKind::StarEqual => "*=",
+            TokenKind::SlashEqual => "/=",
+            TokenKind::PercentEqual => "%=",
+            TokenKind::CaretEqual => "^=",
+            TokenKind::AmpEqual => "&=",
+            TokenKind::PipeEqual => "|=",
+            TokenKind::ShiftLeftEqual => "<<=",
+            TokenKind::ShiftRightEqual => ">>=",
+            TokenKind::Identifier(name) => return format!("identifier `{}`", name),
+            TokenKind::String(_) => return "string literal".to_string(),
+            TokenKind::Char(_) => return "character literal".to_string(),
+            TokenKind::ByteString(_) => return "byte string literal".to_string(),
+            TokenKind::CString(_) => return "C string literal".to_string(),
+            TokenKind::Integer(_) => return "integer literal".to_string(),
+            TokenKind::Float(_) => return "float literal".to_string(),
+            TokenKind::Module => "module",
+            TokenKind::Import => "import",
+            TokenKind::Extern => "extern",
+            TokenKind::Pub => "pub",
+            TokenKind::Fn => "fn",
+            TokenKind::Let => "let",
+            TokenKind::Const => "const",
+            TokenKind::Mut => "mut",
+            TokenKind::If => "if",
+            TokenKind::Else => "else",
+            TokenKind::Return => "return",
+            TokenKind::True => "true",
+            TokenKind::False => "false",
+            TokenKind::As => "as",
+            TokenKind::While => "while",
+            TokenKind::For => "for",
+            TokenKind::In => "in",
+            TokenKind::Loop => "loop",
+            TokenKind::Break => "break",
+            TokenKind::Continue => "continue",
+            TokenKind::Struct => "struct",
+            TokenKind::Enum => "enum",
+            TokenKind::Union => "union",
+            TokenKind::Match => "match",
+            TokenKind::Type => "type",
+            TokenKind::Impl => "impl",
+            TokenKind::Trait => "trait",
+            TokenKind::SelfValue => "self",
+            TokenKind::SelfType => "Self",
+            TokenKind::Null => "null",
+            TokenKind::Defer => "defer",
+            TokenKind::Sizeof => "sizeof",
+            TokenKind::DocComment(..) => return "doc comment".to_string(),
+            TokenKind::Unknown => return "unknown token".to_string(),
+            TokenKind::Eof => return "end of file".to_string(),
+        };
+        format!("`{}`", s)
+    }
+}
@@ -1,5 +1,13 @@
 pub mod ast;
+pub mod attr;
 pub mod cli;
+pub mod cst;
+pub mod diag;
+pub mod dump;
 pub mod lexer;
+pub mod loader;
 pub mod parser;
+pub mod resolve;
 pub mod span;
+pub mod symbol;
+pub mod typeck;
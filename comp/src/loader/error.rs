@@ -0,0 +1,116 @@
+use crate::{
+    diag::Diagnostic,
+    span::{Span, error::LoadError},
+    symbol::Symbol,
+};
+use std::fmt;
+
+/// ImportError is an import that cannot be loaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportError {
+    pub kind: ImportErrorKind,
+    /// Span of the import path.
+    pub span: Span,
+}
+
+impl ImportError {
+    /// Creates a new import error.
+    pub fn new(kind: ImportErrorKind, span: Span) -> ImportError {
+        ImportError { kind, span }
+    }
+
+    /// Converts the error into a diagnostic.
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let (code, label) = match &self.kind {
+            // Read errors keep the code of the underlying load error.
+            ImportErrorKind::Load(err) => {
+                return err.to_diagnostic().primary(self.span, "imported here");
+            }
+            ImportErrorKind::NotFound { .. } => ("E0300", "module not found"),
+            ImportErrorKind::NameMismatch { .. } => ("E0301", "imported here"),
+            ImportErrorKind::Cycle(_) => ("E0302", "closes the cycle"),
+            ImportErrorKind::Duplicate { .. } => ("E0303", "imported again here"),
+        };
+
+        let diag = Diagnostic::error(self.kind.to_string()).code(code).primary(self.span, label);
+        match &self.kind {
+            ImportErrorKind::NotFound { tried, .. } => {
+                let tried: Vec<_> = tried.iter().map(|path| format!("`{}`", path)).collect();
+                diag.note(format!("tried {}", tried.join(", ")))
+            }
+            ImportErrorKind::Load(_) => diag,
+            ImportErrorKind::NameMismatch { declared, expected, decl_span } => diag
+                .secondary(*decl_span, format!("declared as `{}`", declared))
+                .suggest("rename the module after its file", *decl_span, expected.to_string()),
+            ImportErrorKind::Cycle(trace) => {
+                let mut diag = diag;
+                for step in &trace[..trace.len() - 1] {
+                    let label = format!("`{}` imports `{}`", step.importer, step.imported);
+                    diag = diag.secondary(step.span, label);
+                }
+                let mut names: Vec<_> = trace.iter().map(|step| step.importer.as_str()).collect();
+                names.push(trace[0].importer.as_str());
+                diag.note(format!("cycle: {}", names.join(" -> ")))
+            }
+            ImportErrorKind::Duplicate { first, .. } => {
+                diag.secondary(*first, "first imported here")
+            }
+        }
+    }
+}
+
+/// ImportErrorKind is the kind of import error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportErrorKind {
+    /// No file exists for the import path, after trying the given paths.
+    NotFound { path: Symbol, tried: Vec<String> },
+    /// The imported file exists but cannot be read.
+    Load(LoadError),
+    /// The `module` declaration of the imported file does not match the
+    /// import path.
+    NameMismatch { declared: Symbol, expected: Symbol, decl_span: Span },
+    /// The import closes a cycle. The steps go around the cycle, the last
+    /// one is the import closing it.
+    Cycle(Vec<CycleStep>),
+    /// A module name imported twice into the same module.
+    Duplicate { name: Symbol, first: Span },
+}
+
+/// One import of an import cycle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CycleStep {
+    /// Name of the importing module.
+    pub importer: Symbol,
+    /// Name of the imported module.
+    pub imported: Symbol,
+    /// Span of the import path.
+    pub span: Span,
+}
+
+impl fmt::Display for ImportErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportErrorKind::NotFound { path, .. } => {
+                write!(f, "cannot find module `{}`", path)
+            }
+            ImportErrorKind::Load(err) => write!(f, "{}", err),
+            ImportErrorKind::NameMismatch { declared, expected, .. } => write!(
+                f,
+                "module `{}` is declared as `{}`, expected `module {}`",
+                expected, declared, expected
+            ),
+            ImportErrorKind::Cycle(trace) if trace.len() == 1 => {
+                write!(f, "module `{}` imports itself", trace[0].importer)
+            }
+            ImportErrorKind::Cycle(trace) => {
+                let names: Vec<_> =
+                    trace.iter().map(|step| format!("`{}`", step.importer)).collect();
+                let (last, rest) = names.split_last().unwrap();
+                write!(f, "import cycle between {} and {}", rest.join(", "), last)
+            }
+            ImportErrorKind::Duplicate { name, .. } => {
+                write!(f, "module `{}` is imported more than once", name)
+            }
+        }
+    }
+}
@@ -0,0 +1,391 @@
+//! Module loader building the module graph of a program.
+//!
+//! Starting from the root file, every `import "path"` is resolved to a file,
+//! which is lexed and parsed once however many modules import it:
+//!
+//! - `import "std/io"` loads `io.dal` from the standard library directory,
+//! - any other path is relative to the directory of the importing file, so
+//!   `import "math"` in `src/main.dal` loads `src/math.dal`.
+//!
+//! An imported module is bound in the importing module under the last segment
+//! of the import path, e.g. `io` for `"std/io"`, and its `module` declaration,
+//! if any, must use that name. Import cycles are errors.
+//!
+//! The attributes of every module are checked as it is parsed and items
+//! disabled by `@cfg` are removed before its imports are followed, so a
+//! disabled import is never loaded.
+
+use self::error::{CycleStep, ImportError, ImportErrorKind};
+use crate::{
+    ast::{self, ItemKind},
+    attr::{self, CfgSet},
+    diag::Diagnostic,
+    lexer::Lexer,
+    parser::Parser,
+    span::{SourceFile, SourceMap, Span},
+    symbol::{Symbol, sym},
+};
+use std::{
+    collections::{HashMap, HashSet},
+    env, fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+pub mod error;
+
+/// Extension of source files.
+const EXTENSION: &str = ".dal";
+
+/// Environment variable overriding the standard library directory.
+pub const STD_DIR_VAR: &str = "DAL_STD_DIR";
+
+/// Returns the standard library directory: `$DAL_STD_DIR` if it is set, the
+/// `std` directory of the source tree the compiler was built from otherwise.
+pub fn default_std_dir() -> PathBuf {
+    match env::var_os(STD_DIR_VAR) {
+        Some(dir) => PathBuf::from(dir),
+        None => {
+            let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("..").join("std");
+            fs::canonicalize(&dir).unwrap_or(dir)
+        }
+    }
+}
+
+/// Index of a module in its module graph.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ModuleId(u32);
+
+impl ModuleId {
+    /// Returns the index of the module in `ModuleGraph::modules`.
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// A loaded and parsed module.
+pub struct SourceModule {
+    /// Name of the module, from its `module` declaration or its file name.
+    pub name: Symbol,
+    /// Path of the module, unique in the program unlike its name.
+    pub path: ModulePath,
+    /// The source file of the module.
+    pub file: Rc<SourceFile>,
+    /// The parsed module.
+    pub ast: ast::Module,
+    /// Modules imported by this one, in source order.
+    pub imports: Vec<ModuleImport>,
+}
+
+/// An import resolved to a module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModuleImport {
+    /// Name the module is bound to in the importing module.
+    pub name: Symbol,
+    /// The imported module.
+    pub module: ModuleId,
+    /// Span of the import path.
+    pub span: Span,
+}
+
+/// Where a module is in a program, telling apart modules with the same name.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ModulePath {
+    /// Whether the module is in the standard library.
+    pub std: bool,
+    /// Segments of the path of the module file without its extension,
+    /// relative to the standard library directory for a std module and to
+    /// the directory of the root module otherwise, e.g. `a/math`. A `..`
+    /// segment is a parent directory.
+    pub segments: Vec<Symbol>,
+}
+
+impl ModulePath {
+    /// Returns the path of the module imported by `path` from this module.
+    fn join(&self, path: &str) -> ModulePath {
+        let (std, mut segments, path) = match path.strip_prefix("std/") {
+            Some(rest) => (true, Vec::new(), rest),
+            None => {
+                let mut segments = self.segments.clone();
+                segments.pop();
+                (self.std, segments, path)
+            }
+        };
+        for segment in path.split('/') {
+            match segment {
+                "" | "." => {}
+                ".." if segments.last().is_some_and(|last| *last != sym::dotdot) => {
+                    segments.pop();
+                }
+                segment => segments.push(Symbol::intern(segment)),
+            }
+        }
+        ModulePath { std, segments }
+    }
+}
+
+/// ModuleGraph holds every module of a program and the imports between them.
+#[derive(Default)]
+pub struct ModuleGraph {
+    /// Loaded modules, indexed by `ModuleId`. The root module comes first.
+    modules: Vec<SourceModule>,
+    /// Modules ordered so that every module comes after the modules it
+    /// imports.
+    order: Vec<ModuleId>,
+}
+
+impl ModuleGraph {
+    /// Returns the root module, the one given to the loader, if it loaded.
+    pub fn root(&self) -> Option<ModuleId> {
+        if self.modules.is_empty() { None } else { Some(ModuleId(0)) }
+    }
+
+    /// Returns the module with the given id.
+    pub fn get(&self, id: ModuleId) -> &SourceModule {
+        &self.modules[id.index()]
+    }
+
+    /// Returns the module with the given id, mutably.
+    pub fn get_mut(&mut self, id: ModuleId) -> &mut SourceModule {
+        &mut self.modules[id.index()]
+    }
+
+    /// Returns all modules, indexed by `ModuleId`.
+    pub fn modules(&self) -> &[SourceModule] {
+        &self.modules
+    }
+
+    /// Returns all modules, mutably.
+    pub fn modules_mut(&mut self) -> &mut [SourceModule] {
+        &mut self.modules
+    }
+
+    /// Returns the ids of all modules, every module after the modules it
+    /// imports.
+    pub fn order(&self) -> &[ModuleId] {
+        &self.order
+    }
+}
+
+/// Loader loads a module and everything it imports.
+pub struct Loader<'a> {
+    /// Source map receiving the loaded files.
+    sm: &'a mut SourceMap,
+    /// Standard library directory.
+    std_dir: PathBuf,
+    /// Configuration `@cfg` attributes are evaluated against.
+    cfg: CfgSet,
+    /// The graph being built.
+    graph: ModuleGraph,
+    /// Module of every loaded file, by canonical path.
+    by_path: HashMap<PathBuf, ModuleId>,
+    /// Canonical paths of the files that could not be loaded or parsed, so
+    /// their errors are reported once however many modules import them.
+    failed: HashSet<PathBuf>,
+    /// Modules being loaded, with the import being followed in each, from the
+    /// root to the current module.
+    stack: Vec<(ModuleId, Span)>,
+    /// Errors and warnings found while loading.
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Loader<'a> {
+    /// Creates a loader adding files to the source map, looking for the
+    /// standard library in `std_dir` and keeping items enabled by `cfg`.
+    pub fn new(sm: &'a mut SourceMap, std_dir: PathBuf, cfg: CfgSet) -> Loader<'a> {
+        Loader {
+            sm,
+            std_dir,
+            cfg,
+            graph: ModuleGraph::default(),
+            by_path: HashMap::new(),
+            failed: HashSet::new(),
+            stack: Vec::new(),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// Loads the root file and every module it imports, directly or not.
+    ///
+    /// Loading goes on after errors to report as many as possible. The graph
+    /// holds every module that could be parsed, modules whose imports failed
+    /// simply miss them.
+    pub fn load(mut self, path: &str) -> (ModuleGraph, Vec<Diagnostic>) {
+        let file = match self.sm.load_file(path) {
+            Ok(file) => file,
+            Err(err) => {
+                self.diagnostics.push(err.to_diagnostic());
+                return (self.graph, self.diagnostics);
+            }
+        };
+        let name = Path::new(path)
+            .file_stem()
+            .map_or(sym::main, |stem| Symbol::intern(&stem.to_string_lossy()));
+        let module_path = ModulePath { std: false, segments: vec![name] };
+        if let Some(root) = self.add_module(file, name, module_path, None) {
+            if let Ok(canonical) = fs::canonicalize(path) {
+                self.by_path.insert(canonical, root);
+            }
+            self.visit(root);
+        }
+        (self.graph, self.diagnostics)
+    }
+
+    /// Lexes and parses a file and adds it to the graph. `name` is the name
+    /// the module is expected to have, the name of the root module is only
+    /// a fallback when it has no `module` declaration.
+    fn add_module(
+        &mut self,
+        file: Rc<SourceFile>,
+        name: Symbol,
+        path: ModulePath,
+        import_span: Option<Span>,
+    ) -> Option<ModuleId> {
+        let (tokens, errors) = Lexer::new(&file).lex();
+        let failed = errors.iter().any(|err| !err.is_warning());
+        self.diagnostics.extend(errors.iter().map(|err| err.to_diagnostic()));
+        if failed {
+            return None;
+        }
+        let mut ast = match Parser::new(tokens).parse_module() {
+            Ok(ast) => ast,
+            Err(diag) => {
+                self.diagnostics.push(diag);
+                return None;
+            }
+        };
+        let errors = attr::check_module(&ast);
+        self.diagnostics.extend(errors.iter().map(|err| err.to_diagnostic()));
+        attr::strip_cfg(&mut ast, &self.cfg);
+
+        let name = match (&ast.decl, import_span) {
+            (Some(decl), Some(span)) if decl.name != name => {
+                let kind = ImportErrorKind::NameMismatch {
+                    declared: decl.name,
+                    expected: name,
+                    decl_span: decl.span,
+                };
+                self.diagnostics.push(ImportError::new(kind, span).to_diagnostic());
+                name
+            }
+            (Some(decl), None) => decl.name,
+            _ => name,
+        };
+
+        let id = ModuleId(self.graph.modules.len() as u32);
+        self.graph.modules.push(SourceModule { name, path, file, ast, imports: Vec::new() });
+        Some(id)
+    }
+
+    /// Loads the imports of a module, then the module is complete.
+    fn visit(&mut self, id: ModuleId) {
+        let imports: Vec<_> = (self.graph.get(id).ast.items.iter())
+            .filter_map(|item| match &item.kind {
+                ItemKind::Import(import) => Some((import.path, import.path_span)),
+                _ => None,
+            })
+            .collect();
+
+        for (path, span) in imports {
+            self.stack.push((id, span));
+            let import = self.import(id, path, span);
+            self.stack.pop();
+            let Some(import) = import else {
+                continue;
+            };
+
+            let module = self.graph.get_mut(id);
+            if let Some(first) = module.imports.iter().find(|first| first.name == import.name) {
+                let kind = ImportErrorKind::Duplicate { name: import.name, first: first.span };
+                self.diagnostics.push(ImportError::new(kind, span).to_diagnostic());
+                continue;
+            }
+            module.imports.push(import);
+        }
+        self.graph.order.push(id);
+    }
+
+    /// Resolves and loads the import `path` of module `from`.
+    fn import(&mut self, from: ModuleId, path: Symbol, span: Span) -> Option<ModuleImport> {
+        let name = Symbol::intern(path.as_str().rsplit('/').next().unwrap_or_default());
+        let file = match self.resolve(from, path) {
+            Ok(file) => file,
+            Err(tried) => {
+                let kind = ImportErrorKind::NotFound { path, tried };
+                self.diagnostics.push(ImportError::new(kind, span).to_diagnostic());
+                return None;
+            }
+        };
+        let canonical = fs::canonicalize(&file).unwrap_or_else(|_| file.clone());
+
+        if let Some(&module) = self.by_path.get(&canonical) {
+            if let Some(pos) = self.stack.iter().position(|(id, _)| *id == module) {
+                self.cycle_error(pos, span);
+                return None;
+            }
+            return Some(ModuleImport { name, module, span });
+        }
+        if self.failed.contains(&canonical) {
+            return None;
+        }
+
+        let file = match self.sm.load_file(&file.to_string_lossy()) {
+            Ok(file) => file,
+            Err(err) => {
+                let kind = ImportErrorKind::Load(err);
+                self.diagnostics.push(ImportError::new(kind, span).to_diagnostic());
+                self.failed.insert(canonical);
+                return None;
+            }
+        };
+        let module_path = self.graph.get(from).path.join(path.as_str());
+        let Some(module) = self.add_module(file, name, module_path, Some(span)) else {
+            self.failed.insert(canonical);
+            return None;
+        };
+        self.by_path.insert(canonical, module);
+        self.visit(module);
+        Some(ModuleImport { name, module, span })
+    }
+
+    /// Returns the file an import path of module `from` refers to, or the
+    /// paths tried if there is none.
+    fn resolve(&self, from: ModuleId, path: Symbol) -> Result<PathBuf, Vec<String>> {
+        let path = path.as_str();
+        let file = match path.strip_prefix("std/") {
+            Some(rest) => self.std_dir.join(rest),
+            None => {
+                let importer = Path::new(&self.graph.get(from).file.path);
+                importer.parent().unwrap_or(Path::new("")).join(path)
+            }
+        };
+        let mut file = file.into_os_string();
+        file.push(EXTENSION);
+        let file = PathBuf::from(file);
+        if !path.is_empty() && file.is_file() {
+            Ok(file)
+        } else {
+            Err(vec![file.to_string_lossy().to_string()])
+        }
+    }
+
+    /// Reports the import at `span` closing a cycle back to the module at
+    /// `pos` in the stack.
+    fn cycle_error(&mut self, pos: usize, span: Span) {
+        let cycle = &self.stack[pos..];
+        let trace = cycle
+            .iter()
+            .enumerate()
+            .map(|(i, &(id, import_span))| {
+                let next = cycle.get(i + 1).map_or(cycle[0].0, |(next, _)| *next);
+                CycleStep {
+                    importer: self.graph.get(id).name,
+                    imported: self.graph.get(next).name,
+                    span: import_span,
+                }
+            })
+            .collect();
+        let kind = ImportErrorKind::Cycle(trace);
+        self.diagnostics.push(ImportError::new(kind, span).to_diagnostic());
+    }
+}
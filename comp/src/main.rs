@@ -1,34 +1,142 @@
 use dal::{
+    attr::CfgSet,
     cli::{App, ArgKind, CliContext, Command},
+    diag::{Diagnostic, Emitter, ErrorFormat},
+    dump,
     lexer::Lexer,
-    span::SourceFile,
+    loader::{Loader, default_std_dir},
+    parser::Parser,
+    resolve,
+    span::SourceMap,
+    typeck,
 };
 
-static VERSION: &'static str = env!("CARGO_PKG_VERSION");
+use std::{fs, path::PathBuf};
+
+static VERSION: &str = env!("CARGO_PKG_VERSION");
 
 fn compile_handler(ctx: &CliContext) -> i32 {
-    let output = ctx.get_string("o").unwrap_or("a.out");
+    let output = ctx.get_string("o");
     let emit = ctx.get_string("emit").unwrap_or("exe");
 
+    let error_format = ctx.get_string("error-format").unwrap_or("human");
+    let emitter = match ErrorFormat::from_name(error_format) {
+        Some(format) => Emitter::with_format(format),
+        None => {
+            let diag = Diagnostic::error(format!("unknown error format `{}`", error_format))
+                .help("expected `human` or `json`");
+            Emitter::auto().emit(&diag, None);
+            return 1;
+        }
+    };
+
+    if !EMIT_KINDS.contains(&emit) {
+        let diag = Diagnostic::error(format!("unknown emit type `{}`", emit))
+            .help(format!("expected one of {}", EMIT_KINDS.join(", ")));
+        emitter.emit(&diag, None);
+        return 1;
+    }
+
     let free_len = ctx.get_frees().len();
     if free_len == 0 {
-        println!("No target specified");
+        emitter.emit(&Diagnostic::error("no target specified"), None);
         return 1;
     } else if free_len > 1 {
-        println!("Too many targets specified");
+        emitter.emit(&Diagnostic::error("too many targets specified"), None);
         return 1;
     }
 
     let target = &ctx.get_frees()[0];
 
-    let file = SourceFile::new(target);
+    if matches!(emit, "tokens" | "tokens-json" | "ast" | "ast-json") {
+        return dump_handler(target, emit, output, &emitter);
+    }
+
+    let std_dir = ctx.get_string("std-dir").map_or_else(default_std_dir, PathBuf::from);
+    let mut sm = SourceMap::new();
+    let (graph, diags) = Loader::new(&mut sm, std_dir, CfgSet::host()).load(target);
+    for diag in &diags {
+        emitter.emit(diag, Some(&sm));
+    }
+    if diags.iter().any(Diagnostic::is_error) {
+        return 1;
+    }
 
-    let mut lexer = Lexer::new(&file);
+    let (res, errors) = resolve::resolve(&graph);
+    for err in &errors {
+        emitter.emit(&err.to_diagnostic(), Some(&sm));
+    }
+    if errors.iter().any(|err| !err.is_warning()) {
+        return 1;
+    }
 
-    let tokens = lexer.lex();
-    dbg!(tokens);
+    let (_types, errors) = typeck::check(&graph, &res);
+    for err in &errors {
+        emitter.emit(&err.to_diagnostic(), Some(&sm));
+    }
+    if !errors.is_empty() {
+        return 1;
+    }
 
-    return 0;
+    0
+}
+
+/// Prints the tokens or the AST of a single file.
+fn dump_handler(target: &str, emit: &str, output: Option<&str>, emitter: &Emitter) -> i32 {
+    let mut sm = SourceMap::new();
+    let file = match sm.load_file(target) {
+        Ok(file) => file,
+        Err(err) => {
+            emitter.emit(&err.to_diagnostic(), None);
+            return 1;
+        }
+    };
+
+    let (tokens, errors) = Lexer::new(&file).lex();
+    for err in &errors {
+        emitter.emit(&err.to_diagnostic(), Some(&sm));
+    }
+    // Tokens are dumped even after errors, bad input becomes tokens too.
+    match emit {
+        "tokens" => return write_output(output, &dump::tokens(&tokens, &file), emitter),
+        "tokens-json" => return write_output(output, &dump::tokens_json(&tokens, &file), emitter),
+        _ => {}
+    }
+    if errors.iter().any(|err| !err.is_warning()) {
+        return 1;
+    }
+
+    let module = match Parser::new(tokens).parse_module() {
+        Ok(module) => module,
+        Err(diag) => {
+            emitter.emit(&diag, Some(&sm));
+            return 1;
+        }
+    };
+    let tree = dump::ast::module(&module);
+    match emit {
+        "ast-json" => write_output(output, &(tree.to_json(&file) + "\n"), emitter),
+        _ => write_output(output, &tree.render(&file), emitter),
+    }
+}
+
+/// Output types accepted by `--emit`.
+const EMIT_KINDS: &[&str] =
+    &["exe", "lib", "obj", "asm", "llvm-ir", "wasm", "tokens", "tokens-json", "ast", "ast-json"];
+
+/// Writes a textual output to the file given with `-o`, or to stdout.
+fn write_output(output: Option<&str>, text: &str, emitter: &Emitter) -> i32 {
+    let Some(path) = output else {
+        print!("{}", text);
+        return 0;
+    };
+    match fs::write(path, text) {
+        Ok(()) => 0,
+        Err(err) => {
+            emitter.emit(&Diagnostic::error(format!("cannot write `{}`: {}", path, err)), None);
+            1
+        }
+    }
 }
 
 fn main() {
@@ -44,7 +152,19 @@ fn main() {
         .arg(|arg| {
             arg.name("emit")
                 .desc("Specify output type. default (exe)")
-                .hint("[exe|lib|obj|asm|llvm-ir|wasm]")
+                .hint("[exe|lib|obj|asm|llvm-ir|wasm|tokens|tokens-json|ast|ast-json]")
+                .kind(ArgKind::String)
+        })
+        .arg(|arg| {
+            arg.name("std-dir")
+                .desc("Directory of the standard library. default ($DAL_STD_DIR)")
+                .hint("<dir>")
+                .kind(ArgKind::String)
+        })
+        .arg(|arg| {
+            arg.name("error-format")
+                .desc("Specify how errors are printed. default (human)")
+                .hint("[human|json]")
                 .kind(ArgKind::String)
         })
         .handler(compile_handler)
@@ -0,0 +1,133 @@
+//! Building the lossless concrete syntax tree.
+//!
+//! While parsing, the parser records a flat list of events: node starts,
+//! consumed tokens and node ends. Nodes are usually started once the parser
+//! knows what it parsed, by inserting the start event at a checkpoint taken
+//! before the first token of the node. The events are then replayed into a
+//! green tree, adding the trivia and doc comments the parser skipped.
+
+use super::Parser;
+use crate::{
+    ast::ItemKind,
+    cst::{GreenBuilder, SyntaxKind, SyntaxNode, SyntaxTokenKind},
+    diag::Diagnostic,
+    lexer::token::{Token, TokenKind},
+    span::{SourceFile, Span},
+};
+
+/// An event recorded by the parser.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Event {
+    /// Starts a node.
+    Start(SyntaxKind),
+    /// Consumes the token at the given index, and any doc comment before it.
+    Token(usize),
+    /// Finishes the last started node.
+    Finish,
+}
+
+/// Returns the syntax kind of an item.
+pub(super) fn item_kind(kind: &ItemKind) -> SyntaxKind {
+    match kind {
+        ItemKind::Import(_) => SyntaxKind::Import,
+        ItemKind::Extern(_) => SyntaxKind::ExternBlock,
+        ItemKind::Fn(_) => SyntaxKind::Fn,
+        ItemKind::Struct(_) => SyntaxKind::Struct,
+        ItemKind::Union(_) => SyntaxKind::Union,
+        ItemKind::Enum(_) => SyntaxKind::Enum,
+        ItemKind::TypeAlias(_) => SyntaxKind::TypeAlias,
+        ItemKind::Impl(_) => SyntaxKind::Impl,
+        ItemKind::Trait(_) => SyntaxKind::Trait,
+    }
+}
+
+/// Parses a file into a lossless concrete syntax tree.
+///
+/// The tokens must come from a lossless lexer, then printing the tree gives
+/// back the source file byte for byte. Parse errors do not stop the tree from
+/// being built: the tokens after the error are put in an `Error` node and the
+/// error is returned next to the tree.
+pub fn parse_cst(tokens: Vec<Token>, file: &SourceFile) -> (SyntaxNode, Option<Diagnostic>) {
+    let mut parser = Parser::new(tokens);
+    parser.events = Some(Vec::new());
+    let error = parser.parse_module().err();
+    let events = parser.events.take().unwrap_or_default();
+
+    let mut builder = TreeBuilder {
+        builder: GreenBuilder::new(),
+        tokens: &parser.tokens,
+        file,
+        next: 0,
+        trivia_done: false,
+    };
+    builder.builder.start_node(SyntaxKind::SourceFile);
+    for event in events {
+        match event {
+            Event::Start(kind) => {
+                // Leading trivia goes before the node rather than inside it.
+                builder.trivia();
+                builder.builder.start_node(kind);
+            }
+            Event::Token(index) => builder.tokens_to(index + 1),
+            Event::Finish => builder.builder.finish_node(),
+        }
+    }
+
+    let eof = builder.tokens.len() - 1;
+    if builder.next < eof {
+        builder.builder.start_node(SyntaxKind::Error);
+        builder.tokens_to(eof);
+    }
+    while builder.builder.depth() > 1 {
+        builder.builder.finish_node();
+    }
+    builder.tokens_to(eof + 1);
+
+    (SyntaxNode::new_root(builder.builder.finish(), file.start), error)
+}
+
+/// Replays tokens into a green tree.
+struct TreeBuilder<'a> {
+    /// The green tree being built.
+    builder: GreenBuilder,
+    /// Tokens of the file, ending with an EOF token.
+    tokens: &'a [Token],
+    /// Source file the tokens come from.
+    file: &'a SourceFile,
+    /// Index of the next token to add.
+    next: usize,
+    /// Whether the trivia of the next token was already added.
+    trivia_done: bool,
+}
+
+impl<'a> TreeBuilder<'a> {
+    /// Adds the trivia of the next token.
+    fn trivia(&mut self) {
+        if self.trivia_done || self.next == self.tokens.len() {
+            return;
+        }
+        for trivia in &self.tokens[self.next].trivia {
+            self.builder.token(SyntaxTokenKind::Trivia(trivia.kind), self.text(trivia.span));
+        }
+        self.trivia_done = true;
+    }
+
+    /// Adds the tokens up to `end`, excluded, with their trivia.
+    fn tokens_to(&mut self, end: usize) {
+        while self.next < end {
+            self.trivia();
+            let token = &self.tokens[self.next];
+            if token.kind != TokenKind::Eof {
+                let text = self.text(token.span);
+                self.builder.token(SyntaxTokenKind::Token(token.kind.clone()), text);
+            }
+            self.next += 1;
+            self.trivia_done = false;
+        }
+    }
+
+    /// Returns the source text of a span.
+    fn text(&self, span: Span) -> &'a str {
+        &self.file.content[span.lo - self.file.start..span.hi - self.file.start]
+    }
+}
@@ -0,0 +1,1295 @@
+//! Recursive descent parser turning tokens into an AST.
+//!
+//! Operators, from the tightest binding to the loosest:
+//!
+//! ```text
+//! operators                                      associativity
+//! f(x)  f[T]  a.b                                left
+//! -  !  ~  (unary)                               prefix
+//! as                                             left
+//! *  /  %                                        left
+//! +  -                                           left
+//! <<  >>                                         left
+//! &                                              left
+//! ^                                              left
+//! |                                              left
+//! ==  !=  <  <=  >  >=                           none, `a < b < c` is an error
+//! &&                                             left
+//! ||                                             left
+//! ..  ..=                                        none
+//! =  +=  -=  *=  /=  %=  ^=  &=  |=  <<=  >>=    right
+//! ```
+//!
+//! Binary operators from `*` to `||` are parsed by precedence climbing using
+//! `BinOp::precedence` and `BinOp::assoc`, the other levels have their own
+//! functions.
+//!
+//! Struct literals are not allowed directly in the condition of `if`, `while`,
+//! `for` and `match`, `if p == Point { x } { ... }` would be ambiguous. They
+//! can still be used there inside parentheses.
+//!
+//! # Statement termination
+//!
+//! Statements, items and match arms end at a line break, so semicolons are
+//! only needed to put several of them on one line:
+//!
+//! ```text
+//! let a = 1
+//! let b = 2; let c = 3
+//! ```
+//!
+//! A line break does not end an expression when the line ends with a binary
+//! operator, `=`, `,` or an opening bracket, and line breaks are ignored
+//! inside parentheses and brackets:
+//!
+//! ```text
+//! let sum = a +
+//!     b
+//! foo(a,
+//!     b)
+//! ```
+//!
+//! A line can also start with `.` to continue a chain of field accesses.
+//! A line starting with any other operator or with `(` is an error, as it is
+//! unclear whether it continues the previous line or starts a new statement:
+//! `a\n- b` could be `a - b` or `a` followed by `-b`.
+//!
+//! # Attributes
+//!
+//! Items can be preceded by attributes, written alone or as a bracketed,
+//! comma separated list. Both forms mean the same:
+//!
+//! ```text
+//! attributes = ( "@" attribute | "@" "[" attribute ( "," attribute )* ","? "]" )*
+//! attribute  = ident ( "(" ( expr ( "," expr )* ","? )? ")" )?
+//!
+//! @link("c")
+//! @[link("c"), deprecated("use `puts`")]
+//! ```
+//!
+//! The parser accepts any name and arguments, `attr` checks them against the
+//! built-in attributes.
+
+use crate::{
+    ast::{
+        Arm, Assoc, Attribute, BinOp, Block, EnumDecl, Expr, ExprKind, ExternBlock, FieldDef,
+        FieldInit, FnDecl, Ident, ImplBlock, Import, Item, ItemKind, Lit, Local, LocalKind, Module,
+        Mutability, Param, Pat, PatKind, Path, Stmt, StmtKind, StructDecl, TraitDecl, Ty, TyKind,
+        TypeAlias, UnOp, Variant, Visibility,
+    },
+    cst::SyntaxKind,
+    diag::Diagnostic,
+    lexer::token::{DocStyle, Token, TokenKind},
+    span::Span,
+    symbol::{Symbol, kw, sym},
+};
+
+pub mod cst;
+
+pub type PResult<T> = Result<T, Diagnostic>;
+
+/// Parser for the language.
+pub struct Parser {
+    /// Tokens to parse, always terminated by an EOF token.
+    tokens: Vec<Token>,
+    /// Index of the current token.
+    pos: usize,
+    /// Span of the previously consumed token.
+    prev_span: Span,
+    /// Whether a struct literal is currently not allowed, see the module
+    /// documentation.
+    no_struct_literal: bool,
+    /// Whether a line break currently ends an expression, true in blocks and
+    /// false inside parentheses and brackets.
+    newlines: bool,
+    /// Events building the concrete syntax tree, only recorded when parsing
+    /// with `cst::parse_cst`.
+    events: Option<Vec<cst::Event>>,
+}
+
+/// Where a function is declared, deciding what its signature may contain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FnContext {
+    /// A module level function, it must have a body.
+    Free,
+    /// A function in an `extern` block, it has no body and may be variadic.
+    Extern,
+    /// A method in an `impl` block, it may take `self` and must have a body.
+    Impl,
+    /// A method in a `trait`, it may take `self` and may have a default body.
+    Trait,
+}
+
+impl Parser {
+    /// Creates a new parser from the output of the lexer.
+    pub fn new(mut tokens: Vec<Token>) -> Parser {
+        if tokens.last().is_none_or(|tok| tok.kind != TokenKind::Eof) {
+            let lo = tokens.last().map_or(0, |tok| tok.span.hi);
+            tokens.push(Token::new(TokenKind::Eof, Span::new(lo, lo)));
+        }
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            prev_span: Span::new(0, 0),
+            no_struct_literal: false,
+            newlines: false,
+            events: None,
+        };
+        parser.skip_docs();
+        parser
+    }
+
+    /// Skips doc comments, they are collected by `docs` when an item starts.
+    fn skip_docs(&mut self) {
+        while matches!(self.token().kind, TokenKind::DocComment(..)) {
+            // The line break before a doc comment also separates the tokens
+            // around it.
+            let newline = self.token().newline_before;
+            self.pos += 1;
+            self.tokens[self.pos].newline_before |= newline;
+        }
+    }
+
+    /// Collects the doc comments of the given style directly preceding the
+    /// current token.
+    fn docs(&self, style: DocStyle) -> Vec<Symbol> {
+        let mut docs = Vec::new();
+        for tok in self.tokens[..self.pos].iter().rev() {
+            match &tok.kind {
+                TokenKind::DocComment(s, text) if *s == style => docs.push(*text),
+                TokenKind::DocComment(..) => {}
+                _ => break,
+            }
+        }
+        docs.reverse();
+        docs
+    }
+
+    /// Returns the current token.
+    fn token(&self) -> &Token {
+        &self.tokens[self.pos]
+    }
+
+    /// Checks whether the current token is of the given kind.
+    fn check(&self, kind: &TokenKind) -> bool {
+        &self.token().kind == kind
+    }
+
+    /// Consumes the current token and returns it.
+    fn bump(&mut self) -> Token {
+        let tok = self.token().clone();
+        self.prev_span = tok.span;
+        if tok.kind != TokenKind::Eof {
+            if let Some(events) = &mut self.events {
+                events.push(cst::Event::Token(self.pos));
+            }
+            self.pos += 1;
+            self.skip_docs();
+        }
+        tok
+    }
+
+    /// Returns a checkpoint that a syntax node can later be started at.
+    fn checkpoint(&self) -> usize {
+        self.events.as_ref().map_or(0, Vec::len)
+    }
+
+    /// Wraps everything parsed since the checkpoint in a syntax node.
+    fn wrap(&mut self, checkpoint: usize, kind: SyntaxKind) {
+        if let Some(events) = &mut self.events {
+            events.insert(checkpoint, cst::Event::Start(kind));
+            events.push(cst::Event::Finish);
+        }
+    }
+
+    /// Consumes the current token if it is of the given kind.
+    fn eat(&mut self, kind: &TokenKind) -> bool {
+        if self.check(kind) {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Consumes the current token if it is of the given kind, otherwise
+    /// returns an error.
+    fn expect(&mut self, kind: &TokenKind) -> PResult<Token> {
+        if self.check(kind) {
+            return Ok(self.bump());
+        }
+
+        let diag = self.unexpected(&kind.describe());
+        match kind {
+            TokenKind::RightParen | TokenKind::RightBracket | TokenKind::RightBrace => {
+                let span = Span::new(self.prev_span.hi, self.prev_span.hi);
+                let s = kind.describe();
+                Err(diag.suggest("try adding the closing delimiter", span, s.trim_matches('`')))
+            }
+            _ => Err(diag),
+        }
+    }
+
+    /// Checks whether the current token starts a line where line breaks
+    /// end expressions.
+    fn at_line_start(&self) -> bool {
+        self.newlines && self.token().newline_before
+    }
+
+    /// Creates an error for an operator at the start of a line, which could
+    /// either continue the previous line or start a new statement.
+    fn ambiguous_continuation(&self) -> Diagnostic {
+        let tok = self.token();
+        let s = tok.kind.describe();
+        let op = s.trim_matches('`');
+        // Joining the lines keeps the text after the operator in place.
+        let join = Span::new(self.prev_span.hi, tok.span.hi);
+        let replacement = match tok.kind {
+            TokenKind::LeftParen => op.to_string(),
+            _ => format!(" {}", op),
+        };
+        Diagnostic::error(format!("{} at the start of a line is ambiguous", s))
+            .code("E0107")
+            .primary(tok.span, "this could continue the previous line or start a new statement")
+            .suggest(
+                format!("to continue the previous line, move {} to its end", s),
+                join,
+                replacement,
+            )
+            .help("to start a new statement, end the previous one with `;`")
+    }
+
+    /// Expects the end of a statement or item: a `;`, a line break or the
+    /// closing token of the enclosing list, which is not consumed.
+    fn expect_terminator(&mut self, close: &TokenKind) -> PResult<()> {
+        if self.eat(&TokenKind::Semicolon) || self.check(close) || self.token().newline_before {
+            return Ok(());
+        }
+        let tok = self.token();
+        let end = Span::new(self.prev_span.hi, self.prev_span.hi);
+        Err(Diagnostic::error(format!(
+            "expected `;` or a line break, found {}",
+            tok.kind.describe()
+        ))
+        .code("E0108")
+        .primary(tok.span, "unexpected token")
+        .secondary(self.prev_span, "the previous statement ends here")
+        .suggest("add `;` to put several statements on one line", end, ";"))
+    }
+
+    /// Creates an error for the current token.
+    fn unexpected(&self, expected: &str) -> Diagnostic {
+        let tok = self.token();
+        Diagnostic::error(format!("expected {}, found {}", expected, tok.kind.describe()))
+            .code("E0100")
+            .primary(tok.span, format!("expected {}", expected))
+    }
+
+    /// Parses an identifier.
+    fn parse_ident(&mut self) -> PResult<Ident> {
+        match &self.token().kind {
+            TokenKind::Identifier(name) => {
+                let ident = Ident::new(*name, self.token().span);
+                self.bump();
+                Ok(ident)
+            }
+            _ => Err(self.unexpected("identifier")),
+        }
+    }
+
+    /// Parses a path, e.g. `x`, `self`, `Self` or `Color::Red`.
+    fn parse_path(&mut self) -> PResult<Path> {
+        let cp = self.checkpoint();
+        let mut segments = vec![self.parse_path_segment()?];
+        while self.eat(&TokenKind::ColonColon) {
+            segments.push(self.parse_path_segment()?);
+        }
+        let span = segments[0].span.to(self.prev_span);
+        self.wrap(cp, SyntaxKind::Path);
+        Ok(Path { segments, span })
+    }
+
+    /// Parses a path segment, an identifier, `self` or `Self`.
+    fn parse_path_segment(&mut self) -> PResult<Ident> {
+        let name = match self.token().kind {
+            TokenKind::SelfValue => kw::SelfValue,
+            TokenKind::SelfType => kw::SelfType,
+            _ => return self.parse_ident(),
+        };
+        Ok(Ident::new(name, self.bump().span))
+    }
+
+    /// Parses with line breaks ending expressions or not.
+    fn with_newlines<T, F>(&mut self, significant: bool, f: F) -> PResult<T>
+    where
+        F: FnOnce(&mut Parser) -> PResult<T>,
+    {
+        let old = self.newlines;
+        self.newlines = significant;
+        let res = f(self);
+        self.newlines = old;
+        res
+    }
+
+    /// Parses an expression with struct literals allowed or not.
+    fn with_struct_literal<T, F>(&mut self, allowed: bool, f: F) -> PResult<T>
+    where
+        F: FnOnce(&mut Parser) -> PResult<T>,
+    {
+        let old = self.no_struct_literal;
+        self.no_struct_literal = !allowed;
+        let res = f(self);
+        self.no_struct_literal = old;
+        res
+    }
+
+    /// Parses the condition of `if`, `while`, `for` or `match`.
+    fn parse_cond(&mut self) -> PResult<Expr> {
+        self.with_struct_literal(false, Parser::parse_expr)
+    }
+
+    /// Parses a whole source file.
+    pub fn parse_module(&mut self) -> PResult<Module> {
+        let lo = self.token().span;
+        let docs = self.docs(DocStyle::Inner);
+
+        let cp = self.checkpoint();
+        let decl = if self.eat(&TokenKind::Module) {
+            let name = self.parse_ident()?;
+            self.wrap(cp, SyntaxKind::ModuleDecl);
+            self.expect_terminator(&TokenKind::Eof)?;
+            Some(name)
+        } else {
+            None
+        };
+
+        let mut items = Vec::new();
+        while !self.check(&TokenKind::Eof) {
+            if self.eat(&TokenKind::Semicolon) {
+                continue;
+            }
+            items.push(self.parse_item()?);
+            self.expect_terminator(&TokenKind::Eof)?;
+        }
+
+        Ok(Module { docs, decl, items, span: lo.to(self.token().span) })
+    }
+
+    /// Parses attributes preceding an item.
+    fn parse_attributes(&mut self) -> PResult<Vec<Attribute>> {
+        let mut attrs = Vec::new();
+        while self.check(&TokenKind::At) {
+            let cp = self.checkpoint();
+            let lo = self.bump().span;
+            if self.eat(&TokenKind::LeftBracket) {
+                loop {
+                    let lo = self.token().span;
+                    attrs.push(self.parse_attribute(lo)?);
+                    if !self.eat(&TokenKind::Comma) || self.check(&TokenKind::RightBracket) {
+                        break;
+                    }
+                }
+                self.expect(&TokenKind::RightBracket)?;
+            } else {
+                attrs.push(self.parse_attribute(lo)?);
+            }
+            self.wrap(cp, SyntaxKind::Attribute);
+        }
+        Ok(attrs)
+    }
+
+    /// Parses a single attribute, `name` or `name(args)`, starting at `lo`.
+    fn parse_attribute(&mut self, lo: Span) -> PResult<Attribute> {
+        let name = self.parse_ident()?;
+        let args = if self.eat(&TokenKind::LeftParen) {
+            self.parse_comma_list(&TokenKind::RightParen, Parser::parse_expr)?
+        } else {
+            Vec::new()
+        };
+        Ok(Attribute { name, args, span: lo.to(self.prev_span) })
+    }
+
+    /// Parses a comma separated list until the closing token, consuming it.
+    fn parse_comma_list<T, F>(&mut self, close: &TokenKind, mut f: F) -> PResult<Vec<T>>
+    where
+        F: FnMut(&mut Parser) -> PResult<T>,
+    {
+        let list = self.with_newlines(false, |p| {
+            let mut list = Vec::new();
+            while !p.check(close) {
+                list.push(f(p)?);
+                if !p.eat(&TokenKind::Comma) {
+                    break;
+                }
+            }
+            Ok(list)
+        })?;
+        self.expect(close)?;
+        Ok(list)
+    }
+
+    /// Parses a top level item.
+    fn parse_item(&mut self) -> PResult<Item> {
+        let cp = self.checkpoint();
+        let lo = self.token().span;
+        let docs = self.docs(DocStyle::Outer);
+        let attrs = self.parse_attributes()?;
+        let vis = if self.eat(&TokenKind::Pub) { Visibility::Public } else { Visibility::Private };
+
+        let kind = match self.token().kind {
+            TokenKind::Import => ItemKind::Import(self.parse_import()?),
+            TokenKind::Extern => ItemKind::Extern(self.parse_extern()?),
+            TokenKind::Fn => ItemKind::Fn(self.parse_fn(FnContext::Free)?),
+            TokenKind::Struct => ItemKind::Struct(self.parse_struct(&TokenKind::Struct)?),
+            TokenKind::Union => ItemKind::Union(self.parse_struct(&TokenKind::Union)?),
+            TokenKind::Enum => ItemKind::Enum(self.parse_enum()?),
+            TokenKind::Type => ItemKind::TypeAlias(self.parse_type_alias()?),
+            TokenKind::Impl => ItemKind::Impl(self.parse_impl()?),
+            TokenKind::Trait => ItemKind::Trait(self.parse_trait()?),
+            _ => return Err(self.unexpected("item")),
+        };
+
+        self.wrap(cp, cst::item_kind(&kind));
+        Ok(Item { docs, attrs, vis, kind, span: lo.to(self.prev_span) })
+    }
+
+    /// Parses `import "path"`.
+    fn parse_import(&mut self) -> PResult<Import> {
+        self.expect(&TokenKind::Import)?;
+        match &self.token().kind {
+            TokenKind::String(path) => {
+                let import = Import { path: *path, path_span: self.token().span };
+                self.bump();
+                Ok(import)
+            }
+            _ => Err(self.unexpected("import path")),
+        }
+    }
+
+    /// Parses `extern { ... }`.
+    fn parse_extern(&mut self) -> PResult<ExternBlock> {
+        self.expect(&TokenKind::Extern)?;
+        self.expect(&TokenKind::LeftBrace)?;
+
+        let mut items = Vec::new();
+        while !self.eat(&TokenKind::RightBrace) {
+            if self.eat(&TokenKind::Semicolon) {
+                continue;
+            }
+
+            let cp = self.checkpoint();
+            let lo = self.token().span;
+            let docs = self.docs(DocStyle::Outer);
+            let attrs = self.parse_attributes()?;
+            let vis =
+                if self.eat(&TokenKind::Pub) { Visibility::Public } else { Visibility::Private };
+            if !self.check(&TokenKind::Fn) {
+                return Err(self.unexpected("`fn`"));
+            }
+            let kind = ItemKind::Fn(self.parse_fn(FnContext::Extern)?);
+            self.wrap(cp, SyntaxKind::Fn);
+            items.push(Item { docs, attrs, vis, kind, span: lo.to(self.prev_span) });
+            self.expect_terminator(&TokenKind::RightBrace)?;
+        }
+
+        Ok(ExternBlock { items })
+    }
+
+    /// Parses optional generic parameters, `[T, U]`.
+    fn parse_generics(&mut self) -> PResult<Vec<Ident>> {
+        let cp = self.checkpoint();
+        if self.eat(&TokenKind::LeftBracket) {
+            let generics = self.parse_comma_list(&TokenKind::RightBracket, Parser::parse_ident)?;
+            self.wrap(cp, SyntaxKind::GenericParams);
+            Ok(generics)
+        } else {
+            Ok(Vec::new())
+        }
+    }
+
+    /// Parses `struct Name { fields }` or `union Name { fields }`.
+    fn parse_struct(&mut self, keyword: &TokenKind) -> PResult<StructDecl> {
+        self.expect(keyword)?;
+        let name = self.parse_ident()?;
+        let generics = self.parse_generics()?;
+        self.expect(&TokenKind::LeftBrace)?;
+        let fields = self.parse_comma_list(&TokenKind::RightBrace, |p| {
+            let cp = p.checkpoint();
+            let lo = p.token().span;
+            let vis = if p.eat(&TokenKind::Pub) { Visibility::Public } else { Visibility::Private };
+            let name = p.parse_ident()?;
+            p.expect(&TokenKind::Colon)?;
+            let ty = p.parse_ty()?;
+            p.wrap(cp, SyntaxKind::FieldDef);
+            Ok(FieldDef { vis, name, ty, span: lo.to(p.prev_span) })
+        })?;
+        Ok(StructDecl { name, generics, fields })
+    }
+
+    /// Parses `enum Name { variants }`.
+    fn parse_enum(&mut self) -> PResult<EnumDecl> {
+        self.expect(&TokenKind::Enum)?;
+        let name = self.parse_ident()?;
+        let generics = self.parse_generics()?;
+        self.expect(&TokenKind::LeftBrace)?;
+        let variants = self.parse_comma_list(&TokenKind::RightBrace, |p| {
+            let cp = p.checkpoint();
+            let name = p.parse_ident()?;
+            let fields = if p.eat(&TokenKind::LeftParen) {
+                p.parse_comma_list(&TokenKind::RightParen, Parser::parse_ty)?
+            } else {
+                Vec::new()
+            };
+            let discriminant = if p.eat(&TokenKind::Equal) { Some(p.parse_expr()?) } else { None };
+            p.wrap(cp, SyntaxKind::Variant);
+            Ok(Variant { span: name.span.to(p.prev_span), name, fields, discriminant })
+        })?;
+        Ok(EnumDecl { name, generics, variants })
+    }
+
+    /// Parses `type Name = ty`.
+    fn parse_type_alias(&mut self) -> PResult<TypeAlias> {
+        self.expect(&TokenKind::Type)?;
+        let name = self.parse_ident()?;
+        let generics = self.parse_generics()?;
+        self.expect(&TokenKind::Equal)?;
+        let ty = self.parse_ty()?;
+        Ok(TypeAlias { name, generics, ty })
+    }
+
+    /// Parses `impl[T] Type { ... }` or `impl Trait for Type { ... }`.
+    fn parse_impl(&mut self) -> PResult<ImplBlock> {
+        self.expect(&TokenKind::Impl)?;
+        let generics = self.parse_generics()?;
+        let ty = self.parse_ty()?;
+        let (trait_ref, self_ty) = if self.eat(&TokenKind::For) {
+            let trait_ref = match ty.kind {
+                TyKind::Path(path) => path,
+                _ => {
+                    return Err(Diagnostic::error("expected a trait, found a type")
+                        .code("E0105")
+                        .primary(ty.span, "not a trait"));
+                }
+            };
+            (Some(trait_ref), self.parse_ty()?)
+        } else {
+            (None, ty)
+        };
+        let items = self.parse_methods(FnContext::Impl)?;
+        Ok(ImplBlock { generics, trait_ref, self_ty, items })
+    }
+
+    /// Parses `trait Name { ... }`.
+    fn parse_trait(&mut self) -> PResult<TraitDecl> {
+        self.expect(&TokenKind::Trait)?;
+        let name = self.parse_ident()?;
+        let generics = self.parse_generics()?;
+        let items = self.parse_methods(FnContext::Trait)?;
+        Ok(TraitDecl { name, generics, items })
+    }
+
+    /// Parses the methods of an `impl` or `trait` block, `{ fns }`.
+    fn parse_methods(&mut self, ctx: FnContext) -> PResult<Vec<Item>> {
+        self.expect(&TokenKind::LeftBrace)?;
+        let mut items = Vec::new();
+        while !self.eat(&TokenKind::RightBrace) {
+            if self.eat(&TokenKind::Semicolon) {
+                continue;
+            }
+
+            let cp = self.checkpoint();
+            let lo = self.token().span;
+            let docs = self.docs(DocStyle::Outer);
+            let attrs = self.parse_attributes()?;
+            let vis =
+                if self.eat(&TokenKind::Pub) { Visibility::Public } else { Visibility::Private };
+            if !self.check(&TokenKind::Fn) {
+                return Err(self.unexpected("`fn`"));
+            }
+            let kind = ItemKind::Fn(self.parse_fn(ctx)?);
+            self.wrap(cp, SyntaxKind::Fn);
+            items.push(Item { docs, attrs, vis, kind, span: lo.to(self.prev_span) });
+            self.expect_terminator(&TokenKind::RightBrace)?;
+        }
+        Ok(items)
+    }
+
+    /// Parses a function declaration. External declarations have no body.
+    fn parse_fn(&mut self, ctx: FnContext) -> PResult<FnDecl> {
+        self.expect(&TokenKind::Fn)?;
+        let name = self.parse_ident()?;
+        let generics = self.parse_generics()?;
+
+        let params_cp = self.checkpoint();
+        self.expect(&TokenKind::LeftParen)?;
+        let mut params = Vec::new();
+        let mut variadic = false;
+        while !self.check(&TokenKind::RightParen) {
+            if self.check(&TokenKind::Ellipsis) {
+                let span = self.bump().span;
+                if ctx != FnContext::Extern {
+                    return Err(Diagnostic::error(
+                        "variadic parameters are only allowed in extern functions",
+                    )
+                    .code("E0101")
+                    .primary(span, "not allowed here"));
+                }
+                variadic = true;
+                self.eat(&TokenKind::Comma);
+                break;
+            }
+
+            let cp = self.checkpoint();
+            if self.check(&TokenKind::SelfValue) {
+                let span = self.bump().span;
+                if !matches!(ctx, FnContext::Impl | FnContext::Trait) || !params.is_empty() {
+                    return Err(Diagnostic::error(
+                        "`self` parameter is only allowed first in methods",
+                    )
+                    .code("E0106")
+                    .primary(span, "not allowed here"));
+                }
+                let ty = if self.eat(&TokenKind::Colon) {
+                    self.parse_ty()?
+                } else {
+                    let path = Path::from_ident(Ident::new(kw::SelfType, span));
+                    Ty { kind: TyKind::Path(path), span }
+                };
+                params.push(Param {
+                    span: span.to(self.prev_span),
+                    name: Ident::new(kw::SelfValue, span),
+                    ty,
+                });
+            } else {
+                let name = self.parse_ident()?;
+                self.expect(&TokenKind::Colon)?;
+                let ty = self.parse_ty()?;
+                params.push(Param { span: name.span.to(ty.span), name, ty });
+            }
+            self.wrap(cp, SyntaxKind::Param);
+
+            if !self.eat(&TokenKind::Comma) {
+                break;
+            }
+        }
+        self.expect(&TokenKind::RightParen)?;
+        self.wrap(params_cp, SyntaxKind::ParamList);
+
+        let ret = if self.eat(&TokenKind::Arrow) { Some(self.parse_ty()?) } else { None };
+        let body = match ctx {
+            FnContext::Extern => None,
+            FnContext::Trait if !self.check(&TokenKind::LeftBrace) => None,
+            _ => Some(self.parse_block()?),
+        };
+
+        Ok(FnDecl { name, generics, params, variadic, ret, body })
+    }
+
+    /// Parses a type.
+    fn parse_ty(&mut self) -> PResult<Ty> {
+        let cp = self.checkpoint();
+        let lo = self.token().span;
+        let kind = match self.token().kind {
+            TokenKind::Bang => {
+                self.bump();
+                TyKind::Never
+            }
+            TokenKind::Star => {
+                self.bump();
+                let mutability = if self.eat(&TokenKind::Const) {
+                    Mutability::Const
+                } else if self.eat(&TokenKind::Mut) {
+                    Mutability::Mut
+                } else {
+                    return Err(self.unexpected("`const` or `mut`"));
+                };
+                TyKind::Ptr(mutability, Box::new(self.parse_ty()?))
+            }
+            TokenKind::Fn => {
+                self.bump();
+                self.expect(&TokenKind::LeftParen)?;
+                let params = self.parse_comma_list(&TokenKind::RightParen, Parser::parse_ty)?;
+                let ret =
+                    if self.eat(&TokenKind::Arrow) { Some(Box::new(self.parse_ty()?)) } else { None };
+                TyKind::Fn(params, ret)
+            }
+            TokenKind::Identifier(_) | TokenKind::SelfType => TyKind::Path(self.parse_path()?),
+            _ => return Err(self.unexpected("type")),
+        };
+        let node = match kind {
+            TyKind::Path(_) => SyntaxKind::PathType,
+            TyKind::Ptr(..) => SyntaxKind::PtrType,
+            TyKind::Never => SyntaxKind::NeverType,
+            TyKind::Fn(..) => SyntaxKind::FnType,
+        };
+        self.wrap(cp, node);
+        Ok(Ty { kind, span: lo.to(self.prev_span) })
+    }
+
+    /// Parses a block, `{ stmts }`.
+    fn parse_block(&mut self) -> PResult<Block> {
+        let cp = self.checkpoint();
+        let lo = self.expect(&TokenKind::LeftBrace)?.span;
+        let stmts = self.with_newlines(true, |p| {
+            p.with_struct_literal(true, |p| {
+                let mut stmts = Vec::new();
+                while !p.eat(&TokenKind::RightBrace) {
+                    if p.check(&TokenKind::Eof) {
+                        return Err(p.unexpected("`}`"));
+                    }
+                    if p.eat(&TokenKind::Semicolon) {
+                        continue;
+                    }
+                    stmts.push(p.parse_stmt()?);
+                    p.expect_terminator(&TokenKind::RightBrace)?;
+                }
+                Ok(stmts)
+            })
+        })?;
+        self.wrap(cp, SyntaxKind::Block);
+        Ok(Block { stmts, span: lo.to(self.prev_span) })
+    }
+
+    /// Parses a statement.
+    fn parse_stmt(&mut self) -> PResult<Stmt> {
+        let cp = self.checkpoint();
+        let lo = self.token().span;
+        let kind = match self.token().kind {
+            TokenKind::Let | TokenKind::Const => StmtKind::Local(self.parse_local()?),
+            TokenKind::Return => {
+                self.bump();
+                if self.can_begin_expr() && !self.at_line_start() {
+                    StmtKind::Return(Some(self.parse_expr()?))
+                } else {
+                    StmtKind::Return(None)
+                }
+            }
+            TokenKind::Defer => {
+                self.bump();
+                StmtKind::Defer(self.parse_expr()?)
+            }
+            _ => StmtKind::Expr(self.parse_expr()?),
+        };
+        let node = match kind {
+            StmtKind::Local(_) => SyntaxKind::LetStmt,
+            StmtKind::Return(_) => SyntaxKind::ReturnStmt,
+            StmtKind::Defer(_) => SyntaxKind::DeferStmt,
+            StmtKind::Expr(_) => SyntaxKind::ExprStmt,
+        };
+        self.wrap(cp, node);
+        Ok(Stmt { kind, span: lo.to(self.prev_span) })
+    }
+
+    /// Parses `let [mut] name [: ty] [= init]` or `const name [: ty] = init`.
+    fn parse_local(&mut self) -> PResult<Local> {
+        let kind = if self.eat(&TokenKind::Const) {
+            LocalKind::Const
+        } else {
+            self.expect(&TokenKind::Let)?;
+            LocalKind::Let
+        };
+
+        let mutability = if kind == LocalKind::Let && self.eat(&TokenKind::Mut) {
+            Mutability::Mut
+        } else {
+            Mutability::Const
+        };
+
+        let name = self.parse_ident()?;
+        let ty = if self.eat(&TokenKind::Colon) { Some(self.parse_ty()?) } else { None };
+        let init = if self.eat(&TokenKind::Equal) { Some(self.parse_expr()?) } else { None };
+
+        if kind == LocalKind::Const && init.is_none() {
+            return Err(Diagnostic::error("constant must have an initializer")
+                .code("E0102")
+                .primary(name.span, "missing `= value`"));
+        }
+
+        Ok(Local { kind, mutability, name, ty, init })
+    }
+
+    /// Checks whether the current token can start an expression.
+    fn can_begin_expr(&self) -> bool {
+        matches!(
+            self.token().kind,
+            TokenKind::Identifier(_)
+                | TokenKind::Integer(_)
+                | TokenKind::Float(_)
+                | TokenKind::String(_)
+                | TokenKind::Char(_)
+                | TokenKind::ByteString(_)
+                | TokenKind::CString(_)
+                | TokenKind::True
+                | TokenKind::False
+                | TokenKind::LeftParen
+                | TokenKind::LeftBrace
+                | TokenKind::Minus
+                | TokenKind::Bang
+                | TokenKind::Tilde
+                | TokenKind::DotDot
+                | TokenKind::DotDotEqual
+                | TokenKind::If
+                | TokenKind::While
+                | TokenKind::For
+                | TokenKind::Loop
+                | TokenKind::Break
+                | TokenKind::Continue
+                | TokenKind::Match
+                | TokenKind::SelfValue
+                | TokenKind::SelfType
+                | TokenKind::Null
+                | TokenKind::Sizeof
+        )
+    }
+
+    /// Parses an expression.
+    pub fn parse_expr(&mut self) -> PResult<Expr> {
+        let cp = self.checkpoint();
+        let lhs = self.parse_range()?;
+
+        let op = match self.token().kind {
+            TokenKind::Equal => None,
+            TokenKind::PlusEqual => Some(BinOp::Add),
+            TokenKind::MinusEqual => Some(BinOp::Sub),
+            TokenKind::StarEqual => Some(BinOp::Mul),
+            TokenKind::SlashEqual => Some(BinOp::Div),
+            TokenKind::PercentEqual => Some(BinOp::Rem),
+            TokenKind::CaretEqual => Some(BinOp::BitXor),
+            TokenKind::AmpEqual => Some(BinOp::BitAnd),
+            TokenKind::PipeEqual => Some(BinOp::BitOr),
+            TokenKind::ShiftLeftEqual => Some(BinOp::Shl),
+            TokenKind::ShiftRightEqual => Some(BinOp::Shr),
+            _ => return Ok(lhs),
+        };
+        if self.at_line_start() {
+            return Err(self.ambiguous_continuation());
+        }
+        self.bump();
+
+        // Assignment is right associative, `a = b = c` is `a = (b = c)`.
+        let rhs = self.parse_expr()?;
+        let span = lhs.span.to(rhs.span);
+        let kind = match op {
+            Some(op) => ExprKind::AssignOp(op, Box::new(lhs), Box::new(rhs)),
+            None => ExprKind::Assign(Box::new(lhs), Box::new(rhs)),
+        };
+        self.wrap(cp, SyntaxKind::AssignExpr);
+        Ok(Expr { kind, span })
+    }
+
+    /// Parses a range, `a..b` or `a..=b`, where both ends are optional.
+    fn parse_range(&mut self) -> PResult<Expr> {
+        let cp = self.checkpoint();
+        let lo = self.token().span;
+        let start = if matches!(self.token().kind, TokenKind::DotDot | TokenKind::DotDotEqual) {
+            None
+        } else {
+            let expr = self.parse_binary(0)?;
+            if !matches!(self.token().kind, TokenKind::DotDot | TokenKind::DotDotEqual) {
+                return Ok(expr);
+            }
+            if self.at_line_start() {
+                return Err(self.ambiguous_continuation());
+            }
+            Some(Box::new(expr))
+        };
+
+        let inclusive = self.bump().kind == TokenKind::DotDotEqual;
+        // `for i in 0.. { }` must not parse the loop body as the range end.
+        let end =
+            if self.can_begin_expr() && !self.check(&TokenKind::LeftBrace) && !self.at_line_start()
+            {
+                Some(Box::new(self.parse_binary(0)?))
+            } else if inclusive {
+                return Err(Diagnostic::error("inclusive range with no end")
+                    .code("E0104")
+                    .primary(self.prev_span, "`..=` needs an end")
+                    .help("use `..` instead"));
+            } else {
+                None
+            };
+
+        self.wrap(cp, SyntaxKind::RangeExpr);
+        Ok(Expr { kind: ExprKind::Range(start, end, inclusive), span: lo.to(self.prev_span) })
+    }
+
+    /// Returns the binary operator of the current token, if any.
+    fn binary_op(&self) -> Option<BinOp> {
+        let op = match self.token().kind {
+            TokenKind::Plus => BinOp::Add,
+            TokenKind::Minus => BinOp::Sub,
+            TokenKind::Star => BinOp::Mul,
+            TokenKind::Slash => BinOp::Div,
+            TokenKind::Percent => BinOp::Rem,
+            TokenKind::Caret => BinOp::BitXor,
+            TokenKind::Amp => BinOp::BitAnd,
+            TokenKind::Pipe => BinOp::BitOr,
+            TokenKind::ShiftLeft => BinOp::Shl,
+            TokenKind::ShiftRight => BinOp::Shr,
+            TokenKind::EqualEqual => BinOp::Eq,
+            TokenKind::BangEqual => BinOp::Ne,
+            TokenKind::Less => BinOp::Lt,
+            TokenKind::LessEqual => BinOp::Le,
+            TokenKind::Greater => BinOp::Gt,
+            TokenKind::GreaterEqual => BinOp::Ge,
+            TokenKind::AmpAmp => BinOp::And,
+            TokenKind::PipePipe => BinOp::Or,
+            _ => return None,
+        };
+        Some(op)
+    }
+
+    /// Parses binary operators binding tighter than `min_prec` using
+    /// precedence climbing.
+    fn parse_binary(&mut self, min_prec: u8) -> PResult<Expr> {
+        let cp = self.checkpoint();
+        let mut lhs = self.parse_cast()?;
+        while let Some(op) = self.binary_op() {
+            let prec = op.precedence();
+            if prec <= min_prec {
+                break;
+            }
+            if self.at_line_start() {
+                return Err(self.ambiguous_continuation());
+            }
+            self.bump();
+            let rhs = self.parse_binary(prec)?;
+            let span = lhs.span.to(rhs.span);
+            lhs = Expr { kind: ExprKind::Binary(op, Box::new(lhs), Box::new(rhs)), span };
+            self.wrap(cp, SyntaxKind::BinaryExpr);
+
+            if op.assoc() == Assoc::None {
+                if let Some(next) = self.binary_op().filter(|next| next.precedence() == prec) {
+                    return Err(Diagnostic::error(format!(
+                        "`{}` cannot be chained with `{}`",
+                        op.as_str(),
+                        next.as_str()
+                    ))
+                    .code("E0103")
+                    .primary(self.token().span, "chained operator")
+                    .secondary(lhs.span, "this expression is already a comparison")
+                    .help("split the comparison with `&&` or add parentheses"));
+                }
+            }
+        }
+        Ok(lhs)
+    }
+
+    /// Parses a cast, `expr as ty`.
+    fn parse_cast(&mut self) -> PResult<Expr> {
+        let cp = self.checkpoint();
+        let mut expr = self.parse_unary()?;
+        while self.check(&TokenKind::As) {
+            if self.at_line_start() {
+                return Err(self.ambiguous_continuation());
+            }
+            self.bump();
+            let ty = self.parse_ty()?;
+            let span = expr.span.to(ty.span);
+            expr = Expr { kind: ExprKind::Cast(Box::new(expr), ty), span };
+            self.wrap(cp, SyntaxKind::CastExpr);
+        }
+        Ok(expr)
+    }
+
+    /// Parses a unary expression.
+    fn parse_unary(&mut self) -> PResult<Expr> {
+        let cp = self.checkpoint();
+        let op = match self.token().kind {
+            TokenKind::Minus => UnOp::Neg,
+            TokenKind::Bang => UnOp::Not,
+            TokenKind::Tilde => UnOp::BitNot,
+            _ => return self.parse_postfix(),
+        };
+        let lo = self.bump().span;
+        let expr = self.parse_unary()?;
+        let span = lo.to(expr.span);
+        self.wrap(cp, SyntaxKind::UnaryExpr);
+        Ok(Expr { kind: ExprKind::Unary(op, Box::new(expr)), span })
+    }
+
+    /// Parses calls, type arguments and field accesses.
+    fn parse_postfix(&mut self) -> PResult<Expr> {
+        let cp = self.checkpoint();
+        let mut expr = self.parse_primary()?;
+        loop {
+            if self.check(&TokenKind::LeftParen) {
+                if self.at_line_start() {
+                    return Err(self.ambiguous_continuation());
+                }
+                let args_cp = self.checkpoint();
+                self.bump();
+                let args = self.parse_comma_list(&TokenKind::RightParen, Parser::parse_expr)?;
+                self.wrap(args_cp, SyntaxKind::ArgList);
+                let span = expr.span.to(self.prev_span);
+                expr = Expr { kind: ExprKind::Call(Box::new(expr), args), span };
+                self.wrap(cp, SyntaxKind::CallExpr);
+            } else if self.check(&TokenKind::LeftBracket) && !self.at_line_start() {
+                self.bump();
+                let args = self.parse_comma_list(&TokenKind::RightBracket, Parser::parse_ty)?;
+                let span = expr.span.to(self.prev_span);
+                expr = Expr { kind: ExprKind::TypeArgs(Box::new(expr), args), span };
+                self.wrap(cp, SyntaxKind::TypeArgsExpr);
+            } else if self.eat(&TokenKind::Dot) {
+                let field = self.parse_ident()?;
+                let span = expr.span.to(field.span);
+                expr = Expr { kind: ExprKind::Field(Box::new(expr), field), span };
+                self.wrap(cp, SyntaxKind::FieldExpr);
+            } else {
+                break;
+            }
+        }
+        Ok(expr)
+    }
+
+    /// Parses a primary expression.
+    fn parse_primary(&mut self) -> PResult<Expr> {
+        let cp = self.checkpoint();
+        let span = self.token().span;
+        let kind = match &self.token().kind {
+            TokenKind::Integer(lit) => ExprKind::Lit(Lit::Int(*lit)),
+            TokenKind::Float(lit) => ExprKind::Lit(Lit::Float(*lit)),
+            TokenKind::String(s) => ExprKind::Lit(Lit::Str(*s)),
+            TokenKind::Char(c) => ExprKind::Lit(Lit::Char(*c)),
+            TokenKind::ByteString(b) => ExprKind::Lit(Lit::ByteStr(b.clone())),
+            TokenKind::CString(b) => ExprKind::Lit(Lit::CStr(b.clone())),
+            TokenKind::True => ExprKind::Lit(Lit::Bool(true)),
+            TokenKind::False => ExprKind::Lit(Lit::Bool(false)),
+            TokenKind::Null => ExprKind::Lit(Lit::Null),
+            TokenKind::Identifier(_) | TokenKind::SelfValue | TokenKind::SelfType => {
+                let path = self.parse_path()?;
+                if self.check(&TokenKind::LeftBrace)
+                    && !self.no_struct_literal
+                    && !self.at_line_start()
+                {
+                    let expr = self.parse_struct_literal(path)?;
+                    self.wrap(cp, SyntaxKind::StructLit);
+                    return Ok(expr);
+                }
+                self.wrap(cp, SyntaxKind::PathExpr);
+                return Ok(Expr { span: path.span, kind: ExprKind::Path(path) });
+            }
+            TokenKind::LeftParen => {
+                self.bump();
+                let expr =
+                    self.with_newlines(false, |p| p.with_struct_literal(true, Parser::parse_expr))?;
+                self.expect(&TokenKind::RightParen)?;
+                self.wrap(cp, SyntaxKind::ParenExpr);
+                return Ok(Expr {
+                    kind: ExprKind::Paren(Box::new(expr)),
+                    span: span.to(self.prev_span),
+                });
+            }
+            TokenKind::LeftBrace => return self.parse_block_expr(),
+            TokenKind::If => return self.parse_if(),
+            TokenKind::While => return self.parse_while(),
+            TokenKind::For => return self.parse_for(),
+            TokenKind::Match => return self.parse_match(),
+            TokenKind::Loop => {
+                self.bump();
+                let block = self.parse_block()?;
+                self.wrap(cp, SyntaxKind::LoopExpr);
+                return Ok(Expr { kind: ExprKind::Loop(block), span: span.to(self.prev_span) });
+            }
+            TokenKind::Break => {
+                self.bump();
+                let value = if self.can_begin_expr()
+                    && !self.check(&TokenKind::LeftBrace)
+                    && !self.at_line_start()
+                {
+                    Some(Box::new(self.parse_expr()?))
+                } else {
+                    None
+                };
+                self.wrap(cp, SyntaxKind::BreakExpr);
+                return Ok(Expr { kind: ExprKind::Break(value), span: span.to(self.prev_span) });
+            }
+            TokenKind::Continue => ExprKind::Continue,
+            TokenKind::Sizeof => {
+                self.bump();
+                self.expect(&TokenKind::LeftParen)?;
+                let ty = self.parse_ty()?;
+                self.expect(&TokenKind::RightParen)?;
+                self.wrap(cp, SyntaxKind::SizeofExpr);
+                return Ok(Expr { kind: ExprKind::SizeOf(ty), span: span.to(self.prev_span) });
+            }
+            _ => return Err(self.unexpected("expression")),
+        };
+        self.bump();
+        let node = match kind {
+            ExprKind::Continue => SyntaxKind::ContinueExpr,
+            _ => SyntaxKind::Literal,
+        };
+        self.wrap(cp, node);
+        Ok(Expr { kind, span })
+    }
+
+    /// Parses a block used as an expression.
+    fn parse_block_expr(&mut self) -> PResult<Expr> {
+        let cp = self.checkpoint();
+        let block = self.parse_block()?;
+        self.wrap(cp, SyntaxKind::BlockExpr);
+        Ok(Expr { span: block.span, kind: ExprKind::Block(block) })
+    }
+
+    /// Parses the fields of a struct literal, `Path { a: 1, b }`.
+    fn parse_struct_literal(&mut self, path: Path) -> PResult<Expr> {
+        self.expect(&TokenKind::LeftBrace)?;
+        let fields = self.with_struct_literal(true, |p| {
+            p.parse_comma_list(&TokenKind::RightBrace, |p| {
+                let cp = p.checkpoint();
+                let name = p.parse_ident()?;
+                let expr = if p.eat(&TokenKind::Colon) {
+                    p.parse_expr()?
+                } else {
+                    let path = Path::from_ident(name);
+                    Expr { span: name.span, kind: ExprKind::Path(path) }
+                };
+                p.wrap(cp, SyntaxKind::FieldInit);
+                Ok(FieldInit { span: name.span.to(p.prev_span), name, expr })
+            })
+        })?;
+        let span = path.span.to(self.prev_span);
+        Ok(Expr { kind: ExprKind::Struct(path, fields), span })
+    }
+
+    /// Parses `if cond { ... } [else { ... } | else if ...]`.
+    fn parse_if(&mut self) -> PResult<Expr> {
+        let cp = self.checkpoint();
+        let lo = self.expect(&TokenKind::If)?.span;
+        let cond = self.parse_cond()?;
+        let then = self.parse_block()?;
+        let els = if self.eat(&TokenKind::Else) {
+            if self.check(&TokenKind::If) {
+                Some(Box::new(self.parse_if()?))
+            } else {
+                Some(Box::new(self.parse_block_expr()?))
+            }
+        } else {
+            None
+        };
+        self.wrap(cp, SyntaxKind::IfExpr);
+        Ok(Expr { kind: ExprKind::If(Box::new(cond), then, els), span: lo.to(self.prev_span) })
+    }
+
+    /// Parses `while cond { ... }`.
+    fn parse_while(&mut self) -> PResult<Expr> {
+        let cp = self.checkpoint();
+        let lo = self.expect(&TokenKind::While)?.span;
+        let cond = self.parse_cond()?;
+        let body = self.parse_block()?;
+        self.wrap(cp, SyntaxKind::WhileExpr);
+        Ok(Expr { kind: ExprKind::While(Box::new(cond), body), span: lo.to(self.prev_span) })
+    }
+
+    /// Parses `for name in iter { ... }`.
+    fn parse_for(&mut self) -> PResult<Expr> {
+        let cp = self.checkpoint();
+        let lo = self.expect(&TokenKind::For)?.span;
+        let name = self.parse_ident()?;
+        self.expect(&TokenKind::In)?;
+        let iter = self.parse_cond()?;
+        let body = self.parse_block()?;
+        self.wrap(cp, SyntaxKind::ForExpr);
+        Ok(Expr { kind: ExprKind::For(name, Box::new(iter), body), span: lo.to(self.prev_span) })
+    }
+
+    /// Parses `match expr { pat [if guard] => body, ... }`.
+    fn parse_match(&mut self) -> PResult<Expr> {
+        let cp = self.checkpoint();
+        let lo = self.expect(&TokenKind::Match)?.span;
+        let scrutinee = self.parse_cond()?;
+        self.expect(&TokenKind::LeftBrace)?;
+
+        let arms = self.with_newlines(true, |p| p.with_struct_literal(true, Parser::parse_arms))?;
+
+        let span = lo.to(self.prev_span);
+        self.wrap(cp, SyntaxKind::MatchExpr);
+        Ok(Expr { kind: ExprKind::Match(Box::new(scrutinee), arms), span })
+    }
+
+    /// Parses the arms of a match expression after the `{`. An arm ends
+    /// with a `,`, a line break or its block body.
+    fn parse_arms(&mut self) -> PResult<Vec<Arm>> {
+        let mut arms = Vec::new();
+        while !self.eat(&TokenKind::RightBrace) {
+            let cp = self.checkpoint();
+            let pat = self.parse_pat()?;
+            let guard = if self.eat(&TokenKind::If) { Some(self.parse_expr()?) } else { None };
+            self.expect(&TokenKind::FatArrow)?;
+            let is_block = self.check(&TokenKind::LeftBrace);
+            let body = if is_block { self.parse_block_expr()? } else { self.parse_expr()? };
+            let span = pat.span.to(body.span);
+            arms.push(Arm { pat, guard, body, span });
+            if !self.eat(&TokenKind::Comma)
+                && !is_block
+                && !self.check(&TokenKind::RightBrace)
+                && !self.token().newline_before
+            {
+                return Err(self.unexpected("`,`, `}` or a line break"));
+            }
+            self.wrap(cp, SyntaxKind::MatchArm);
+        }
+        Ok(arms)
+    }
+
+    /// Parses a pattern, alternatives are separated by `|`.
+    fn parse_pat(&mut self) -> PResult<Pat> {
+        let cp = self.checkpoint();
+        let first = self.parse_pat_primary()?;
+        if !self.check(&TokenKind::Pipe) {
+            return Ok(first);
+        }
+        let mut pats = vec![first];
+        while self.eat(&TokenKind::Pipe) {
+            pats.push(self.parse_pat_primary()?);
+        }
+        let span = pats[0].span.to(self.prev_span);
+        self.wrap(cp, SyntaxKind::OrPat);
+        Ok(Pat { kind: PatKind::Or(pats), span })
+    }
+
+    /// Parses a pattern without alternatives.
+    fn parse_pat_primary(&mut self) -> PResult<Pat> {
+        let cp = self.checkpoint();
+        let lo = self.token().span;
+        let kind = match &self.token().kind {
+            TokenKind::Identifier(name) if *name == sym::underscore => {
+                self.bump();
+                PatKind::Wild
+            }
+            TokenKind::Identifier(_) | TokenKind::SelfType => {
+                let path = self.parse_path()?;
+                if self.eat(&TokenKind::LeftParen) {
+                    let pats = self.parse_comma_list(&TokenKind::RightParen, Parser::parse_pat)?;
+                    PatKind::TupleStruct(path, pats)
+                } else if path.segments.len() == 1 && path.segments[0].name != kw::SelfType {
+                    PatKind::Binding(path.segments.into_iter().next().unwrap())
+                } else {
+                    PatKind::Path(path)
+                }
+            }
+            TokenKind::Minus
+            | TokenKind::Integer(_)
+            | TokenKind::Float(_)
+            | TokenKind::String(_)
+            | TokenKind::Char(_)
+            | TokenKind::ByteString(_)
+            | TokenKind::CString(_)
+            | TokenKind::True
+            | TokenKind::False
+            | TokenKind::Null => PatKind::Lit(Box::new(self.parse_unary()?)),
+            _ => return Err(self.unexpected("pattern")),
+        };
+        let node = match kind {
+            PatKind::Wild => SyntaxKind::WildPat,
+            PatKind::Lit(_) => SyntaxKind::LitPat,
+            PatKind::Binding(_) => SyntaxKind::BindingPat,
+            PatKind::Path(_) => SyntaxKind::PathPat,
+            PatKind::TupleStruct(..) => SyntaxKind::TupleStructPat,
+            PatKind::Or(_) => SyntaxKind::OrPat,
+        };
+        self.wrap(cp, node);
+        Ok(Pat { kind, span: lo.to(self.prev_span) })
+    }
+}
@@ -0,0 +1,116 @@
+use super::Namespace;
+use crate::{diag::Diagnostic, span::Span, symbol::Symbol};
+use std::fmt;
+
+/// ResolveError is a name that cannot be resolved or is not accessible, or
+/// the use of a deprecated definition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolveError {
+    pub kind: ResolveErrorKind,
+    pub span: Span,
+}
+
+impl ResolveError {
+    /// Creates a new resolve error.
+    pub fn new(kind: ResolveErrorKind, span: Span) -> ResolveError {
+        ResolveError { kind, span }
+    }
+
+    /// Checks whether the error is only a warning, which does not stop the
+    /// compilation.
+    pub fn is_warning(&self) -> bool {
+        matches!(self.kind, ResolveErrorKind::Deprecated { .. })
+    }
+
+    /// Converts the error into a diagnostic.
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let (code, label) = match &self.kind {
+            ResolveErrorKind::Undefined { .. } => ("E0400", "not found in this scope".to_string()),
+            ResolveErrorKind::WrongNamespace { expected, .. } => {
+                ("E0401", format!("not a {}", expected.describe()))
+            }
+            ResolveErrorKind::NotFound { container_kind, .. } => {
+                ("E0402", format!("not found in this {}", container_kind))
+            }
+            ResolveErrorKind::Private { kind, .. } => ("E0403", format!("private {}", kind)),
+            ResolveErrorKind::Duplicate { .. } => ("E0404", "defined again here".to_string()),
+            ResolveErrorKind::NotContainer { .. } => ("E0405", "not a module or type".to_string()),
+            ResolveErrorKind::Deprecated { .. } => ("W0400", "deprecated".to_string()),
+        };
+
+        let diag = if self.is_warning() {
+            Diagnostic::warning(self.kind.to_string())
+        } else {
+            Diagnostic::error(self.kind.to_string())
+        };
+        let diag = diag.code(code).primary(self.span, label);
+        match &self.kind {
+            ResolveErrorKind::Undefined { suggestion: Some(suggestion), .. }
+            | ResolveErrorKind::NotFound { suggestion: Some(suggestion), .. } => {
+                diag.suggest("a similar name exists", self.span, suggestion.to_string())
+            }
+            ResolveErrorKind::Private { def_span, .. } => diag
+                .secondary(*def_span, "defined here")
+                .help("items are private to their module unless declared `pub`"),
+            ResolveErrorKind::Duplicate { first, .. } => {
+                diag.secondary(*first, "first defined here")
+            }
+            ResolveErrorKind::Deprecated { note: Some(note), .. } => diag.note(note.to_string()),
+            _ => diag,
+        }
+    }
+}
+
+/// ResolveErrorKind is the kind of resolve error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResolveErrorKind {
+    /// A name not declared in any enclosing scope, with the closest name in
+    /// scope if there is one.
+    Undefined { name: Symbol, ns: Namespace, suggestion: Option<Symbol> },
+    /// A name used as a value that is a type or a module, or the opposite.
+    WrongNamespace { name: Symbol, expected: Namespace, found: &'static str },
+    /// A name not declared in a module or type, e.g. `x` in `math.x`.
+    NotFound {
+        name: Symbol,
+        container: Symbol,
+        container_kind: &'static str,
+        suggestion: Option<Symbol>,
+    },
+    /// An item of another module that is not `pub`.
+    Private { name: Symbol, kind: &'static str, def_span: Span },
+    /// A name declared twice in the same scope.
+    Duplicate { name: Symbol, first: Span },
+    /// A path prefix that is neither a module nor a type, e.g. `x` in `x::y`.
+    NotContainer { name: Symbol, found: &'static str },
+    /// A use of a definition marked `@deprecated`, with the note of the
+    /// attribute.
+    Deprecated { name: Symbol, kind: &'static str, note: Option<Symbol> },
+}
+
+impl fmt::Display for ResolveErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResolveErrorKind::Undefined { name, ns, .. } => {
+                write!(f, "cannot find {} `{}` in this scope", ns.describe(), name)
+            }
+            ResolveErrorKind::WrongNamespace { name, expected, found } => {
+                write!(f, "expected {}, found {} `{}`", expected.describe(), found, name)
+            }
+            ResolveErrorKind::NotFound { name, container, container_kind, .. } => {
+                write!(f, "cannot find `{}` in {} `{}`", name, container_kind, container)
+            }
+            ResolveErrorKind::Private { name, kind, .. } => {
+                write!(f, "{} `{}` is private", kind, name)
+            }
+            ResolveErrorKind::Duplicate { name, .. } => {
+                write!(f, "the name `{}` is defined more than once", name)
+            }
+            ResolveErrorKind::NotContainer { name, found } => {
+                write!(f, "expected module or type, found {} `{}`", found, name)
+            }
+            ResolveErrorKind::Deprecated { name, kind, .. } => {
+                write!(f, "use of deprecated {} `{}`", kind, name)
+            }
+        }
+    }
+}
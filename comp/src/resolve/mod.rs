@@ -0,0 +1,1032 @@
+//! Name resolution.
+//!
+//! The resolver binds every name used in a program to its declaration. Names
+//! live in two namespaces, values (functions, parameters and locals) and
+//! types (structs, unions, enums, type aliases, traits, generic parameters and
+//! primitive types), and are looked up from the innermost scope out:
+//!
+//! - the locals of the enclosing blocks, a `let` shadowing earlier bindings of
+//!   the same name from the next statement on,
+//! - the parameters and generic parameters of the enclosing function, and the
+//!   generic parameters and `Self` of the enclosing impl block or trait,
+//! - the items of the module and the modules it imports,
+//! - the primitive types.
+//!
+//! Items of an imported module are reached through the name of the import,
+//! `math.add` in expressions or `math::Point` in paths, and must be `pub`.
+//! Variants and associated functions are reached through their type, as in
+//! `Color::Red` or `Point::new`. Fields and methods of values depend on their
+//! type and are left to the type checker.
+//!
+//! The result maps the span of every resolved path to what it refers to. A
+//! module access such as `math.add` is recorded under the span of the whole
+//! field expression. Once every path is resolved, the uses of definitions
+//! marked `@deprecated` are reported as warnings.
+
+use self::error::{ResolveError, ResolveErrorKind};
+use crate::{
+    ast::{
+        Block, Expr, ExprKind, FnDecl, Ident, Item, ItemKind, Pat, PatKind, Path, Stmt, StmtKind,
+        Ty, TyKind, Visibility,
+    },
+    attr::{Attrs, Target},
+    loader::{ModuleGraph, ModuleId, ModuleImport},
+    span::Span,
+    symbol::{Symbol, edit_distance::find_best_match, kw, sym},
+};
+use std::collections::HashMap;
+
+pub mod error;
+
+/// A primitive type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PrimTy {
+    I8,
+    I16,
+    I32,
+    I64,
+    Isize,
+    U8,
+    U16,
+    U32,
+    U64,
+    Usize,
+    F32,
+    F64,
+    Bool,
+    Void,
+}
+
+impl PrimTy {
+    /// All primitive types.
+    pub const ALL: [PrimTy; 14] = [
+        PrimTy::I8,
+        PrimTy::I16,
+        PrimTy::I32,
+        PrimTy::I64,
+        PrimTy::Isize,
+        PrimTy::U8,
+        PrimTy::U16,
+        PrimTy::U32,
+        PrimTy::U64,
+        PrimTy::Usize,
+        PrimTy::F32,
+        PrimTy::F64,
+        PrimTy::Bool,
+        PrimTy::Void,
+    ];
+
+    /// Returns the name of the type.
+    pub fn name(self) -> Symbol {
+        match self {
+            PrimTy::I8 => sym::i8,
+            PrimTy::I16 => sym::i16,
+            PrimTy::I32 => sym::i32,
+            PrimTy::I64 => sym::i64,
+            PrimTy::Isize => sym::isize,
+            PrimTy::U8 => sym::u8,
+            PrimTy::U16 => sym::u16,
+            PrimTy::U32 => sym::u32,
+            PrimTy::U64 => sym::u64,
+            PrimTy::Usize => sym::usize,
+            PrimTy::F32 => sym::f32,
+            PrimTy::F64 => sym::f64,
+            PrimTy::Bool => sym::bool,
+            PrimTy::Void => sym::void,
+        }
+    }
+
+    /// Returns the primitive type with the given name.
+    pub fn from_name(name: Symbol) -> Option<PrimTy> {
+        PrimTy::ALL.into_iter().find(|prim| prim.name() == name)
+    }
+}
+
+/// Namespace of a name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Namespace {
+    Value,
+    Type,
+}
+
+impl Namespace {
+    /// Returns a short description of the names in the namespace.
+    pub fn describe(self) -> &'static str {
+        match self {
+            Namespace::Value => "value",
+            Namespace::Type => "type",
+        }
+    }
+}
+
+/// Index of a definition in `Resolutions`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DefId(u32);
+
+impl DefId {
+    /// Returns the index of the definition.
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Kind of definition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DefKind {
+    Fn,
+    /// A function declared in an extern block.
+    ExternFn,
+    /// A function of an impl block or trait.
+    Method,
+    Struct,
+    Union,
+    Enum,
+    Variant,
+    TypeAlias,
+    Trait,
+}
+
+impl DefKind {
+    /// Returns a short description of the definition, e.g. `function`.
+    pub fn describe(self) -> &'static str {
+        match self {
+            DefKind::Fn | DefKind::ExternFn => "function",
+            DefKind::Method => "associated function",
+            DefKind::Struct => "struct",
+            DefKind::Union => "union",
+            DefKind::Enum => "enum",
+            DefKind::Variant => "variant",
+            DefKind::TypeAlias => "type alias",
+            DefKind::Trait => "trait",
+        }
+    }
+
+    /// Returns the namespace the definition is in.
+    pub fn namespace(self) -> Namespace {
+        match self {
+            DefKind::Fn | DefKind::ExternFn | DefKind::Method | DefKind::Variant => {
+                Namespace::Value
+            }
+            _ => Namespace::Type,
+        }
+    }
+}
+
+/// Position of a definition in the AST of its module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ItemPath {
+    /// Index of the item in the module.
+    pub item: usize,
+    /// Index of the function in an extern block, impl block or trait, or of
+    /// the variant in an enum.
+    pub nested: Option<usize>,
+}
+
+/// A named declaration of a module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Def {
+    /// Name of the definition.
+    pub name: Ident,
+    /// Kind of definition.
+    pub kind: DefKind,
+    /// Visibility of the definition. Variants and trait functions have the
+    /// visibility of their enum or trait.
+    pub vis: Visibility,
+    /// Module the definition is in.
+    pub module: ModuleId,
+    /// Position of the definition in its module.
+    pub path: ItemPath,
+    /// Type or trait a variant or associated function belongs to.
+    pub parent: Option<DefId>,
+}
+
+/// What a name refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Res {
+    /// An item, variant or associated function.
+    Def(DefId),
+    /// A parameter, local or pattern binding, by the span of its name.
+    Local(Span),
+    /// A generic parameter, by the span of its name.
+    Generic(Span),
+    /// A primitive type.
+    PrimTy(PrimTy),
+    /// `Self` in an impl block or trait.
+    SelfTy,
+    /// An imported module.
+    Module(ModuleId),
+}
+
+/// Names declared at the top level of a module.
+#[derive(Debug, Default)]
+struct ModuleItems {
+    /// Functions, by name.
+    values: HashMap<Symbol, DefId>,
+    /// Types and traits, by name.
+    types: HashMap<Symbol, DefId>,
+    /// Imported modules, by the name they are bound to.
+    modules: HashMap<Symbol, ModuleImport>,
+}
+
+impl ModuleItems {
+    /// Returns the table of a namespace.
+    fn table(&self, ns: Namespace) -> &HashMap<Symbol, DefId> {
+        match ns {
+            Namespace::Value => &self.values,
+            Namespace::Type => &self.types,
+        }
+    }
+}
+
+/// Resolutions holds the definitions of a program and what every name used
+/// in it refers to.
+#[derive(Debug, Default)]
+pub struct Resolutions {
+    /// Definitions, indexed by `DefId`.
+    defs: Vec<Def>,
+    /// Definitions by module and position.
+    by_path: HashMap<(ModuleId, ItemPath), DefId>,
+    /// Top level names of every module, indexed by `ModuleId`.
+    items: Vec<ModuleItems>,
+    /// Variants and associated functions, by parent and name.
+    assoc: HashMap<(DefId, Symbol), DefId>,
+    /// What every resolved path refers to, by span.
+    uses: HashMap<Span, Res>,
+}
+
+impl Resolutions {
+    /// Returns the definition with the given id.
+    pub fn def(&self, id: DefId) -> &Def {
+        &self.defs[id.index()]
+    }
+
+    /// Returns all definitions with their ids.
+    pub fn defs(&self) -> impl Iterator<Item = (DefId, &Def)> {
+        self.defs.iter().enumerate().map(|(i, def)| (DefId(i as u32), def))
+    }
+
+    /// Returns the definition at the given position of a module.
+    pub fn def_at(&self, module: ModuleId, path: ItemPath) -> Option<DefId> {
+        self.by_path.get(&(module, path)).copied()
+    }
+
+    /// Returns the top level definition of a module with the given name.
+    pub fn item(&self, module: ModuleId, name: Symbol, ns: Namespace) -> Option<DefId> {
+        self.items.get(module.index())?.table(ns).get(&name).copied()
+    }
+
+    /// Returns the variant or associated function of a definition with the
+    /// given name.
+    pub fn assoc(&self, parent: DefId, name: Symbol) -> Option<DefId> {
+        self.assoc.get(&(parent, name)).copied()
+    }
+
+    /// Returns what the path or module access at `span` refers to.
+    pub fn get(&self, span: Span) -> Option<Res> {
+        self.uses.get(&span).copied()
+    }
+}
+
+/// Resolves the names of every module of a program.
+pub fn resolve(graph: &ModuleGraph) -> (Resolutions, Vec<ResolveError>) {
+    let mut resolver = Resolver {
+        graph,
+        res: Resolutions::default(),
+        module: ModuleId::default(),
+        scopes: Vec::new(),
+        self_def: None,
+        errors: Vec::new(),
+    };
+    resolver.res.items.resize_with(graph.modules().len(), ModuleItems::default);
+    for &id in graph.order() {
+        resolver.collect(id);
+    }
+    for &id in graph.order() {
+        resolver.collect_impls(id);
+    }
+    for &id in graph.order() {
+        resolver.resolve_module(id);
+    }
+    resolver.check_deprecated();
+    (resolver.res, resolver.errors)
+}
+
+/// Names bound by a function, block, match arm or generic parameter list.
+#[derive(Debug, Default)]
+struct Scope {
+    /// Parameters and locals, by name.
+    values: HashMap<Symbol, Res>,
+    /// Generic parameters and `Self`, by name.
+    types: HashMap<Symbol, Res>,
+}
+
+/// Resolver walks the modules of a program, keeping track of the names in
+/// scope.
+struct Resolver<'a> {
+    /// The program.
+    graph: &'a ModuleGraph,
+    /// The result being built.
+    res: Resolutions,
+    /// Module being resolved.
+    module: ModuleId,
+    /// Scopes of the current item, innermost last.
+    scopes: Vec<Scope>,
+    /// Definition `Self` refers to in the current impl block.
+    self_def: Option<DefId>,
+    /// Errors found so far.
+    errors: Vec<ResolveError>,
+}
+
+impl<'a> Resolver<'a> {
+    /// Records an error.
+    fn error(&mut self, kind: ResolveErrorKind, span: Span) {
+        self.errors.push(ResolveError::new(kind, span));
+    }
+
+    /// Warns about every resolved use of a deprecated definition, in source
+    /// order.
+    fn check_deprecated(&mut self) {
+        let mut uses: Vec<_> = self
+            .res
+            .uses
+            .iter()
+            .filter_map(|(&span, &res)| match res {
+                Res::Def(id) => Some((span, id)),
+                _ => None,
+            })
+            .collect();
+        uses.sort_by_key(|(span, _)| span.lo);
+        let mut deprecations = HashMap::new();
+        for (span, id) in uses {
+            let deprecated = *deprecations.entry(id).or_insert_with(|| self.deprecation(id));
+            // Uses inside the deprecated item itself are not reported.
+            if let Some((item_span, note)) = deprecated {
+                if item_span.lo <= span.lo && span.hi <= item_span.hi {
+                    continue;
+                }
+                let def = self.res.def(id);
+                let kind = ResolveErrorKind::Deprecated {
+                    name: def.name.name,
+                    kind: def.kind.describe(),
+                    note,
+                };
+                self.error(kind, span);
+            }
+        }
+    }
+
+    /// Returns the span of the item of a definition and the note of its
+    /// `@deprecated` attribute, if it has one. Variants have no attributes of
+    /// their own.
+    fn deprecation(&self, id: DefId) -> Option<(Span, Option<Symbol>)> {
+        let def = self.res.def(id);
+        let item = &self.graph.get(def.module).ast.items[def.path.item];
+        let (item, parent) = match (&item.kind, def.path.nested) {
+            (ItemKind::Extern(block), Some(i)) => (&block.items[i], Some(Target::ExternBlock)),
+            (ItemKind::Impl(block), Some(i)) => (&block.items[i], Some(Target::Impl)),
+            (ItemKind::Trait(decl), Some(i)) => (&decl.items[i], Some(Target::Trait)),
+            (_, Some(_)) => return None,
+            (_, None) => (item, None),
+        };
+        // Invalid attributes were reported when the module was loaded.
+        let note = Attrs::lower(&item.attrs, Target::of(item, parent)).0.deprecated?;
+        Some((item.span, note))
+    }
+
+    /// Returns the top level names of the current module.
+    fn items(&self) -> &ModuleItems {
+        &self.res.items[self.module.index()]
+    }
+
+    /// Adds a definition without binding its name.
+    fn add_def(&mut self, def: Def) -> DefId {
+        let id = DefId(self.res.defs.len() as u32);
+        self.res.by_path.insert((def.module, def.path), id);
+        self.res.defs.push(def);
+        id
+    }
+
+    /// Adds a top level definition to the current module.
+    fn define(&mut self, name: Ident, kind: DefKind, vis: Visibility, path: ItemPath) {
+        let def = Def { name, kind, vis, module: self.module, path, parent: None };
+        let id = self.add_def(def);
+        let ns = kind.namespace();
+        let items = self.items();
+        let first = match items.table(ns).get(&name.name) {
+            Some(&first) => Some(self.res.def(first).name.span),
+            None => items.modules.get(&name.name).map(|import| import.span),
+        };
+        if let Some(first) = first {
+            self.error(ResolveErrorKind::Duplicate { name: name.name, first }, name.span);
+            return;
+        }
+        let items = &mut self.res.items[self.module.index()];
+        match ns {
+            Namespace::Value => items.values.insert(name.name, id),
+            Namespace::Type => items.types.insert(name.name, id),
+        };
+    }
+
+    /// Adds a variant or associated function of `parent` to the current
+    /// module. Duplicates are reported unless `quiet` is set.
+    fn define_assoc(
+        &mut self,
+        parent: DefId,
+        name: Ident,
+        kind: DefKind,
+        vis: Visibility,
+        path: ItemPath,
+        quiet: bool,
+    ) {
+        let def = Def { name, kind, vis, module: self.module, path, parent: Some(parent) };
+        let id = self.add_def(def);
+        if let Some(&first) = self.res.assoc.get(&(parent, name.name)) {
+            if !quiet {
+                let first = self.res.def(first).name.span;
+                self.error(ResolveErrorKind::Duplicate { name: name.name, first }, name.span);
+            }
+            return;
+        }
+        self.res.assoc.insert((parent, name.name), id);
+    }
+
+    /// Collects the imports and top level definitions of a module, with the
+    /// variants of its enums and the functions of its traits.
+    fn collect(&mut self, id: ModuleId) {
+        self.module = id;
+        let module = self.graph.get(id);
+        for import in &module.imports {
+            self.res.items[id.index()].modules.insert(import.name, *import);
+        }
+
+        for (i, item) in module.ast.items.iter().enumerate() {
+            let path = ItemPath { item: i, nested: None };
+            match &item.kind {
+                ItemKind::Import(_) | ItemKind::Impl(_) => {}
+                ItemKind::Extern(block) => {
+                    for (j, inner) in block.items.iter().enumerate() {
+                        if let ItemKind::Fn(decl) = &inner.kind {
+                            let path = ItemPath { item: i, nested: Some(j) };
+                            self.define(decl.name, DefKind::ExternFn, inner.vis, path);
+                        }
+                    }
+                }
+                ItemKind::Fn(decl) => self.define(decl.name, DefKind::Fn, item.vis, path),
+                ItemKind::Struct(decl) => self.define(decl.name, DefKind::Struct, item.vis, path),
+                ItemKind::Union(decl) => self.define(decl.name, DefKind::Union, item.vis, path),
+                ItemKind::TypeAlias(alias) => {
+                    self.define(alias.name, DefKind::TypeAlias, item.vis, path)
+                }
+                ItemKind::Enum(decl) => {
+                    self.define(decl.name, DefKind::Enum, item.vis, path);
+                    let parent = self.res.by_path[&(id, path)];
+                    for (j, variant) in decl.variants.iter().enumerate() {
+                        let path = ItemPath { item: i, nested: Some(j) };
+                        let kind = DefKind::Variant;
+                        self.define_assoc(parent, variant.name, kind, item.vis, path, false);
+                    }
+                }
+                ItemKind::Trait(decl) => {
+                    self.define(decl.name, DefKind::Trait, item.vis, path);
+                    let parent = self.res.by_path[&(id, path)];
+                    for (j, inner) in decl.items.iter().enumerate() {
+                        if let ItemKind::Fn(decl) = &inner.kind {
+                            let path = ItemPath { item: i, nested: Some(j) };
+                            let kind = DefKind::Method;
+                            self.define_assoc(parent, decl.name, kind, item.vis, path, false);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Collects the functions of the impl blocks of a module. Functions of
+    /// inherent impls are associated with their type when it is declared in
+    /// the module, functions of trait impls only if the name is still free.
+    fn collect_impls(&mut self, id: ModuleId) {
+        self.module = id;
+        let module = self.graph.get(id);
+        for (i, item) in module.ast.items.iter().enumerate() {
+            let ItemKind::Impl(block) = &item.kind else {
+                continue;
+            };
+            let parent = match &block.self_ty.kind {
+                TyKind::Path(path) if path.segments.len() == 1 => {
+                    self.items().types.get(&path.segments[0].name).copied()
+                }
+                _ => None,
+            };
+            for (j, inner) in block.items.iter().enumerate() {
+                let ItemKind::Fn(decl) = &inner.kind else {
+                    continue;
+                };
+                let path = ItemPath { item: i, nested: Some(j) };
+                match parent {
+                    Some(parent) => {
+                        let quiet = block.trait_ref.is_some();
+                        self.define_assoc(
+                            parent,
+                            decl.name,
+                            DefKind::Method,
+                            inner.vis,
+                            path,
+                            quiet,
+                        );
+                    }
+                    None => {
+                        let def = Def {
+                            name: decl.name,
+                            kind: DefKind::Method,
+                            vis: inner.vis,
+                            module: id,
+                            path,
+                            parent: None,
+                        };
+                        self.add_def(def);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Resolves the names used in the items of a module.
+    fn resolve_module(&mut self, id: ModuleId) {
+        self.module = id;
+        let graph = self.graph;
+        for item in &graph.get(id).ast.items {
+            self.item(item);
+        }
+    }
+
+    /// Runs `f` with `scope` pushed as the innermost scope.
+    fn with_scope<R>(&mut self, scope: Scope, f: impl FnOnce(&mut Self) -> R) -> R {
+        self.scopes.push(scope);
+        let result = f(self);
+        self.scopes.pop();
+        result
+    }
+
+    /// Runs `f` with generic parameters in scope.
+    fn with_generics<R>(&mut self, generics: &[Ident], f: impl FnOnce(&mut Self) -> R) -> R {
+        let mut scope = Scope::default();
+        for (i, param) in generics.iter().enumerate() {
+            if let Some(first) = generics[..i].iter().find(|first| first.name == param.name) {
+                let kind = ResolveErrorKind::Duplicate { name: param.name, first: first.span };
+                self.error(kind, param.span);
+                continue;
+            }
+            scope.types.insert(param.name, Res::Generic(param.span));
+        }
+        self.with_scope(scope, f)
+    }
+
+    /// Runs `f` with `Self` in scope, referring to `self_def` if it is a
+    /// definition.
+    fn with_self<R>(&mut self, self_def: Option<DefId>, f: impl FnOnce(&mut Self) -> R) -> R {
+        let mut scope = Scope::default();
+        scope.types.insert(kw::SelfType, Res::SelfTy);
+        let outer = std::mem::replace(&mut self.self_def, self_def);
+        let result = self.with_scope(scope, f);
+        self.self_def = outer;
+        result
+    }
+
+    /// Binds a value in the innermost scope, shadowing any earlier binding of
+    /// the same name.
+    fn bind(&mut self, name: Ident) {
+        if name.name == sym::underscore {
+            return;
+        }
+        let scope = self.scopes.last_mut().expect("binding outside of any scope");
+        scope.values.insert(name.name, Res::Local(name.span));
+    }
+
+    /// Resolves the names used in an item.
+    fn item(&mut self, item: &Item) {
+        match &item.kind {
+            ItemKind::Import(_) => {}
+            ItemKind::Extern(block) => {
+                for inner in &block.items {
+                    self.item(inner);
+                }
+            }
+            ItemKind::Fn(decl) => self.fn_decl(decl),
+            ItemKind::Struct(decl) | ItemKind::Union(decl) => {
+                self.with_generics(&decl.generics, |this| {
+                    for field in &decl.fields {
+                        this.ty(&field.ty);
+                    }
+                })
+            }
+            ItemKind::Enum(decl) => self.with_generics(&decl.generics, |this| {
+                for variant in &decl.variants {
+                    for ty in &variant.fields {
+                        this.ty(ty);
+                    }
+                    if let Some(discriminant) = &variant.discriminant {
+                        this.expr(discriminant);
+                    }
+                }
+            }),
+            ItemKind::TypeAlias(alias) => {
+                self.with_generics(&alias.generics, |this| this.ty(&alias.ty))
+            }
+            ItemKind::Impl(block) => self.with_generics(&block.generics, |this| {
+                if let Some(trait_ref) = &block.trait_ref {
+                    this.path(trait_ref, Namespace::Type);
+                }
+                this.ty(&block.self_ty);
+                let self_def = match &block.self_ty.kind {
+                    TyKind::Path(path) => match this.res.get(path.span) {
+                        Some(Res::Def(id)) => Some(id),
+                        _ => None,
+                    },
+                    _ => None,
+                };
+                this.with_self(self_def, |this| {
+                    for inner in &block.items {
+                        this.item(inner);
+                    }
+                })
+            }),
+            ItemKind::Trait(decl) => self.with_generics(&decl.generics, |this| {
+                this.with_self(None, |this| {
+                    for inner in &decl.items {
+                        this.item(inner);
+                    }
+                })
+            }),
+        }
+    }
+
+    /// Resolves the signature and body of a function.
+    fn fn_decl(&mut self, decl: &FnDecl) {
+        self.with_generics(&decl.generics, |this| {
+            this.with_scope(Scope::default(), |this| {
+                for (i, param) in decl.params.iter().enumerate() {
+                    this.ty(&param.ty);
+                    let name = param.name;
+                    if let Some(first) = decl.params[..i].iter().find(|p| p.name.name == name.name)
+                    {
+                        let first = first.name.span;
+                        this.error(
+                            ResolveErrorKind::Duplicate { name: name.name, first },
+                            name.span,
+                        );
+                        continue;
+                    }
+                    this.bind(name);
+                }
+                if let Some(ret) = &decl.ret {
+                    this.ty(ret);
+                }
+                if let Some(body) = &decl.body {
+                    this.block(body);
+                }
+            })
+        })
+    }
+
+    /// Resolves the statements of a block in a scope of their own.
+    fn block(&mut self, block: &Block) {
+        self.with_scope(Scope::default(), |this| {
+            for stmt in &block.stmts {
+                this.stmt(stmt);
+            }
+        })
+    }
+
+    /// Resolves a statement. A local is bound after its initializer is
+    /// resolved, so `let x = x + 1` refers to the previous `x`.
+    fn stmt(&mut self, stmt: &Stmt) {
+        match &stmt.kind {
+            StmtKind::Local(local) => {
+                if let Some(ty) = &local.ty {
+                    self.ty(ty);
+                }
+                if let Some(init) = &local.init {
+                    self.expr(init);
+                }
+                self.bind(local.name);
+            }
+            StmtKind::Return(value) => {
+                if let Some(value) = value {
+                    self.expr(value);
+                }
+            }
+            StmtKind::Defer(value) | StmtKind::Expr(value) => self.expr(value),
+        }
+    }
+
+    /// Resolves the names used in an expression.
+    fn expr(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::Lit(_) | ExprKind::Continue => {}
+            ExprKind::Path(path) => {
+                self.path(path, Namespace::Value);
+            }
+            ExprKind::Unary(_, operand) | ExprKind::Paren(operand) => self.expr(operand),
+            ExprKind::Binary(_, lhs, rhs)
+            | ExprKind::Assign(lhs, rhs)
+            | ExprKind::AssignOp(_, lhs, rhs) => {
+                self.expr(lhs);
+                self.expr(rhs);
+            }
+            ExprKind::Range(lo, hi, _) => {
+                for bound in [lo, hi].into_iter().flatten() {
+                    self.expr(bound);
+                }
+            }
+            ExprKind::Call(callee, args) => {
+                self.expr(callee);
+                for arg in args {
+                    self.expr(arg);
+                }
+            }
+            ExprKind::TypeArgs(callee, args) => {
+                self.expr(callee);
+                for arg in args {
+                    self.ty(arg);
+                }
+            }
+            ExprKind::Field(base, name) => self.field(expr, base, *name),
+            ExprKind::Cast(value, ty) => {
+                self.expr(value);
+                self.ty(ty);
+            }
+            ExprKind::Block(block) | ExprKind::Loop(block) => self.block(block),
+            ExprKind::If(cond, then, els) => {
+                self.expr(cond);
+                self.block(then);
+                if let Some(els) = els {
+                    self.expr(els);
+                }
+            }
+            ExprKind::While(cond, body) => {
+                self.expr(cond);
+                self.block(body);
+            }
+            ExprKind::For(var, iter, body) => {
+                self.expr(iter);
+                self.with_scope(Scope::default(), |this| {
+                    this.bind(*var);
+                    this.block(body);
+                });
+            }
+            ExprKind::Break(value) => {
+                if let Some(value) = value {
+                    self.expr(value);
+                }
+            }
+            ExprKind::Match(scrutinee, arms) => {
+                self.expr(scrutinee);
+                for arm in arms {
+                    self.with_scope(Scope::default(), |this| {
+                        this.pat(&arm.pat);
+                        if let Some(guard) = &arm.guard {
+                            this.expr(guard);
+                        }
+                        this.expr(&arm.body);
+                    });
+                }
+            }
+            ExprKind::Struct(path, fields) => {
+                self.path(path, Namespace::Type);
+                for field in fields {
+                    self.expr(&field.expr);
+                }
+            }
+            ExprKind::SizeOf(ty) => self.ty(ty),
+        }
+    }
+
+    /// Resolves a field expression. When the base is the name of an imported
+    /// module not shadowed by a value, the field is an item of that module.
+    fn field(&mut self, expr: &Expr, base: &Expr, name: Ident) {
+        if let ExprKind::Path(path) = &base.kind {
+            if let [module] = path.segments[..] {
+                let import = self.items().modules.get(&module.name).copied();
+                if let (None, Some(import)) = (self.lookup(module.name, Namespace::Value), import) {
+                    self.res.uses.insert(path.span, Res::Module(import.module));
+                    if let Some(res) = self.member(import.module, name, Namespace::Value) {
+                        self.res.uses.insert(expr.span, res);
+                    }
+                    return;
+                }
+            }
+        }
+        self.expr(base);
+    }
+
+    /// Resolves a pattern, binding the names it introduces.
+    fn pat(&mut self, pat: &Pat) {
+        match &pat.kind {
+            PatKind::Wild => {}
+            PatKind::Lit(value) => self.expr(value),
+            PatKind::Binding(name) => self.bind(*name),
+            PatKind::Path(path) => {
+                self.path(path, Namespace::Value);
+            }
+            PatKind::TupleStruct(path, fields) => {
+                self.path(path, Namespace::Value);
+                for field in fields {
+                    self.pat(field);
+                }
+            }
+            PatKind::Or(pats) => {
+                for pat in pats {
+                    self.pat(pat);
+                }
+            }
+        }
+    }
+
+    /// Resolves the names used in a type.
+    fn ty(&mut self, ty: &Ty) {
+        match &ty.kind {
+            TyKind::Path(path) => {
+                self.path(path, Namespace::Type);
+            }
+            TyKind::Ptr(_, pointee) => self.ty(pointee),
+            TyKind::Never => {}
+            TyKind::Fn(params, ret) => {
+                for param in params {
+                    self.ty(param);
+                }
+                if let Some(ret) = ret {
+                    self.ty(ret);
+                }
+            }
+        }
+    }
+
+    /// Resolves a path in the given namespace and records the result. All
+    /// segments but the last are modules or types.
+    fn path(&mut self, path: &Path, ns: Namespace) -> Option<Res> {
+        let (&first, rest) = path.segments.split_first()?;
+        let res = if rest.is_empty() {
+            let Some(res) = self.lookup(first.name, ns) else {
+                self.undefined(first, ns);
+                return None;
+            };
+            res
+        } else {
+            let res = self.lookup(first.name, Namespace::Type).or_else(|| {
+                self.items().modules.get(&first.name).map(|import| Res::Module(import.module))
+            });
+            let Some(mut res) = res else {
+                self.undefined(first, Namespace::Type);
+                return None;
+            };
+            let mut prefix = first;
+            for (i, &segment) in rest.iter().enumerate() {
+                let segment_ns = if i + 1 == rest.len() { ns } else { Namespace::Type };
+                res = match res {
+                    Res::Module(module) => self.member(module, segment, segment_ns)?,
+                    Res::Def(parent) => self.assoc(parent, segment)?,
+                    // Functions of traits are only known once `Self` is.
+                    Res::SelfTy => self.assoc(self.self_def?, segment)?,
+                    _ => {
+                        let found = self.describe(res);
+                        let kind = ResolveErrorKind::NotContainer { name: prefix.name, found };
+                        self.error(kind, prefix.span);
+                        return None;
+                    }
+                };
+                prefix = segment;
+            }
+            res
+        };
+        self.res.uses.insert(path.span, res);
+        Some(res)
+    }
+
+    /// Looks a name up in the scopes of the current item, then the items of
+    /// the current module and the primitive types.
+    fn lookup(&self, name: Symbol, ns: Namespace) -> Option<Res> {
+        for scope in self.scopes.iter().rev() {
+            let table = match ns {
+                Namespace::Value => &scope.values,
+                Namespace::Type => &scope.types,
+            };
+            if let Some(&res) = table.get(&name) {
+                return Some(res);
+            }
+        }
+        if let Some(&id) = self.items().table(ns).get(&name) {
+            return Some(Res::Def(id));
+        }
+        match ns {
+            Namespace::Value => None,
+            Namespace::Type => PrimTy::from_name(name).map(Res::PrimTy),
+        }
+    }
+
+    /// Looks an item up in an imported module, checking it is visible from
+    /// the current module.
+    fn member(&mut self, module: ModuleId, name: Ident, ns: Namespace) -> Option<Res> {
+        let Some(&id) = self.res.items[module.index()].table(ns).get(&name.name) else {
+            let items = &self.res.items[module.index()];
+            let candidates = (items.values.values().chain(items.types.values()))
+                .map(|&id| self.res.def(id))
+                .filter(|def| def.vis == Visibility::Public || module == self.module)
+                .map(|def| def.name.name);
+            let suggestion = find_best_match(name.name, candidates);
+            let kind = ResolveErrorKind::NotFound {
+                name: name.name,
+                container: self.graph.get(module).name,
+                container_kind: "module",
+                suggestion,
+            };
+            self.error(kind, name.span);
+            return None;
+        };
+        self.check_visible(id, name.span);
+        Some(Res::Def(id))
+    }
+
+    /// Looks a variant or associated function up in a type or trait.
+    fn assoc(&mut self, parent: DefId, name: Ident) -> Option<Res> {
+        let Some(id) = self.res.assoc(parent, name.name) else {
+            let candidates = (self.res.assoc.iter())
+                .filter(|((other, _), _)| *other == parent)
+                .map(|((_, candidate), _)| *candidate);
+            let suggestion = find_best_match(name.name, candidates);
+            let def = self.res.def(parent);
+            let kind = ResolveErrorKind::NotFound {
+                name: name.name,
+                container: def.name.name,
+                container_kind: def.kind.describe(),
+                suggestion,
+            };
+            self.error(kind, name.span);
+            return None;
+        };
+        // Variants and trait functions are as visible as their parent, which
+        // is checked when the parent is resolved.
+        if self.res.def(parent).kind != DefKind::Trait && self.res.def(id).kind == DefKind::Method {
+            self.check_visible(id, name.span);
+        }
+        Some(Res::Def(id))
+    }
+
+    /// Reports a use at `span` of a private definition of another module.
+    fn check_visible(&mut self, id: DefId, span: Span) {
+        let def = *self.res.def(id);
+        if def.vis == Visibility::Private && def.module != self.module {
+            let kind = ResolveErrorKind::Private {
+                name: def.name.name,
+                kind: def.kind.describe(),
+                def_span: def.name.span,
+            };
+            self.error(kind, span);
+        }
+    }
+
+    /// Reports a name not found in the given namespace, with the closest name
+    /// in scope, or what the name is if it is in the other namespace.
+    fn undefined(&mut self, name: Ident, ns: Namespace) {
+        let other = match ns {
+            Namespace::Value => Namespace::Type,
+            Namespace::Type => Namespace::Value,
+        };
+        let found = match self.lookup(name.name, other) {
+            Some(res) => Some(self.describe(res)),
+            None => self.items().modules.get(&name.name).map(|_| "module"),
+        };
+        if let Some(found) = found {
+            let kind = ResolveErrorKind::WrongNamespace { name: name.name, expected: ns, found };
+            self.error(kind, name.span);
+            return;
+        }
+
+        let mut candidates: Vec<Symbol> = Vec::new();
+        for scope in &self.scopes {
+            let table = match ns {
+                Namespace::Value => &scope.values,
+                Namespace::Type => &scope.types,
+            };
+            candidates.extend(table.keys());
+        }
+        candidates.extend(self.items().table(ns).keys());
+        candidates.extend(self.items().modules.keys());
+        if ns == Namespace::Type {
+            candidates.extend(PrimTy::ALL.map(PrimTy::name));
+        }
+        let suggestion = find_best_match(name.name, candidates);
+        self.error(ResolveErrorKind::Undefined { name: name.name, ns, suggestion }, name.span);
+    }
+
+    /// Returns a short description of what a name refers to.
+    fn describe(&self, res: Res) -> &'static str {
+        match res {
+            Res::Def(id) => self.res.def(id).kind.describe(),
+            Res::Local(_) => "local variable",
+            Res::Generic(_) => "type parameter",
+            Res::PrimTy(_) => "primitive type",
+            Res::SelfTy => "`Self` type",
+            Res::Module(_) => "module",
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use crate::diag::Diagnostic;
+use std::{fmt, io};
+
+/// LoadError is an error found while loading a source file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadError {
+    /// Path of the file as given to the source map.
+    pub path: String,
+    pub kind: LoadErrorKind,
+}
+
+/// LoadErrorKind is the kind of load error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoadErrorKind {
+    /// The file does not exist.
+    NotFound,
+    /// The file cannot be read by the current user.
+    PermissionDenied,
+    /// The path is a directory.
+    IsDirectory,
+    /// The content is not valid UTF-8, starting at the given byte offset on
+    /// the given line.
+    NotUtf8 { offset: usize, line: usize },
+    /// Any other I/O error.
+    Other(String),
+}
+
+impl LoadError {
+    /// Creates a new load error.
+    pub fn new(path: &str, kind: LoadErrorKind) -> LoadError {
+        LoadError { path: path.to_string(), kind }
+    }
+
+    /// Creates a load error from an I/O error.
+    pub fn from_io(path: &str, err: &io::Error) -> LoadError {
+        let kind = match err.kind() {
+            io::ErrorKind::NotFound => LoadErrorKind::NotFound,
+            io::ErrorKind::PermissionDenied => LoadErrorKind::PermissionDenied,
+            io::ErrorKind::IsADirectory => LoadErrorKind::IsDirectory,
+            _ => LoadErrorKind::Other(err.to_string()),
+        };
+        LoadError::new(path, kind)
+    }
+
+    /// Converts the error into a diagnostic.
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let code = match self.kind {
+            LoadErrorKind::NotFound => "E0020",
+            LoadErrorKind::PermissionDenied => "E0021",
+            LoadErrorKind::IsDirectory => "E0022",
+            LoadErrorKind::NotUtf8 { .. } => "E0023",
+            LoadErrorKind::Other(_) => "E0024",
+        };
+        let diag = Diagnostic::error(self.to_string()).code(code);
+        match self.kind {
+            LoadErrorKind::NotUtf8 { .. } => diag.note("source files must be encoded as UTF-8"),
+            _ => diag,
+        }
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            LoadErrorKind::NotFound => write!(f, "couldn't find `{}`", self.path),
+            LoadErrorKind::PermissionDenied => {
+                write!(f, "couldn't read `{}`: permission denied", self.path)
+            }
+            LoadErrorKind::IsDirectory => write!(f, "`{}` is a directory", self.path),
+            LoadErrorKind::NotUtf8 { offset, line } => write!(
+                f,
+                "`{}` is not valid UTF-8: invalid byte at offset {} (line {})",
+                self.path, offset, line
+            ),
+            LoadErrorKind::Other(err) => write!(f, "couldn't read `{}`: {}", self.path, err),
+        }
+    }
+}
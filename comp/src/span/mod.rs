@@ -1,3 +1,111 @@
+use self::error::{LoadError, LoadErrorKind};
+use std::{fs, path::Path, rc::Rc};
+
+pub mod error;
+
+/// SourceMap owns every source file of a compilation. Each file is assigned
+/// a distinct range of global byte offsets, so a `Span` alone identifies the
+/// file it belongs to.
+#[derive(Default)]
+pub struct SourceMap {
+    /// Loaded files, ordered by their start offset.
+    files: Vec<Rc<SourceFile>>,
+}
+
+impl SourceMap {
+    /// Creates an empty source map.
+    pub fn new() -> SourceMap {
+        SourceMap { files: Vec::new() }
+    }
+
+    /// Loads the file at the given path.
+    pub fn load_file(&mut self, path: &str) -> Result<Rc<SourceFile>, LoadError> {
+        // Reading a directory does not fail on every platform, so check first.
+        if fs::metadata(path).is_ok_and(|meta| meta.is_dir()) {
+            return Err(LoadError::new(path, LoadErrorKind::IsDirectory));
+        }
+
+        let bytes = fs::read(path).map_err(|err| LoadError::from_io(path, &err))?;
+        let content = String::from_utf8(bytes).map_err(|err| {
+            let offset = err.utf8_error().valid_up_to();
+            let valid = String::from_utf8_lossy(&err.as_bytes()[..offset]);
+            let line = line_starts(&valid).len();
+            LoadError::new(path, LoadErrorKind::NotUtf8 { offset, line })
+        })?;
+
+        Ok(self.add_file(path, content))
+    }
+
+    /// Adds an in-memory file, e.g. an unsaved editor buffer or a test input.
+    /// `path` is only used for display and does not have to exist.
+    pub fn add_virtual_file(&mut self, path: &str, content: &str) -> Rc<SourceFile> {
+        self.add_file(path, content.to_string())
+    }
+
+    /// Adds a file with the given content after the last file.
+    fn add_file(&mut self, path: &str, content: String) -> Rc<SourceFile> {
+        // Leave a gap of one byte so the end of a file is never the start of
+        // the next one.
+        let start = self.files.last().map_or(0, |file| file.end() + 1);
+        let file = Rc::new(SourceFile::new(path, content, start));
+        self.files.push(file.clone());
+        file
+    }
+
+    /// Returns all loaded files.
+    pub fn files(&self) -> &[Rc<SourceFile>] {
+        &self.files
+    }
+
+    /// Returns the file containing the global byte offset `pos`.
+    pub fn lookup_file(&self, pos: usize) -> Option<&Rc<SourceFile>> {
+        let idx = self.files.partition_point(|file| file.start <= pos).checked_sub(1)?;
+        let file = &self.files[idx];
+        if pos <= file.end() { Some(file) } else { None }
+    }
+
+    /// Returns the file, line and column of the global byte offset `pos`.
+    pub fn lookup(&self, pos: usize) -> Option<(&Rc<SourceFile>, LineCol)> {
+        let file = self.lookup_file(pos)?;
+        Some((file, file.line_col(pos)))
+    }
+
+    /// Returns the source text covered by the span.
+    pub fn snippet(&self, span: Span) -> Option<&str> {
+        Some(self.lookup_file(span.lo)?.snippet(span))
+    }
+}
+
+/// Byte order mark, skipped at the start of a file.
+pub const BOM: char = '\u{feff}';
+
+/// Checks whether the character ends a line. A `\r` directly followed by
+/// `\n` is part of a single `\r\n` line break.
+pub fn is_line_break(c: char) -> bool {
+    matches!(c, '\n' | '\r' | '\u{85}' | '\u{2028}' | '\u{2029}')
+}
+
+/// Checks whether the character is a Unicode bidirectional control
+/// character, the ones used by "Trojan Source" attacks.
+pub fn is_bidi_control(c: char) -> bool {
+    matches!(c, '\u{202a}'..='\u{202e}' | '\u{2066}'..='\u{2069}')
+}
+
+/// Returns the byte offsets of the start of every line in the text.
+fn line_starts(text: &str) -> Vec<usize> {
+    let mut lines = vec![0];
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if c == '\r' && chars.peek().is_some_and(|(_, next)| *next == '\n') {
+            continue;
+        }
+        if is_line_break(c) {
+            lines.push(i + c.len_utf8());
+        }
+    }
+    lines
+}
+
 /// Source file information.
 pub struct SourceFile {
     /// Name of the source file.
@@ -6,43 +114,147 @@ pub struct SourceFile {
     pub path: String,
     /// Content of the source file.
     pub content: String,
-    /// Position of the lines in the source file.
+    /// Byte offsets, relative to `start`, of the start of every line in the
+    /// source file. Lines end with `\n`, `\r\n` or any other character
+    /// accepted by `is_line_break`.
     pub lines: Vec<usize>,
+    /// Global byte offset of the first byte of the file in its source map.
+    pub start: usize,
 }
 
 impl SourceFile {
-    /// Creates a new source file from the given path.
-    pub fn new(path: &str) -> SourceFile {
-        let content = std::fs::read_to_string(path).unwrap_or_else(|err| {
-            println!("Error reading file: {}", err);
-            return "".to_string();
-        });
-
-        let mut lines = Vec::new();
-        lines.push(0);
-
-        for (i, c) in content.chars().enumerate() {
-            if c == '\n' {
-                lines.push(i + 1);
-            }
+    /// Creates a new source file starting at the global offset `start`.
+    fn new(path: &str, content: String, start: usize) -> SourceFile {
+        let lines = line_starts(&content);
+
+        // Paths like `..` have no file name, fall back to the whole path.
+        let name = match Path::new(path).file_name() {
+            Some(name) => name.to_string_lossy().to_string(),
+            None => path.to_string(),
+        };
+
+        SourceFile { name, path: path.to_string(), content, lines, start }
+    }
+
+    /// Returns the global byte offset just past the last byte of the file.
+    pub fn end(&self) -> usize {
+        self.start + self.content.len()
+    }
+
+    /// Returns the index of the line containing the content offset `pos`,
+    /// starting from 0.
+    fn line_index(&self, pos: usize) -> usize {
+        match self.lines.binary_search(&pos) {
+            Ok(line) => line,
+            Err(line) => line - 1,
+        }
+    }
+
+    /// Returns the content offset of the first character of the line with the
+    /// given index. A byte order mark is not part of the first line, as
+    /// editors do not show it.
+    fn line_start(&self, line: usize) -> usize {
+        match line {
+            0 if self.content.starts_with(BOM) => BOM.len_utf8(),
+            _ => self.lines[line],
+        }
+    }
+
+    /// Converts the global byte offset `pos` to an offset into the content,
+    /// rounded down to the nearest character boundary.
+    fn floor_char_boundary(&self, pos: usize) -> usize {
+        let mut pos = pos.saturating_sub(self.start).min(self.content.len());
+        while !self.content.is_char_boundary(pos) {
+            pos -= 1;
         }
+        pos
+    }
+
+    /// Returns the line and column of the global byte offset `pos`. Columns
+    /// count characters, not bytes.
+    pub fn line_col(&self, pos: usize) -> LineCol {
+        let pos = self.floor_char_boundary(pos);
+        let line = self.line_index(pos);
+        let col = self.content[self.line_start(line).min(pos)..pos].chars().count();
+        LineCol { line: line + 1, col: col + 1 }
+    }
 
-        let name = std::path::Path::new(path).file_name().unwrap().to_str().unwrap().to_string();
+    /// Returns the column of the global byte offset `pos` counted in UTF-16 code
+    /// units, as used by editors speaking the language server protocol.
+    pub fn utf16_col(&self, pos: usize) -> usize {
+        let pos = self.floor_char_boundary(pos);
+        let start = self.line_start(self.line_index(pos)).min(pos);
+        self.content[start..pos].chars().map(char::len_utf16).sum::<usize>() + 1
+    }
+
+    /// Returns the text of the given line without its line terminator, or
+    /// `None` if the line does not exist.
+    pub fn line_text(&self, line: usize) -> Option<&str> {
+        let index = line.checked_sub(1)?;
+        self.lines.get(index)?;
+        let start = self.line_start(index);
+        let end = self.lines.get(line).copied().unwrap_or(self.content.len());
+        Some(self.content[start..end].trim_end_matches(is_line_break))
+    }
+
+    /// Returns the source text covered by the span.
+    pub fn snippet(&self, span: Span) -> &str {
+        &self.content[self.floor_char_boundary(span.lo)..self.floor_char_boundary(span.hi)]
+    }
 
-        SourceFile { name, path: path.to_string(), content, lines }
+    /// Returns a new version of the file with the text covered by `span`
+    /// replaced by `text`, along with the edit to pass to `Lexer::relex`. The
+    /// new version starts at the same offset and is not part of any source
+    /// map.
+    pub fn edit(&self, span: Span, text: &str) -> (SourceFile, Edit) {
+        let lo = self.floor_char_boundary(span.lo);
+        let hi = self.floor_char_boundary(span.hi);
+        let content = format!("{}{}{}", &self.content[..lo], text, &self.content[hi..]);
+        let lo = self.start + lo;
+        let edit = Edit { lo, old_hi: self.start + hi, new_hi: lo + text.len() };
+        (SourceFile::new(&self.path, content, self.start), edit)
     }
 
     /// Returns corresponding span of the end of file.
     pub fn eof_span(&self) -> Span {
-        let lo = self.content.len();
-        Span::new(lo, lo)
+        Span::new(self.end(), self.end())
+    }
+}
+
+/// Edit describes a change between two versions of a source file starting at
+/// the same offset: the text at `lo..old_hi` in the old version was replaced
+/// by the text at `lo..new_hi` in the new one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Edit {
+    /// Global byte offset of the start of the change.
+    pub lo: usize,
+    /// End of the replaced text in the old version.
+    pub old_hi: usize,
+    /// End of the inserted text in the new version.
+    pub new_hi: usize,
+}
+
+impl Edit {
+    /// Maps an offset at or after `old_hi` in the old version to the new
+    /// version.
+    pub fn shift(self, pos: usize) -> usize {
+        pos - self.old_hi + self.new_hi
     }
 }
 
-/// Span of the source file.
+/// Line and column of a position in a source file, both starting from 1.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineCol {
+    pub line: usize,
+    pub col: usize,
+}
+
+/// Span of a source file, as global byte offsets into its source map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Span {
+    /// Byte offset of the start of the span.
     pub lo: usize,
+    /// Byte offset of the end of the span, exclusive.
     pub hi: usize,
 }
 
@@ -51,4 +263,9 @@ impl Span {
     pub fn new(lo: usize, hi: usize) -> Span {
         Span { lo, hi }
     }
+
+    /// Returns a span covering both `self` and `other`.
+    pub fn to(self, other: Span) -> Span {
+        Span::new(self.lo.min(other.lo), self.hi.max(other.hi))
+    }
 }
@@ -0,0 +1,57 @@
+//! Edit distance between names, used to suggest a close name when a name is
+//! not found.
+
+use super::Symbol;
+
+/// Returns the optimal string alignment distance between two strings,
+/// counting characters: the Levenshtein distance where swapping two adjacent
+/// characters, a common typo, is a single edit.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // Distances from the prefixes of `a` ending two characters, one character
+    // and zero characters before the current one to every prefix of `b`.
+    let mut prev: Vec<usize> = Vec::new();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for i in 0..a.len() {
+        let mut next = vec![i + 1; b.len() + 1];
+        for j in 0..b.len() {
+            next[j + 1] =
+                if a[i] == b[j] { row[j] } else { 1 + row[j].min(row[j + 1]).min(next[j]) };
+            if i > 0 && j > 0 && a[i] == b[j - 1] && a[i - 1] == b[j] {
+                next[j + 1] = next[j + 1].min(prev[j - 1] + 1);
+            }
+        }
+        prev = std::mem::replace(&mut row, next);
+    }
+    row[b.len()]
+}
+
+/// Returns the candidate closest to `name`, if one is close enough to be a
+/// likely typo: a case-insensitive match, or one within a third of the
+/// length of `name` that keeps some of its characters. Ties go to the
+/// alphabetically first candidate so the result does not depend on the order
+/// of the candidates.
+pub fn find_best_match(
+    name: Symbol,
+    candidates: impl IntoIterator<Item = Symbol>,
+) -> Option<Symbol> {
+    let name = name.as_str();
+    let len = name.chars().count();
+    let max = (len / 3).max(1);
+    let mut best: Option<(usize, &str, Symbol)> = None;
+    for candidate in candidates {
+        let s = candidate.as_str();
+        if s == name {
+            continue;
+        }
+        let distance = if s.eq_ignore_ascii_case(name) { 0 } else { edit_distance(name, s) };
+        if distance > max || distance >= len {
+            continue;
+        }
+        if best.is_none_or(|(best, best_s, _)| (distance, s) < (best, best_s)) {
+            best = Some((distance, s, candidate));
+        }
+    }
+    best.map(|(_, _, candidate)| candidate)
+}
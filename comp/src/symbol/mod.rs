@@ -0,0 +1,214 @@
+//! Interned strings shared by the whole compiler.
+//!
+//! Identifiers, literals and names are interned once into a global table and
+//! referred to by `Symbol`, a `u32` handle, so later stages compare names
+//! without touching strings. Keywords and names the compiler looks for, e.g.
+//! `main`, are interned up front and available as constants in `kw` and
+//! `sym`.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{LazyLock, Mutex},
+};
+
+/// Symbol is an interned string. Symbols are cheap to copy and compare, and
+/// two symbols are equal exactly when their strings are.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(u32);
+
+pub mod edit_distance;
+
+/// Declares the pre-interned symbols. Keywords come first so `is_keyword` is
+/// a single comparison.
+macro_rules! symbols {
+    (
+        keywords { $($kw:ident: $kw_str:literal,)* }
+        symbols { $($sym:ident: $sym_str:literal,)* }
+    ) => {
+        /// Index of every pre-interned symbol.
+        #[allow(non_camel_case_types)]
+        #[repr(u32)]
+        enum Predefined {
+            $($kw,)*
+            $($sym,)*
+            /// Number of pre-interned symbols.
+            Count,
+        }
+
+        /// Strings of the pre-interned symbols, in the order of `Predefined`.
+        const PREDEFINED: [&str; Predefined::Count as usize] = [$($kw_str,)* $($sym_str,)*];
+
+        /// Number of keywords, they are the first pre-interned symbols.
+        const KEYWORDS: u32 = [$($kw_str),*].len() as u32;
+
+        /// Keywords of the language.
+        pub mod kw {
+            use super::{Predefined, Symbol};
+
+            $(
+                #[doc = concat!("`", $kw_str, "`")]
+                #[allow(non_upper_case_globals)]
+                pub const $kw: Symbol = Symbol(Predefined::$kw as u32);
+            )*
+        }
+
+        /// Names with a special meaning to the compiler.
+        pub mod sym {
+            use super::{Predefined, Symbol};
+
+            $(
+                #[doc = concat!("`", $sym_str, "`")]
+                #[allow(non_upper_case_globals)]
+                pub const $sym: Symbol = Symbol(Predefined::$sym as u32);
+            )*
+        }
+    };
+}
+
+symbols! {
+    keywords {
+        Module: "module",
+        Import: "import",
+        Extern: "extern",
+        Pub: "pub",
+        Fn: "fn",
+        Let: "let",
+        Const: "const",
+        Mut: "mut",
+        If: "if",
+        Else: "else",
+        Return: "return",
+        True: "true",
+        False: "false",
+        As: "as",
+        While: "while",
+        For: "for",
+        In: "in",
+        Loop: "loop",
+        Break: "break",
+        Continue: "continue",
+        Struct: "struct",
+        Enum: "enum",
+        Union: "union",
+        Match: "match",
+        Type: "type",
+        Impl: "impl",
+        Trait: "trait",
+        SelfValue: "self",
+        SelfType: "Self",
+        Null: "null",
+        Defer: "defer",
+        Sizeof: "sizeof",
+    }
+    symbols {
+        main: "main",
+        _start: "_start",
+        link: "link",
+        std: "std",
+        underscore: "_",
+        dotdot: "..",
+        inline: "inline",
+        noinline: "noinline",
+        export: "export",
+        extern_name: "extern_name",
+        deprecated: "deprecated",
+        cfg: "cfg",
+        test: "test",
+        align: "align",
+        packed: "packed",
+        not: "not",
+        all: "all",
+        any: "any",
+        target_os: "target_os",
+        target_arch: "target_arch",
+        target_family: "target_family",
+        unix: "unix",
+        windows: "windows",
+        i8: "i8",
+        i16: "i16",
+        i32: "i32",
+        i64: "i64",
+        isize: "isize",
+        u8: "u8",
+        u16: "u16",
+        u32: "u32",
+        u64: "u64",
+        usize: "usize",
+        f32: "f32",
+        f64: "f64",
+        bool: "bool",
+        void: "void",
+    }
+}
+
+impl Symbol {
+    /// Interns the string and returns its symbol.
+    pub fn intern(s: &str) -> Symbol {
+        INTERNER.lock().unwrap().intern(s)
+    }
+
+    /// Returns the interned string.
+    pub fn as_str(self) -> &'static str {
+        INTERNER.lock().unwrap().strings[self.0 as usize]
+    }
+
+    /// Returns the index of the symbol in the interner.
+    pub fn as_u32(self) -> u32 {
+        self.0
+    }
+
+    /// Checks whether the symbol is a keyword of the language.
+    pub fn is_keyword(self) -> bool {
+        self.0 < KEYWORDS
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Interner maps strings to symbols and back.
+struct Interner {
+    /// Symbol of every interned string.
+    symbols: HashMap<&'static str, Symbol>,
+    /// Interned strings, indexed by symbol.
+    strings: Vec<&'static str>,
+}
+
+impl Interner {
+    /// Creates an interner holding the pre-interned symbols.
+    fn new() -> Interner {
+        let mut interner = Interner { symbols: HashMap::new(), strings: Vec::new() };
+        for s in PREDEFINED {
+            interner.symbols.insert(s, Symbol(interner.strings.len() as u32));
+            interner.strings.push(s);
+        }
+        interner
+    }
+
+    /// Interns the string, allocating it the first time it is seen.
+    fn intern(&mut self, s: &str) -> Symbol {
+        if let Some(&sym) = self.symbols.get(s) {
+            return sym;
+        }
+        // Interned strings live as long as the compiler, so leaking them is
+        // what lets `as_str` hand out `'static` references.
+        let s: &'static str = Box::leak(s.into());
+        let sym = Symbol(self.strings.len() as u32);
+        self.strings.push(s);
+        self.symbols.insert(s, sym);
+        sym
+    }
+}
+
+/// The interner shared by the whole compiler.
+static INTERNER: LazyLock<Mutex<Interner>> = LazyLock::new(|| Mutex::new(Interner::new()));
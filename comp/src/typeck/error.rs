@@ -0,0 +1,322 @@
+use super::ty::{FloatTy, Ty};
+use crate::{
+    ast::{BinOp, UnOp},
+    diag::Diagnostic,
+    span::Span,
+    symbol::Symbol,
+};
+use std::fmt;
+
+/// TypeError is an expression or declaration that does not type check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeError {
+    pub kind: TypeErrorKind,
+    pub span: Span,
+}
+
+impl TypeError {
+    /// Creates a new type error.
+    pub fn new(kind: TypeErrorKind, span: Span) -> TypeError {
+        TypeError { kind, span }
+    }
+
+    /// Converts the error into a diagnostic.
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        if let TypeErrorKind::Instantiated { error, instance } = &self.kind {
+            return error
+                .to_diagnostic()
+                .secondary(self.span, format!("`{}` instantiated here", instance))
+                .note(format!(
+                    "the error is in `{}`, an instance of a generic function",
+                    instance
+                ));
+        }
+        let (code, label) = match &self.kind {
+            TypeErrorKind::Mismatch { expected, found, .. } => {
+                ("E0500", format!("expected `{}`, found `{}`", expected, found))
+            }
+            TypeErrorKind::ArgCount { expected, .. } => {
+                ("E0501", format!("expected {}", plural(*expected, "argument")))
+            }
+            TypeErrorKind::NotCallable(_) => ("E0502", "not a function".to_string()),
+            TypeErrorKind::InvalidBinary { .. } => ("E0503", "invalid operands".to_string()),
+            TypeErrorKind::InvalidUnary { .. } => ("E0504", "invalid operand".to_string()),
+            TypeErrorKind::NoField { .. } => ("E0505", "unknown field".to_string()),
+            TypeErrorKind::PrivateField { .. } => ("E0506", "private field".to_string()),
+            TypeErrorKind::InvalidCast { .. } => ("E0507", "invalid cast".to_string()),
+            TypeErrorKind::MissingReturn { .. } => ("E0508", "reaches the end here".to_string()),
+            TypeErrorKind::AnnotationNeeded { .. } => ("E0509", "type unknown".to_string()),
+            TypeErrorKind::NotAType { .. } => ("E0510", "not a type".to_string()),
+            TypeErrorKind::AliasCycle { .. } => ("E0511", "refers to itself".to_string()),
+            TypeErrorKind::OutsideLoop(_) => ("E0512", "outside of a loop".to_string()),
+            TypeErrorKind::BreakWithValue => ("E0513", "value not allowed here".to_string()),
+            TypeErrorKind::NotIterable(_) => ("E0514", "not a range".to_string()),
+            TypeErrorKind::RangeOutsideFor => ("E0515", "not a `for` loop iterator".to_string()),
+            TypeErrorKind::MissingFields { .. } => ("E0516", "missing fields".to_string()),
+            TypeErrorKind::UnionInit { .. } => ("E0517", "expected one field".to_string()),
+            TypeErrorKind::NotAssignable => ("E0518", "cannot assign to this".to_string()),
+            TypeErrorKind::FieldCount { expected, .. } => {
+                ("E0519", format!("expected {}", plural(*expected, "field")))
+            }
+            TypeErrorKind::LitOutOfRange { ty } => ("E0520", format!("does not fit in `{}`", ty)),
+            TypeErrorKind::InvalidVariadicArg { .. } => {
+                ("E0521", "cannot be passed to `...`".to_string())
+            }
+            TypeErrorKind::TypeArgCount { expected, .. } => {
+                ("E0522", format!("expected {}", plural(*expected, "type argument")))
+            }
+            TypeErrorKind::CannotInfer { .. } => ("E0523", "type arguments unknown".to_string()),
+            TypeErrorKind::InstanceDepth { .. } => ("E0524", "instantiated here".to_string()),
+            TypeErrorKind::Immutable { .. } => ("E0525", "cannot assign".to_string()),
+            TypeErrorKind::UnsupportedGenerics { .. } => ("E0526", "not supported".to_string()),
+            TypeErrorKind::Instantiated { .. } => unreachable!("converted above"),
+        };
+
+        let diag = Diagnostic::error(self.kind.to_string()).code(code).primary(self.span, label);
+        match &self.kind {
+            TypeErrorKind::Mismatch { expected, found, origin } => {
+                let diag = match origin {
+                    Some(origin) => diag.secondary(*origin, "expected because of this"),
+                    None => diag,
+                };
+                match (expected, found) {
+                    (Ty::Ptr(..), Ty::Str) => {
+                        diag.help("use a C string literal, `c\"...\"`, for a NUL-terminated string")
+                    }
+                    _ => diag,
+                }
+            }
+            TypeErrorKind::ArgCount { def_span: Some(def_span), .. } => {
+                diag.secondary(*def_span, "defined here")
+            }
+            TypeErrorKind::InvalidBinary { op, .. } => diag.note(binary_requirement(*op)),
+            TypeErrorKind::MissingReturn { ret_span, .. } => {
+                diag.secondary(*ret_span, "return type declared here")
+            }
+            TypeErrorKind::AnnotationNeeded { name } => {
+                diag.help(format!("give `{}` a type, e.g. `let {}: i32`", name, name))
+            }
+            TypeErrorKind::LitOutOfRange { ty } => match ty {
+                Ty::Int(int) => {
+                    diag.note(format!("`{}` ranges from {} to {}", ty, int.min(), int.max()))
+                }
+                Ty::Float(FloatTy::F32) => {
+                    diag.note(format!("`f32` ranges from {:e} to {:e}", f32::MIN, f32::MAX))
+                }
+                _ => diag.note(format!("`{}` ranges from {:e} to {:e}", ty, f64::MIN, f64::MAX)),
+            },
+            TypeErrorKind::InvalidVariadicArg { .. } => {
+                diag.note("only numbers, `bool` and pointers can be passed to `...`")
+            }
+            TypeErrorKind::CannotInfer { name, params } => {
+                let params: Vec<_> = params.iter().map(|param| param.as_str()).collect();
+                diag.help(format!("specify the type arguments: `{}[{}]`", name, params.join(", ")))
+            }
+            TypeErrorKind::Immutable { name, binding } => diag
+                .secondary(*binding, "not declared with `let mut`")
+                .help(format!("to assign it, declare it with `let mut {}`", name)),
+            TypeErrorKind::UnsupportedGenerics { .. } => diag.help(
+                "only functions outside of impl blocks, traits and extern blocks can be generic",
+            ),
+            TypeErrorKind::InstanceDepth { .. } => diag.note(
+                "a generic function calling itself with other type arguments has infinitely many instances",
+            ),
+            _ => diag,
+        }
+    }
+}
+
+/// Returns `n thing` or `n things`.
+fn plural(n: usize, thing: &str) -> String {
+    if n == 1 { format!("1 {}", thing) } else { format!("{} {}s", n, thing) }
+}
+
+/// Returns what the operands of a binary operator must be.
+fn binary_requirement(op: BinOp) -> String {
+    let operands = match op {
+        BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div | BinOp::Rem => {
+            "two integers or two floats of the same type"
+        }
+        BinOp::BitXor | BinOp::BitAnd | BinOp::BitOr => {
+            "two integers or two bools of the same type"
+        }
+        BinOp::Shl | BinOp::Shr => "an integer and an integer shift amount",
+        BinOp::Eq | BinOp::Ne => "two numbers, bools or pointers of the same type",
+        BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge => "two numbers or pointers of the same type",
+        BinOp::And | BinOp::Or => "two bools",
+    };
+    format!("`{}` takes {}", op.as_str(), operands)
+}
+
+/// TypeErrorKind is the kind of type error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeErrorKind {
+    /// A value of the wrong type, with the span of what set the expected
+    /// type, e.g. a parameter or a `let` annotation.
+    Mismatch { expected: Ty, found: Ty, origin: Option<Span> },
+    /// A call with the wrong number of arguments.
+    ArgCount { expected: usize, found: usize, variadic: bool, def_span: Option<Span> },
+    /// A call of a value that is not a function.
+    NotCallable(Ty),
+    /// A binary operator applied to operands it does not support.
+    InvalidBinary { op: BinOp, lhs: Ty, rhs: Ty },
+    /// A unary operator applied to an operand it does not support.
+    InvalidUnary { op: UnOp, ty: Ty },
+    /// A field that the type does not have.
+    NoField { ty: Ty, name: Symbol },
+    /// A field that is not `pub` accessed from another module.
+    PrivateField { ty: Ty, name: Symbol },
+    /// A cast between types that cannot be converted.
+    InvalidCast { from: Ty, to: Ty },
+    /// A function with a return type whose body can end without returning.
+    MissingReturn { name: Symbol, ret: Ty, ret_span: Span },
+    /// A `let` without a type or an initializer.
+    AnnotationNeeded { name: Symbol },
+    /// A name used as a type that is not one, e.g. a trait.
+    NotAType { name: Symbol, found: &'static str },
+    /// A type alias defined in terms of itself.
+    AliasCycle { name: Symbol },
+    /// `break` or `continue` outside of a loop.
+    OutsideLoop(&'static str),
+    /// `break` with a value out of a `while` or `for` loop.
+    BreakWithValue,
+    /// A `for` loop over something else than a range.
+    NotIterable(Ty),
+    /// A range anywhere else than as the iterator of a `for` loop.
+    RangeOutsideFor,
+    /// A struct initializer without all the fields of the struct.
+    MissingFields { ty: Ty, names: Vec<Symbol> },
+    /// A union initializer that does not set exactly one field.
+    UnionInit { ty: Ty },
+    /// An assignment to something else than a local or a field.
+    NotAssignable,
+    /// An assignment to a binding not declared with `let mut`, or to one of
+    /// its fields, with the span of the binding.
+    Immutable { name: Symbol, binding: Span },
+    /// A variant pattern with the wrong number of fields.
+    FieldCount { variant: Symbol, expected: usize, found: usize },
+    /// A number literal that does not fit in its type.
+    LitOutOfRange { ty: Ty },
+    /// An argument passed to the `...` of a variadic function whose type C
+    /// cannot take there.
+    InvalidVariadicArg { ty: Ty },
+    /// Type arguments given to a function with another number of generic
+    /// parameters, or to something else than a generic function.
+    TypeArgCount { expected: usize, found: usize },
+    /// A use of a generic function whose type arguments are not given and
+    /// cannot be inferred, with its generic parameters.
+    CannotInfer { name: Symbol, params: Vec<Symbol> },
+    /// An instance of a generic function needed by instances nested too
+    /// deep, with the limit.
+    InstanceDepth { name: Symbol, limit: usize },
+    /// Generic parameters on another item than a function outside of any
+    /// block, with a description of the item in the plural.
+    UnsupportedGenerics { item: &'static str },
+    /// An error in the body of an instance of a generic function, reported
+    /// at the use that instantiated it.
+    Instantiated { error: Box<TypeError>, instance: String },
+}
+
+impl fmt::Display for TypeErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeErrorKind::Mismatch { .. } => write!(f, "mismatched types"),
+            TypeErrorKind::ArgCount { expected, found, variadic, .. } => write!(
+                f,
+                "function takes {}{} but {} supplied",
+                if *variadic { "at least " } else { "" },
+                plural(*expected, "argument"),
+                if *found == 1 { "1 was".to_string() } else { format!("{} were", found) }
+            ),
+            TypeErrorKind::NotCallable(ty) => write!(f, "expected function, found `{}`", ty),
+            TypeErrorKind::InvalidBinary { op, lhs, rhs } => {
+                write!(f, "cannot apply `{}` to `{}` and `{}`", op.as_str(), lhs, rhs)
+            }
+            TypeErrorKind::InvalidUnary { op, ty } => {
+                write!(f, "cannot apply unary `{}` to `{}`", op.as_str(), ty)
+            }
+            TypeErrorKind::NoField { ty, name } => {
+                write!(f, "no field `{}` on type `{}`", name, ty)
+            }
+            TypeErrorKind::PrivateField { ty, name } => {
+                write!(f, "field `{}` of `{}` is private", name, ty)
+            }
+            TypeErrorKind::InvalidCast { from, to } => {
+                write!(f, "cannot cast `{}` to `{}`", from, to)
+            }
+            TypeErrorKind::MissingReturn { name, ret: Ty::Never, .. } => write!(
+                f,
+                "function `{}` is declared to never return but can reach the end of its body",
+                name
+            ),
+            TypeErrorKind::MissingReturn { name, ret, .. } => write!(
+                f,
+                "function `{}` can reach the end of its body without returning a value of type `{}`",
+                name, ret
+            ),
+            TypeErrorKind::AnnotationNeeded { name } => {
+                write!(f, "type annotations needed for `{}`", name)
+            }
+            TypeErrorKind::NotAType { name, found } => {
+                write!(f, "expected type, found {} `{}`", found, name)
+            }
+            TypeErrorKind::AliasCycle { name } => {
+                write!(f, "type alias `{}` refers to itself", name)
+            }
+            TypeErrorKind::OutsideLoop(keyword) => write!(f, "`{}` outside of a loop", keyword),
+            TypeErrorKind::BreakWithValue => {
+                write!(f, "`break` with a value is only allowed in `loop`")
+            }
+            TypeErrorKind::NotIterable(ty) => {
+                write!(f, "`for` loops iterate over ranges, found `{}`", ty)
+            }
+            TypeErrorKind::RangeOutsideFor => {
+                write!(f, "ranges can only be used as the iterator of a `for` loop")
+            }
+            TypeErrorKind::MissingFields { ty, names } => {
+                let names: Vec<_> = names.iter().map(|name| format!("`{}`", name)).collect();
+                write!(f, "missing fields {} in initializer of `{}`", names.join(", "), ty)
+            }
+            TypeErrorKind::UnionInit { ty } => {
+                write!(f, "initializer of union `{}` must set exactly one field", ty)
+            }
+            TypeErrorKind::NotAssignable => write!(f, "invalid left-hand side of assignment"),
+            TypeErrorKind::Immutable { name, .. } => {
+                write!(f, "cannot assign to immutable binding `{}`", name)
+            }
+            TypeErrorKind::FieldCount { variant, expected, found } => write!(
+                f,
+                "this pattern has {}, but variant `{}` has {}",
+                plural(*found, "field"),
+                variant,
+                plural(*expected, "field")
+            ),
+            TypeErrorKind::LitOutOfRange { ty } => write!(f, "literal out of range for `{}`", ty),
+            TypeErrorKind::InvalidVariadicArg { ty } => {
+                write!(f, "cannot pass a value of type `{}` to a variadic function", ty)
+            }
+            TypeErrorKind::TypeArgCount { expected: 0, .. } => {
+                write!(f, "type arguments given to something else than a generic function")
+            }
+            TypeErrorKind::TypeArgCount { expected, found } => write!(
+                f,
+                "function takes {} but {} supplied",
+                plural(*expected, "type argument"),
+                if *found == 1 { "1 was".to_string() } else { format!("{} were", found) }
+            ),
+            TypeErrorKind::CannotInfer { name, .. } => {
+                write!(f, "cannot infer the type arguments of `{}`", name)
+            }
+            TypeErrorKind::InstanceDepth { name, limit } => write!(
+                f,
+                "reached the limit of {} nested instances of generic functions while \
+                 instantiating `{}`",
+                limit, name
+            ),
+            TypeErrorKind::UnsupportedGenerics { item } => {
+                write!(f, "generic parameters are not supported on {}", item)
+            }
+            TypeErrorKind::Instantiated { error, .. } => write!(f, "{}", error.kind),
+        }
+    }
+}
@@ -0,0 +1,151 @@
+//! Inference variables and their unification.
+
+use super::ty::{FloatTy, FnSig, InferTy, IntTy, Ty};
+use crate::ast::Mutability;
+
+/// InferCtxt holds the inference variables of the body being checked.
+#[derive(Debug, Default)]
+pub struct InferCtxt {
+    /// Type every variable is bound to, by index. A variable may be bound to
+    /// another variable.
+    vars: Vec<Option<Ty>>,
+}
+
+impl InferCtxt {
+    /// Forgets every variable, before a new body is checked.
+    pub fn clear(&mut self) {
+        self.vars.clear();
+    }
+
+    /// Returns a new variable of any type.
+    pub fn new_var(&mut self) -> Ty {
+        Ty::Infer(InferTy::Var(self.push()))
+    }
+
+    /// Returns a new variable for the type of an integer literal.
+    pub fn new_int(&mut self) -> Ty {
+        Ty::Infer(InferTy::Int(self.push()))
+    }
+
+    /// Returns a new variable for the type of a float literal.
+    pub fn new_float(&mut self) -> Ty {
+        Ty::Infer(InferTy::Float(self.push()))
+    }
+
+    /// Adds an unbound variable and returns its index.
+    fn push(&mut self) -> u32 {
+        self.vars.push(None);
+        (self.vars.len() - 1) as u32
+    }
+
+    /// Returns the type, or the type the variable it is is bound to, until
+    /// it is not a bound variable.
+    pub fn shallow(&self, ty: &Ty) -> Ty {
+        let mut ty = ty;
+        while let Ty::Infer(var) = ty {
+            match &self.vars[var.index()] {
+                Some(bound) => ty = bound,
+                None => break,
+            }
+        }
+        ty.clone()
+    }
+
+    /// Returns the type with every bound variable replaced by its type.
+    pub fn resolve(&self, ty: &Ty) -> Ty {
+        self.map(ty, &|ty| ty)
+    }
+
+    /// Returns the type with every variable replaced by its type, and the
+    /// variables left unbound by their default: `i32` for integer literals,
+    /// `f64` for float literals and the error type for other variables, whose
+    /// type cannot be known.
+    pub fn finish(&self, ty: &Ty) -> Ty {
+        self.map(ty, &|ty| match ty {
+            Ty::Infer(InferTy::Int(_)) => Ty::Int(IntTy::I32),
+            Ty::Infer(InferTy::Float(_)) => Ty::Float(FloatTy::F64),
+            Ty::Infer(InferTy::Var(_)) => Ty::Error,
+            ty => ty,
+        })
+    }
+
+    /// Resolves the variables of a type, passing unbound ones to `unbound`.
+    fn map(&self, ty: &Ty, unbound: &impl Fn(Ty) -> Ty) -> Ty {
+        match self.shallow(ty) {
+            Ty::Ptr(mutability, pointee) => {
+                Ty::Ptr(mutability, Box::new(self.map(&pointee, unbound)))
+            }
+            Ty::Fn(sig) => Ty::Fn(Box::new(FnSig {
+                params: sig.params.iter().map(|param| self.map(param, unbound)).collect(),
+                ret: self.map(&sig.ret, unbound),
+                variadic: sig.variadic,
+            })),
+            ty @ Ty::Infer(_) => unbound(ty),
+            ty => ty,
+        }
+    }
+
+    /// Makes two types equal by binding variables, returning whether it is
+    /// possible. An integer or float literal variable can only be bound to
+    /// an integer or float type.
+    pub fn unify(&mut self, a: &Ty, b: &Ty) -> bool {
+        let (a, b) = (self.shallow(a), self.shallow(b));
+        match (&a, &b) {
+            _ if a == b => true,
+            (Ty::Error, _) | (_, Ty::Error) => true,
+            (Ty::Infer(InferTy::Var(var)), ty) | (ty, Ty::Infer(InferTy::Var(var))) => {
+                self.bind(*var, ty)
+            }
+            (Ty::Infer(InferTy::Int(var)), ty @ (Ty::Int(_) | Ty::Infer(InferTy::Int(_))))
+            | (ty @ Ty::Int(_), Ty::Infer(InferTy::Int(var)))
+            | (
+                Ty::Infer(InferTy::Float(var)),
+                ty @ (Ty::Float(_) | Ty::Infer(InferTy::Float(_))),
+            )
+            | (ty @ Ty::Float(_), Ty::Infer(InferTy::Float(var))) => self.bind(*var, ty),
+            (Ty::Ptr(a_mut, a), Ty::Ptr(b_mut, b)) => a_mut == b_mut && self.unify(a, b),
+            (Ty::Fn(a), Ty::Fn(b)) => {
+                a.params.len() == b.params.len()
+                    && a.variadic == b.variadic
+                    && a.params.iter().zip(&b.params).all(|(a, b)| self.unify(a, b))
+                    && self.unify(&a.ret, &b.ret)
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns whether a value of type `found` can be used where `expected`
+    /// is expected, unifying them: `!` can be used as any type and a
+    /// `*mut T` as a `*const T`.
+    pub fn coerce(&mut self, found: &Ty, expected: &Ty) -> bool {
+        let (found, expected) = (self.shallow(found), self.shallow(expected));
+        match (&found, &expected) {
+            (Ty::Never | Ty::Error, _) | (_, Ty::Error) => true,
+            (Ty::Ptr(Mutability::Mut, found), Ty::Ptr(Mutability::Const, expected)) => {
+                self.unify(found, expected)
+            }
+            _ => self.unify(&found, &expected),
+        }
+    }
+
+    /// Binds an unbound variable, unless the type contains it.
+    fn bind(&mut self, var: u32, ty: &Ty) -> bool {
+        if self.occurs(var, ty) {
+            return false;
+        }
+        self.vars[var as usize] = Some(ty.clone());
+        true
+    }
+
+    /// Returns whether a variable occurs in a type.
+    fn occurs(&self, var: u32, ty: &Ty) -> bool {
+        match self.shallow(ty) {
+            Ty::Infer(other) => other.index() == var as usize,
+            Ty::Ptr(_, pointee) => self.occurs(var, &pointee),
+            Ty::Fn(sig) => {
+                sig.params.iter().any(|param| self.occurs(var, param)) || self.occurs(var, &sig.ret)
+            }
+            _ => false,
+        }
+    }
+}
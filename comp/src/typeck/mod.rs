@@ -0,0 +1,1633 @@
+//! Type checking.
+//!
+//! The checker computes the type of every expression of a resolved program
+//! and checks it against the type expected where the expression is used: the
+//! annotation of a `let`, the parameter of a call, the return type of the
+//! function, and so on. The expected type is passed down while checking, so a
+//! literal takes the type its context wants.
+//!
+//! The types are the integers `i8` to `i64`, `u8` to `u64`, `isize` and
+//! `usize`, the floats `f32` and `f64`, `bool`, `str`, the type of string
+//! literals, `void`, the type of functions without a return type and of
+//! statements, `!`, the type of expressions that never complete, raw pointers
+//! `*const T` and `*mut T`, function types `fn(T, U) -> R`, structs, unions
+//! and enums.
+//!
+//! Types are inferred within a function body. A type not known yet is an
+//! inference variable, which the uses of the value bind: in
+//!
+//! ```text
+//! let x = 10
+//! math.add(x, 1)
+//! ```
+//!
+//! `x` is an integer of unknown type until it is passed to a parameter of
+//! type `isize`, which makes it an `isize`. A `let` without a type or an
+//! initializer is typed by its uses too. Once the body is checked, the
+//! variables still unbound take their default type.
+//!
+//! Literals are typed as follows:
+//!
+//! - an integer literal without suffix has the integer type expected, or the
+//!   integer type its uses infer, `i32` when nothing constrains it, and a
+//!   float literal without suffix likewise a float type, `f64` by default,
+//! - string and byte string literals, `"a"` and `b"a"`, are `str`, and C
+//!   string literals, `c"a"`, the only NUL-terminated ones, `*const u8`,
+//! - char literals are `u32`,
+//! - `null` has the pointer type expected, `*const void` otherwise.
+//!
+//! Number literals are checked against their type once it is inferred: `256`
+//! does not fit in a `u8`, nor `1e39` in an `f32`. A negated literal is
+//! checked as a whole, so `-128i8` is valid.
+//!
+//! Only locals declared with `let mut` can be assigned, along with their
+//! fields; parameters, `const`s and pattern bindings cannot.
+//!
+//! The only implicit conversions are from `!` to any type and from `*mut T`
+//! to `*const T`. Other conversions are casts: between numeric types, between
+//! pointer types, between pointers and integers, and from `bool` or an enum
+//! to an integer. An untyped integer literal cast to an integer type has that
+//! type, and `usize` when cast to a pointer.
+//!
+//! A generic function, `fn sum[T](a: T, b: T) -> T`, is not checked on its
+//! own but once per instance, with its generic parameters replaced by the
+//! type arguments of the instance. The type arguments of a use are given
+//! explicitly, `sum[u8]`, or inferred like the types of locals, `sum(1, 2)`
+//! being `sum[i32]`. So an operation invalid for some type arguments is only
+//! an error in the instances with those, reported once at the use that
+//! needed the first of them. Instances needed by instances are found until
+//! none is new, up to a limit for functions that instantiate themselves with
+//! ever larger types. Only functions outside of impl blocks, traits and
+//! extern blocks can be generic: generic parameters on other items are
+//! rejected.
+//!
+//! Arguments passed to the `...` of a variadic extern function are checked on
+//! their own, then get the C default argument promotions: integers narrower
+//! than `i32` and `bool` are passed as `i32`, `f32` as `f64`. Other types
+//! than numbers, `bool` and pointers cannot be passed to `...`.
+
+use self::{
+    error::{TypeError, TypeErrorKind},
+    infer::InferCtxt,
+    mono::Instance,
+    ty::{FloatTy, FnSig, InferTy, IntTy, Ty},
+};
+use crate::{
+    ast::{
+        Arm, BinOp, Block, Expr, ExprKind, FieldInit, FnDecl, Ident, Item, ItemKind, Lit,
+        Mutability, Pat, PatKind, Path, Stmt, StmtKind, UnOp, Visibility,
+    },
+    loader::{ModuleGraph, ModuleId},
+    resolve::{DefId, DefKind, Res, Resolutions},
+    span::Span,
+    symbol::{Symbol, kw},
+};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+pub mod error;
+mod infer;
+pub mod mono;
+pub mod ty;
+
+/// Number of instances of generic functions that can be nested, each needed
+/// by the body of the previous one.
+const INSTANCE_DEPTH_LIMIT: usize = 64;
+
+/// Field of a struct or union with its type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldTy {
+    /// Name of the field.
+    pub name: Ident,
+    /// Visibility of the field.
+    pub vis: Visibility,
+    /// Type of the field.
+    pub ty: Ty,
+}
+
+/// TypeckResults holds the types computed by the checker.
+#[derive(Debug, Default)]
+pub struct TypeckResults {
+    /// Types of the bodies of the functions that are not generic and of
+    /// the discriminants of enums.
+    bodies: BodyTypes,
+    /// Signature of every function and variant with fields, generic
+    /// parameters left as they are.
+    sigs: HashMap<DefId, FnSig>,
+    /// Fields of every struct and union.
+    fields: HashMap<DefId, Vec<FieldTy>>,
+    /// Type of every type alias.
+    aliases: HashMap<DefId, Ty>,
+    /// Every instance of a generic function used, in the order they were
+    /// found.
+    instances: Vec<MonoFn>,
+}
+
+impl TypeckResults {
+    /// Returns the types of the bodies of the functions that are not
+    /// generic.
+    pub fn bodies(&self) -> &BodyTypes {
+        &self.bodies
+    }
+
+    /// Returns the signature of a function or variant.
+    pub fn sig(&self, id: DefId) -> Option<&FnSig> {
+        self.sigs.get(&id)
+    }
+
+    /// Returns the fields of a struct or union.
+    pub fn fields(&self, id: DefId) -> Option<&[FieldTy]> {
+        self.fields.get(&id).map(Vec::as_slice)
+    }
+
+    /// Returns the instances of generic functions used by the program.
+    pub fn instances(&self) -> &[MonoFn] {
+        &self.instances
+    }
+}
+
+/// BodyTypes holds the types of the expressions and bindings of bodies.
+#[derive(Debug, Default)]
+pub struct BodyTypes {
+    /// Type of every expression, by span.
+    exprs: HashMap<Span, Ty>,
+    /// Type of every parameter, local and pattern binding, by the span of its
+    /// name.
+    locals: HashMap<Span, Ty>,
+    /// Type every argument passed to `...` is promoted to, by span, when it
+    /// is not its own type.
+    promotions: HashMap<Span, Ty>,
+    /// Instance every use of a generic function refers to, by span.
+    instances: HashMap<Span, Instance>,
+}
+
+impl BodyTypes {
+    /// Returns the type of the expression at `span`.
+    pub fn expr_ty(&self, span: Span) -> Option<&Ty> {
+        self.exprs.get(&span)
+    }
+
+    /// Returns the type of the binding whose name is at `span`.
+    pub fn local_ty(&self, span: Span) -> Option<&Ty> {
+        self.locals.get(&span)
+    }
+
+    /// Returns the type the variadic argument at `span` is promoted to, if
+    /// it is promoted.
+    pub fn promoted_ty(&self, span: Span) -> Option<&Ty> {
+        self.promotions.get(&span)
+    }
+
+    /// Returns the instance the use of a generic function at `span` refers
+    /// to.
+    pub fn instance(&self, span: Span) -> Option<&Instance> {
+        self.instances.get(&span)
+    }
+}
+
+/// An instance of a generic function, checked with its type arguments.
+#[derive(Debug)]
+pub struct MonoFn {
+    /// The function and its type arguments.
+    pub instance: Instance,
+    /// Symbol name of the instance.
+    pub symbol: String,
+    /// Signature of the instance.
+    pub sig: FnSig,
+    /// Types of the body of the instance.
+    pub types: BodyTypes,
+}
+
+/// Type checks every definition of a resolved program.
+pub fn check(graph: &ModuleGraph, res: &Resolutions) -> (TypeckResults, Vec<TypeError>) {
+    let mut checker = Checker {
+        graph,
+        res,
+        results: TypeckResults::default(),
+        aliases: Vec::new(),
+        impl_tys: HashMap::new(),
+        module: ModuleId::default(),
+        self_ty: None,
+        ret: None,
+        loops: Vec::new(),
+        infer: InferCtxt::default(),
+        body: BodyCtx::default(),
+        mutable: HashSet::new(),
+        generic_args: HashMap::new(),
+        seen: HashSet::new(),
+        queue: VecDeque::new(),
+        depth: 0,
+        instance_errors: HashSet::new(),
+        errors: Vec::new(),
+    };
+    for module in graph.modules() {
+        for item in &module.ast.items {
+            checker.reject_generics(item, None);
+        }
+    }
+    for (id, def) in res.defs() {
+        match def.kind {
+            // Checked once per instance.
+            DefKind::Fn if !checker.fn_decl(id).generics.is_empty() => {
+                checker.sig(id);
+            }
+            DefKind::Fn | DefKind::Method => {
+                let sig = checker.sig(id);
+                checker.check_fn(id, sig);
+            }
+            DefKind::ExternFn | DefKind::Variant => {
+                checker.sig(id);
+            }
+            DefKind::Struct | DefKind::Union => {
+                checker.fields(id);
+            }
+            DefKind::Enum => checker.check_enum(id),
+            DefKind::TypeAlias => {
+                checker.alias_ty(id);
+            }
+            DefKind::Trait => {}
+        }
+    }
+    // Every signature is known by now, so the type arguments of an instance
+    // only apply to its body.
+    while let Some(pending) = checker.queue.pop_front() {
+        checker.check_instance(pending);
+    }
+    (checker.results, checker.errors)
+}
+
+/// Kind of loop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LoopKind {
+    Loop,
+    While,
+    For,
+}
+
+/// A loop being checked.
+#[derive(Debug)]
+struct LoopCtx {
+    /// Kind of loop.
+    kind: LoopKind,
+    /// Whether a `break` leaves the loop.
+    broken: bool,
+    /// Type of the values given to `break`, for `loop`.
+    break_ty: Option<Ty>,
+}
+
+/// Return type of the function being checked.
+#[derive(Debug)]
+struct RetCtx {
+    /// The return type.
+    ty: Ty,
+    /// Span of the return type, if written.
+    span: Option<Span>,
+}
+
+/// Value of a number literal.
+#[derive(Debug, Clone, Copy)]
+enum LitValue {
+    /// An integer, `None` if it does not even fit in a `u128`.
+    Int(Option<u128>),
+    Float(f64),
+}
+
+/// A number literal to check against its type once it is inferred.
+#[derive(Debug)]
+struct LitCheck {
+    /// Span of the literal, with its `-` if negated.
+    span: Span,
+    /// Value of the literal.
+    value: LitValue,
+    /// Type of the literal.
+    ty: Ty,
+    /// Whether the literal is the operand of a `-`.
+    negated: bool,
+}
+
+/// What is left to check in the body being checked once its types are
+/// inferred.
+#[derive(Debug, Default)]
+struct BodyCtx {
+    /// Spans of the expressions of the body.
+    exprs: Vec<Span>,
+    /// Spans of the names of the locals and pattern bindings of the body.
+    locals: Vec<Span>,
+    /// `let`s without a type or an initializer, typed by their uses.
+    unannotated: Vec<Ident>,
+    /// Number literals of the body.
+    lits: Vec<LitCheck>,
+    /// Integers of a type not known yet given to `-`, with their span.
+    negations: Vec<(Span, Ty)>,
+    /// Arguments passed to the `...` of a variadic function, with their
+    /// span.
+    varargs: Vec<(Span, Ty)>,
+    /// Uses of generic functions, with their span and the types of their
+    /// type arguments.
+    instances: Vec<(Span, DefId, Vec<Ty>)>,
+}
+
+/// An instance of a generic function waiting to be checked.
+#[derive(Debug)]
+struct PendingInstance {
+    /// The instance.
+    instance: Instance,
+    /// Span of the first use of the instance.
+    span: Span,
+    /// Number of instances it is nested in, plus one.
+    depth: usize,
+}
+
+/// Checker walks the definitions of a program, computing types.
+struct Checker<'a> {
+    /// The program.
+    graph: &'a ModuleGraph,
+    /// Names of the program.
+    res: &'a Resolutions,
+    /// The result being built.
+    results: TypeckResults,
+    /// Type aliases being lowered, innermost last.
+    aliases: Vec<DefId>,
+    /// Self type of every impl block, by module and item index.
+    impl_tys: HashMap<(ModuleId, usize), Ty>,
+    /// Module of the definition being checked.
+    module: ModuleId,
+    /// Type `Self` refers to in the definition being checked.
+    self_ty: Option<Ty>,
+    /// Return type of the function being checked.
+    ret: Option<RetCtx>,
+    /// Loops around the expression being checked, innermost last.
+    loops: Vec<LoopCtx>,
+    /// Inference variables of the body being checked.
+    infer: InferCtxt,
+    /// Deferred checks of the body being checked.
+    body: BodyCtx,
+    /// Spans of the names of the locals declared with `let mut`, the only
+    /// bindings that can be assigned.
+    mutable: HashSet<Span>,
+    /// Type arguments of the instance being checked, by generic parameter.
+    generic_args: HashMap<Symbol, Ty>,
+    /// Instances found so far.
+    seen: HashSet<Instance>,
+    /// Instances found but not checked yet.
+    queue: VecDeque<PendingInstance>,
+    /// Number of instances the body being checked is nested in.
+    depth: usize,
+    /// Errors found in the bodies of instances, by span and kind, so that an
+    /// error shared by several instances of a function is reported once.
+    instance_errors: HashSet<(Span, std::mem::Discriminant<TypeErrorKind>)>,
+    /// Errors found so far.
+    errors: Vec<TypeError>,
+}
+
+impl<'a> Checker<'a> {
+    /// Records an error.
+    fn error(&mut self, kind: TypeErrorKind, span: Span) {
+        self.errors.push(TypeError::new(kind, span));
+    }
+
+    /// Reports the generic parameters of an item and of the items nested in
+    /// it, unless it is a function outside of any block. `block` describes
+    /// the functions of the enclosing block, if any.
+    fn reject_generics(&mut self, item: &Item, block: Option<&'static str>) {
+        let (generics, kind) = match &item.kind {
+            ItemKind::Import(_) => return,
+            ItemKind::Fn(decl) => match block {
+                Some(kind) => (&decl.generics, kind),
+                None => return,
+            },
+            ItemKind::Extern(block) => {
+                for inner in &block.items {
+                    self.reject_generics(inner, Some("extern functions"));
+                }
+                return;
+            }
+            ItemKind::Struct(decl) => (&decl.generics, "structs"),
+            ItemKind::Union(decl) => (&decl.generics, "unions"),
+            ItemKind::Enum(decl) => (&decl.generics, "enums"),
+            ItemKind::TypeAlias(alias) => (&alias.generics, "type aliases"),
+            ItemKind::Impl(block) => (&block.generics, "impl blocks"),
+            ItemKind::Trait(decl) => (&decl.generics, "traits"),
+        };
+        if let (Some(first), Some(last)) = (generics.first(), generics.last()) {
+            let kind = TypeErrorKind::UnsupportedGenerics { item: kind };
+            self.error(kind, first.span.to(last.span));
+        }
+        let items = match &item.kind {
+            ItemKind::Impl(block) => &block.items,
+            ItemKind::Trait(decl) => &decl.items,
+            _ => return,
+        };
+        for inner in items {
+            self.reject_generics(inner, Some("methods"));
+        }
+    }
+
+    /// Returns the top level item a definition is declared in.
+    fn module_item(&self, id: DefId) -> &'a Item {
+        let def = self.res.def(id);
+        &self.graph.get(def.module).ast.items[def.path.item]
+    }
+
+    /// Returns the declaration of a function.
+    fn fn_decl(&self, id: DefId) -> &'a FnDecl {
+        let item = self.module_item(id);
+        let item = match (&item.kind, self.res.def(id).path.nested) {
+            (ItemKind::Extern(block), Some(i)) => &block.items[i],
+            (ItemKind::Impl(block), Some(i)) => &block.items[i],
+            (ItemKind::Trait(decl), Some(i)) => &decl.items[i],
+            _ => item,
+        };
+        match &item.kind {
+            ItemKind::Fn(decl) => decl,
+            _ => unreachable!("definition is not a function"),
+        }
+    }
+
+    /// Runs `f` in the context of a definition: its module and the type
+    /// `Self` refers to in it.
+    fn with_def<R>(&mut self, id: DefId, f: impl FnOnce(&mut Self) -> R) -> R {
+        let def = *self.res.def(id);
+        let self_ty = match &self.module_item(id).kind {
+            ItemKind::Impl(block) => {
+                let key = (def.module, def.path.item);
+                match self.impl_tys.get(&key) {
+                    Some(ty) => Some(ty.clone()),
+                    None => {
+                        let outer = self.self_ty.take();
+                        let ty = self.lower_ty(&block.self_ty);
+                        self.self_ty = outer;
+                        self.impl_tys.insert(key, ty.clone());
+                        Some(ty)
+                    }
+                }
+            }
+            ItemKind::Trait(_) => Some(Ty::Param(kw::SelfType)),
+            _ => None,
+        };
+        let outer_module = std::mem::replace(&mut self.module, def.module);
+        let outer_self = std::mem::replace(&mut self.self_ty, self_ty);
+        let result = f(self);
+        self.module = outer_module;
+        self.self_ty = outer_self;
+        result
+    }
+
+    /// Converts a type written in the source into a type.
+    fn lower_ty(&mut self, ty: &crate::ast::Ty) -> Ty {
+        use crate::ast::TyKind;
+        match &ty.kind {
+            TyKind::Never => Ty::Never,
+            TyKind::Ptr(mutability, pointee) => {
+                Ty::Ptr(*mutability, Box::new(self.lower_ty(pointee)))
+            }
+            TyKind::Path(path) => self.path_ty(path),
+            TyKind::Fn(params, ret) => {
+                let params = params.iter().map(|param| self.lower_ty(param)).collect();
+                let ret = ret.as_ref().map_or(Ty::Void, |ret| self.lower_ty(ret));
+                Ty::Fn(Box::new(FnSig { params, ret, variadic: false }))
+            }
+        }
+    }
+
+    /// Returns the type a path in a type refers to.
+    fn path_ty(&mut self, path: &Path) -> Ty {
+        let name = path.segments.last().map_or(kw::SelfType, |segment| segment.name);
+        match self.res.get(path.span) {
+            Some(Res::PrimTy(prim)) => Ty::from_prim(prim),
+            Some(Res::Generic(_)) => {
+                self.generic_args.get(&name).cloned().unwrap_or(Ty::Param(name))
+            }
+            Some(Res::SelfTy) => self.self_ty.clone().unwrap_or(Ty::Error),
+            Some(Res::Def(id)) => {
+                let def = self.res.def(id);
+                match def.kind {
+                    DefKind::Struct | DefKind::Union | DefKind::Enum => Ty::Adt(id, def.name.name),
+                    DefKind::TypeAlias => self.alias_ty(id),
+                    kind => {
+                        let found = kind.describe();
+                        self.error(TypeErrorKind::NotAType { name, found }, path.span);
+                        Ty::Error
+                    }
+                }
+            }
+            // Errors were reported by the resolver.
+            Some(Res::Local(_) | Res::Module(_)) | None => Ty::Error,
+        }
+    }
+
+    /// Returns the type a type alias stands for.
+    fn alias_ty(&mut self, id: DefId) -> Ty {
+        if let Some(ty) = self.results.aliases.get(&id) {
+            return ty.clone();
+        }
+        let ItemKind::TypeAlias(alias) = &self.module_item(id).kind else {
+            unreachable!("definition is not a type alias");
+        };
+        if self.aliases.contains(&id) {
+            self.error(TypeErrorKind::AliasCycle { name: alias.name.name }, alias.name.span);
+            self.results.aliases.insert(id, Ty::Error);
+            return Ty::Error;
+        }
+        self.aliases.push(id);
+        let ty = self.with_def(id, |this| this.lower_ty(&alias.ty));
+        self.aliases.pop();
+        // An alias in a cycle was set to the error type when the cycle was
+        // found.
+        self.results.aliases.entry(id).or_insert(ty).clone()
+    }
+
+    /// Returns the signature of a function, or of the constructor of a
+    /// variant.
+    fn sig(&mut self, id: DefId) -> FnSig {
+        if let Some(sig) = self.results.sigs.get(&id) {
+            return sig.clone();
+        }
+        let def = *self.res.def(id);
+        let sig = self.with_def(id, |this| match def.kind {
+            DefKind::Variant => {
+                let ItemKind::Enum(decl) = &this.module_item(id).kind else {
+                    unreachable!("variant outside of an enum");
+                };
+                let variant = &decl.variants[def.path.nested.unwrap_or_default()];
+                let parent = def.parent.expect("variant without an enum");
+                FnSig {
+                    params: variant.fields.iter().map(|ty| this.lower_ty(ty)).collect(),
+                    ret: Ty::Adt(parent, decl.name.name),
+                    variadic: false,
+                }
+            }
+            _ => {
+                let decl = this.fn_decl(id);
+                FnSig {
+                    params: decl.params.iter().map(|param| this.lower_ty(&param.ty)).collect(),
+                    ret: decl.ret.as_ref().map_or(Ty::Void, |ret| this.lower_ty(ret)),
+                    variadic: decl.variadic,
+                }
+            }
+        });
+        self.results.sigs.insert(id, sig.clone());
+        sig
+    }
+
+    /// Returns the fields of a struct or union.
+    fn fields(&mut self, id: DefId) -> Vec<FieldTy> {
+        if let Some(fields) = self.results.fields.get(&id) {
+            return fields.clone();
+        }
+        let (ItemKind::Struct(decl) | ItemKind::Union(decl)) = &self.module_item(id).kind else {
+            unreachable!("definition is not a struct or union");
+        };
+        let fields: Vec<_> = self.with_def(id, |this| {
+            (decl.fields.iter())
+                .map(|field| FieldTy {
+                    name: field.name,
+                    vis: field.vis,
+                    ty: this.lower_ty(&field.ty),
+                })
+                .collect()
+        });
+        self.results.fields.insert(id, fields.clone());
+        fields
+    }
+
+    /// Checks the variants of an enum and their discriminants.
+    fn check_enum(&mut self, id: DefId) {
+        let ItemKind::Enum(decl) = &self.module_item(id).kind else {
+            unreachable!("definition is not an enum");
+        };
+        self.with_def(id, |this| {
+            this.body(|this| {
+                for variant in &decl.variants {
+                    if let Some(discriminant) = &variant.discriminant {
+                        this.coerce(discriminant, &Ty::Int(IntTy::Isize), None);
+                    }
+                }
+            })
+        });
+    }
+
+    /// Checks the body of a function against its signature.
+    fn check_fn(&mut self, id: DefId, sig: FnSig) {
+        let decl = self.fn_decl(id);
+        for (param, ty) in decl.params.iter().zip(&sig.params) {
+            self.results.bodies.locals.insert(param.name.span, ty.clone());
+        }
+        let Some(body) = &decl.body else {
+            return;
+        };
+
+        let ret_span = decl.ret.as_ref().map(|ret| ret.span);
+        self.ret = Some(RetCtx { ty: sig.ret.clone(), span: ret_span });
+        let ty = self.with_def(id, |this| this.body(|this| this.block(body)));
+        self.ret = None;
+
+        if !ty.is_never() && !matches!(sig.ret, Ty::Void | Ty::Error) {
+            let end = Span::new(body.span.hi.saturating_sub(1), body.span.hi);
+            let kind = TypeErrorKind::MissingReturn {
+                name: decl.name.name,
+                ret: sig.ret,
+                ret_span: ret_span.unwrap_or(decl.name.span),
+            };
+            self.error(kind, end);
+        }
+    }
+
+    /// Checks a function body or a constant expression with `f`, then
+    /// replaces the inference variables in the types computed by their
+    /// types and runs the checks that needed them.
+    fn body<R>(&mut self, f: impl FnOnce(&mut Self) -> R) -> R {
+        self.infer.clear();
+        let first_error = self.errors.len();
+        let result = f(self);
+        let body = std::mem::take(&mut self.body);
+
+        for name in body.unannotated {
+            let ty = self.results.bodies.locals.get(&name.span).map(|ty| self.infer.shallow(ty));
+            if let Some(Ty::Infer(InferTy::Var(_))) = ty {
+                self.error(TypeErrorKind::AnnotationNeeded { name: name.name }, name.span);
+            }
+        }
+        for span in body.exprs {
+            if let Some(ty) = self.results.bodies.exprs.get_mut(&span) {
+                *ty = self.infer.finish(ty);
+            }
+        }
+        for span in body.locals {
+            if let Some(ty) = self.results.bodies.locals.get_mut(&span) {
+                *ty = self.infer.finish(ty);
+            }
+        }
+        for (span, ty) in body.negations {
+            if let Ty::Int(int) = self.infer.finish(&ty) {
+                if !int.is_signed() {
+                    self.error(
+                        TypeErrorKind::InvalidUnary { op: UnOp::Neg, ty: Ty::Int(int) },
+                        span,
+                    );
+                }
+            }
+        }
+        for lit in body.lits {
+            let ty = self.infer.finish(&lit.ty);
+            if !lit_fits(lit.value, &ty, lit.negated) {
+                self.error(TypeErrorKind::LitOutOfRange { ty }, lit.span);
+            }
+        }
+        for (span, ty) in body.varargs {
+            let ty = self.infer.finish(&ty);
+            let promoted = match ty {
+                Ty::Int(int) if int.bits() < 32 => Ty::Int(IntTy::I32),
+                Ty::Bool => Ty::Int(IntTy::I32),
+                Ty::Float(FloatTy::F32) => Ty::Float(FloatTy::F64),
+                Ty::Int(_) | Ty::Float(_) | Ty::Ptr(..) | Ty::Never | Ty::Error => continue,
+                ty => {
+                    self.error(TypeErrorKind::InvalidVariadicArg { ty }, span);
+                    continue;
+                }
+            };
+            self.results.bodies.promotions.insert(span, promoted);
+        }
+        for (span, id, vars) in body.instances {
+            let unknown = |ty: &Ty| matches!(ty, Ty::Infer(InferTy::Var(_)));
+            if vars.iter().any(|var| unknown(&self.infer.shallow(var))) {
+                let decl = self.fn_decl(id);
+                let params = decl.generics.iter().map(|param| param.name).collect();
+                self.error(TypeErrorKind::CannotInfer { name: decl.name.name, params }, span);
+                continue;
+            }
+            let args: Vec<_> = vars.iter().map(|var| self.infer.finish(var)).collect();
+            // Uses in generic code or with errors are not instances.
+            if args.iter().all(is_concrete) {
+                self.use_instance(Instance { def: id, args }, span);
+            }
+        }
+        // Report the errors of the body in source order, deferred ones too.
+        self.errors[first_error..].sort_by_key(|error| error.span.lo);
+        self.infer.clear();
+        result
+    }
+
+    /// Records the type of a local or pattern binding.
+    fn bind(&mut self, name: Span, ty: Ty) {
+        self.results.bodies.locals.insert(name, ty);
+        self.body.locals.push(name);
+    }
+
+    /// Checks the body of an instance of a generic function, reporting its
+    /// errors at the use that instantiated it.
+    fn check_instance(&mut self, pending: PendingInstance) {
+        let PendingInstance { instance, span, depth } = pending;
+        let decl = self.fn_decl(instance.def);
+        let args = decl.generics.iter().map(|param| param.name).zip(instance.args.iter().cloned());
+        self.generic_args = args.collect();
+        self.depth = depth;
+        let sig = mono::subst_sig(&self.sig(instance.def), &self.generic_args);
+        let outer = std::mem::take(&mut self.results.bodies);
+        let first_error = self.errors.len();
+        self.check_fn(instance.def, sig.clone());
+        let types = std::mem::replace(&mut self.results.bodies, outer);
+        self.generic_args.clear();
+        self.depth = 0;
+
+        let name = instance.name(self.res);
+        let errors: Vec<_> = self.errors.drain(first_error..).collect();
+        for error in errors {
+            // The chain of instances leading to the limit is not shown.
+            if let TypeErrorKind::InstanceDepth { .. } = error.kind {
+                self.errors.push(error);
+                continue;
+            }
+            if !self.instance_errors.insert((error.span, std::mem::discriminant(&error.kind))) {
+                continue;
+            }
+            let kind =
+                TypeErrorKind::Instantiated { error: Box::new(error), instance: name.clone() };
+            self.error(kind, span);
+        }
+        let symbol = instance.mangle(self.res, self.graph);
+        self.results.instances.push(MonoFn { instance, symbol, sig, types });
+    }
+
+    /// Records an instance of a generic function used at `span`, to check
+    /// it if it is new.
+    fn use_instance(&mut self, instance: Instance, span: Span) {
+        self.results.bodies.instances.insert(span, instance.clone());
+        if !self.seen.insert(instance.clone()) {
+            return;
+        }
+        if self.depth >= INSTANCE_DEPTH_LIMIT {
+            let name = self.res.def(instance.def).name.name;
+            let kind = TypeErrorKind::InstanceDepth { name, limit: INSTANCE_DEPTH_LIMIT };
+            self.error(kind, span);
+            return;
+        }
+        self.queue.push_back(PendingInstance { instance, span, depth: self.depth + 1 });
+    }
+
+    /// Checks the statements of a block. The block is `!` if one of its
+    /// statements never completes, `void` otherwise.
+    fn block(&mut self, block: &Block) -> Ty {
+        let mut diverges = false;
+        for stmt in &block.stmts {
+            diverges |= self.stmt(stmt);
+        }
+        if diverges { Ty::Never } else { Ty::Void }
+    }
+
+    /// Checks a statement, returning whether it never completes.
+    fn stmt(&mut self, stmt: &Stmt) -> bool {
+        match &stmt.kind {
+            StmtKind::Local(local) => {
+                if local.mutability == Mutability::Mut {
+                    self.mutable.insert(local.name.span);
+                }
+                let ty = match (&local.ty, &local.init) {
+                    (Some(ty), init) => {
+                        let ty = self.lower_ty(ty);
+                        let origin = local.ty.as_ref().map(|ty| ty.span);
+                        let init = init.as_ref().map(|init| self.coerce(init, &ty, origin));
+                        if init.is_some_and(|init| init == Ty::Never) {
+                            self.bind(local.name.span, ty);
+                            return true;
+                        }
+                        ty
+                    }
+                    (None, Some(init)) => self.expr(init, None),
+                    (None, None) => {
+                        self.body.unannotated.push(local.name);
+                        self.infer.new_var()
+                    }
+                };
+                let diverges = ty == Ty::Never;
+                self.bind(local.name.span, ty);
+                diverges
+            }
+            StmtKind::Return(value) => {
+                let Some(ret) = self.ret.as_ref() else {
+                    return true;
+                };
+                let (expected, origin) = (ret.ty.clone(), ret.span);
+                match value {
+                    Some(value) => {
+                        self.coerce(value, &expected, origin);
+                    }
+                    None if !matches!(expected, Ty::Void | Ty::Error) => {
+                        let kind = TypeErrorKind::Mismatch { expected, found: Ty::Void, origin };
+                        self.error(kind, stmt.span);
+                    }
+                    None => {}
+                }
+                true
+            }
+            StmtKind::Defer(value) => {
+                self.expr(value, None);
+                false
+            }
+            StmtKind::Expr(value) => self.expr_stmt(value) == Ty::Never,
+        }
+    }
+
+    /// Checks an expression whose value is discarded. The arms of a `match`
+    /// statement may then have different types.
+    fn expr_stmt(&mut self, expr: &Expr) -> Ty {
+        match &expr.kind {
+            ExprKind::Match(scrutinee, arms) => {
+                let ty = self.match_expr(scrutinee, arms, None, true);
+                self.results.bodies.exprs.insert(expr.span, ty.clone());
+                ty
+            }
+            _ => self.expr(expr, None),
+        }
+    }
+
+    /// Checks an expression against the type expected where it is used and
+    /// returns its type.
+    fn coerce(&mut self, expr: &Expr, expected: &Ty, origin: Option<Span>) -> Ty {
+        let found = self.expr(expr, Some(expected));
+        if !self.infer.coerce(&found, expected) {
+            let kind = TypeErrorKind::Mismatch {
+                expected: self.infer.resolve(expected),
+                found: self.infer.resolve(&found),
+                origin,
+            };
+            self.error(kind, expr.span);
+        }
+        found
+    }
+
+    /// Computes the type of an expression, using the expected type, if any,
+    /// to type literals.
+    fn expr(&mut self, expr: &Expr, expected: Option<&Ty>) -> Ty {
+        let ty = self.expr_kind(expr, expected);
+        self.results.bodies.exprs.insert(expr.span, ty.clone());
+        self.body.exprs.push(expr.span);
+        ty
+    }
+
+    /// Computes the type of an expression, without recording it.
+    fn expr_kind(&mut self, expr: &Expr, expected: Option<&Ty>) -> Ty {
+        match &expr.kind {
+            ExprKind::Lit(lit) => self.lit(expr, lit, expected),
+            ExprKind::Path(path) => self.value_ty(path.span),
+            ExprKind::Unary(op, operand) => self.unary(expr, *op, operand, expected),
+            ExprKind::Binary(op, lhs, rhs) => self.binary(expr, *op, lhs, rhs, expected),
+            ExprKind::Assign(lhs, rhs) => {
+                let ty = self.place(lhs);
+                self.coerce(rhs, &ty, None);
+                Ty::Void
+            }
+            ExprKind::AssignOp(op, lhs, rhs) => {
+                let ty = self.place(lhs);
+                let rhs_ty = self.expr(rhs, Some(&ty));
+                self.binary_ty(expr, *op, ty, rhs_ty);
+                Ty::Void
+            }
+            ExprKind::Range(lo, hi, _) => {
+                for bound in [lo, hi].into_iter().flatten() {
+                    self.expr(bound, None);
+                }
+                self.error(TypeErrorKind::RangeOutsideFor, expr.span);
+                Ty::Error
+            }
+            ExprKind::Call(callee, args) => self.call(expr, callee, args),
+            ExprKind::TypeArgs(callee, args) => self.type_args(expr, callee, args),
+            ExprKind::Field(base, name) => self.field(expr, base, *name),
+            ExprKind::Cast(value, target) => {
+                let to = self.lower_ty(target);
+                let from = self.expr(value, None);
+                let from = self.infer.shallow(&from);
+                match (&from, &to) {
+                    (Ty::Infer(InferTy::Int(_)), Ty::Int(_))
+                    | (Ty::Infer(InferTy::Float(_)), Ty::Float(_)) => {
+                        self.infer.unify(&from, &to);
+                    }
+                    (Ty::Infer(InferTy::Int(_)), Ty::Ptr(..)) => {
+                        self.infer.unify(&from, &Ty::Int(IntTy::Usize));
+                    }
+                    _ => {}
+                }
+                if !can_cast(&from, &to, self.res) {
+                    let from = self.infer.resolve(&from);
+                    self.error(TypeErrorKind::InvalidCast { from, to: to.clone() }, expr.span);
+                }
+                to
+            }
+            ExprKind::Paren(inner) => self.expr(inner, expected),
+            ExprKind::Block(block) => self.block(block),
+            ExprKind::If(cond, then, els) => {
+                self.coerce(cond, &Ty::Bool, None);
+                let then = self.block(then);
+                let els = els.as_ref().map(|els| self.expr(els, None));
+                match els {
+                    Some(Ty::Never) if then == Ty::Never => Ty::Never,
+                    Some(_) | None => Ty::Void,
+                }
+            }
+            ExprKind::While(cond, body) => {
+                self.coerce(cond, &Ty::Bool, None);
+                self.in_loop(LoopKind::While, |this| this.block(body));
+                Ty::Void
+            }
+            ExprKind::For(var, iter, body) => {
+                let elem = match &iter.kind {
+                    ExprKind::Range(Some(lo), Some(hi), _) => {
+                        let ty = self.expr(lo, None);
+                        self.coerce(hi, &ty, None);
+                        let ty = self.infer.shallow(&ty);
+                        if !ty.is_integral() && ty != Ty::Error {
+                            self.error(TypeErrorKind::NotIterable(ty), iter.span);
+                            Ty::Error
+                        } else {
+                            ty
+                        }
+                    }
+                    _ => {
+                        let ty = self.expr(iter, None);
+                        if ty != Ty::Error {
+                            self.error(TypeErrorKind::NotIterable(ty), iter.span);
+                        }
+                        Ty::Error
+                    }
+                };
+                self.bind(var.span, elem);
+                self.in_loop(LoopKind::For, |this| this.block(body));
+                Ty::Void
+            }
+            ExprKind::Loop(body) => {
+                let ctx = self.in_loop(LoopKind::Loop, |this| this.block(body));
+                match (ctx.broken, ctx.break_ty) {
+                    (false, _) => Ty::Never,
+                    (true, ty) => ty.unwrap_or(Ty::Void),
+                }
+            }
+            ExprKind::Break(value) => {
+                let value_ty = value.as_ref().map(|value| {
+                    let expected = self.loops.last().and_then(|ctx| ctx.break_ty.clone());
+                    match expected {
+                        Some(expected) => self.coerce(value, &expected, None),
+                        None => self.expr(value, None),
+                    }
+                });
+                match self.loops.last_mut() {
+                    None => self.error(TypeErrorKind::OutsideLoop("break"), expr.span),
+                    Some(ctx) => {
+                        ctx.broken = true;
+                        match (ctx.kind, value_ty) {
+                            (_, None) => {}
+                            (LoopKind::Loop, Some(ty)) => {
+                                ctx.break_ty.get_or_insert(ty);
+                            }
+                            (LoopKind::While | LoopKind::For, Some(_)) => {
+                                self.error(TypeErrorKind::BreakWithValue, expr.span)
+                            }
+                        }
+                    }
+                }
+                Ty::Never
+            }
+            ExprKind::Continue => {
+                if self.loops.is_empty() {
+                    self.error(TypeErrorKind::OutsideLoop("continue"), expr.span);
+                }
+                Ty::Never
+            }
+            ExprKind::Match(scrutinee, arms) => self.match_expr(scrutinee, arms, expected, false),
+            ExprKind::Struct(path, fields) => self.struct_lit(expr, path, fields),
+            ExprKind::SizeOf(ty) => {
+                self.lower_ty(ty);
+                Ty::Int(IntTy::Usize)
+            }
+        }
+    }
+
+    /// Runs `f` inside a loop and returns what is known about the loop.
+    fn in_loop(&mut self, kind: LoopKind, f: impl FnOnce(&mut Self) -> Ty) -> LoopCtx {
+        self.loops.push(LoopCtx { kind, broken: false, break_ty: None });
+        f(self);
+        self.loops.pop().expect("loop stack is balanced")
+    }
+
+    /// Returns the type of a path or module access used as a value.
+    fn value_ty(&mut self, span: Span) -> Ty {
+        match self.res.get(span) {
+            Some(Res::Local(binding)) => {
+                self.results.bodies.locals.get(&binding).cloned().unwrap_or(Ty::Error)
+            }
+            Some(Res::Def(id)) => match self.res.def(id).kind {
+                DefKind::Fn if !self.fn_decl(id).generics.is_empty() => self.instantiate(span, id),
+                DefKind::Fn | DefKind::ExternFn | DefKind::Method => Ty::Fn(Box::new(self.sig(id))),
+                DefKind::Variant => {
+                    let sig = self.sig(id);
+                    if sig.params.is_empty() { sig.ret } else { Ty::Fn(Box::new(sig)) }
+                }
+                _ => Ty::Error,
+            },
+            _ => Ty::Error,
+        }
+    }
+
+    /// Returns the type of a use of a generic function, with new variables
+    /// for its type arguments.
+    fn instantiate(&mut self, span: Span, id: DefId) -> Ty {
+        let decl = self.fn_decl(id);
+        let vars: Vec<_> = decl.generics.iter().map(|_| self.infer.new_var()).collect();
+        let args = decl.generics.iter().map(|param| param.name).zip(vars.iter().cloned());
+        let sig = mono::subst_sig(&self.sig(id), &args.collect());
+        self.body.instances.push((span, id, vars));
+        Ty::Fn(Box::new(sig))
+    }
+
+    /// Checks explicit type arguments, e.g. `[i32]` in `sum[i32]`.
+    fn type_args(&mut self, expr: &Expr, callee: &Expr, args: &[crate::ast::Ty]) -> Ty {
+        let ty = self.expr(callee, None);
+        let args: Vec<_> = args.iter().map(|arg| self.lower_ty(arg)).collect();
+        if ty == Ty::Error {
+            return ty;
+        }
+        let vars = match self.body.instances.last() {
+            Some((span, _, vars)) if *span == callee.span => vars.clone(),
+            _ => Vec::new(),
+        };
+        if vars.len() != args.len() {
+            let kind = TypeErrorKind::TypeArgCount { expected: vars.len(), found: args.len() };
+            self.error(kind, expr.span);
+            return ty;
+        }
+        for (var, arg) in vars.iter().zip(&args) {
+            self.infer.unify(var, arg);
+        }
+        ty
+    }
+
+    /// Checks a unary operation.
+    fn unary(&mut self, expr: &Expr, op: UnOp, operand: &Expr, expected: Option<&Ty>) -> Ty {
+        let ty = self.expr(operand, expected);
+        let ty = self.infer.shallow(&ty);
+        if let (UnOp::Neg, ExprKind::Lit(Lit::Int(_) | Lit::Float(_))) = (op, &operand.kind) {
+            // The literal was recorded last, it is checked with its sign.
+            if let Some(lit) = self.body.lits.last_mut() {
+                lit.span = expr.span;
+                lit.negated = true;
+            }
+        }
+        let valid = match (op, &ty) {
+            (_, Ty::Error | Ty::Never) => return Ty::Error,
+            (UnOp::Neg, Ty::Int(int)) => int.is_signed(),
+            (UnOp::Neg, Ty::Infer(InferTy::Int(_))) => {
+                // Checked once the type is inferred.
+                self.body.negations.push((operand.span, ty.clone()));
+                true
+            }
+            (UnOp::Neg, ty) => ty.is_numeric(),
+            (UnOp::Not, Ty::Bool) => true,
+            (UnOp::Not | UnOp::BitNot, ty) => ty.is_integral(),
+        };
+        if !valid {
+            self.error(TypeErrorKind::InvalidUnary { op, ty: ty.clone() }, operand.span);
+            return Ty::Error;
+        }
+        ty
+    }
+
+    /// Checks a binary operation. The right operand is expected to have the
+    /// type of the left one, and the left one the expected type of an
+    /// arithmetic result.
+    fn binary(
+        &mut self,
+        expr: &Expr,
+        op: BinOp,
+        lhs: &Expr,
+        rhs: &Expr,
+        expected: Option<&Ty>,
+    ) -> Ty {
+        match op {
+            BinOp::And | BinOp::Or => {
+                self.coerce(lhs, &Ty::Bool, None);
+                self.coerce(rhs, &Ty::Bool, None);
+                Ty::Bool
+            }
+            _ => {
+                let expected = expected.filter(|_| !op.is_comparison());
+                let lhs = self.expr(lhs, expected);
+                let rhs = self.expr(rhs, Some(&lhs));
+                self.binary_ty(expr, op, lhs, rhs)
+            }
+        }
+    }
+
+    /// Returns the type of a binary operation on operands of the given types,
+    /// reporting unsupported operands.
+    fn binary_ty(&mut self, expr: &Expr, op: BinOp, lhs: Ty, rhs: Ty) -> Ty {
+        let (lhs, rhs) = (self.infer.shallow(&lhs), self.infer.shallow(&rhs));
+        // `!` operands never produce a value, use the other operand's type.
+        let (lhs, rhs) = match (lhs, rhs) {
+            (Ty::Never, rhs) => (rhs.clone(), rhs),
+            (lhs, Ty::Never) => (lhs.clone(), lhs),
+            operands => operands,
+        };
+        let result = if op.is_comparison() { Ty::Bool } else { lhs.clone() };
+        if lhs == Ty::Error || rhs == Ty::Error {
+            return if op.is_comparison() { Ty::Bool } else { Ty::Error };
+        }
+        // The operands have the same type, but for the shift amount.
+        let same = match op {
+            BinOp::Shl | BinOp::Shr => true,
+            _ if op.is_comparison() => self.infer.unify(&strip_mut(&lhs), &strip_mut(&rhs)),
+            _ => self.infer.unify(&lhs, &rhs),
+        };
+        let (lhs, rhs) = (self.infer.shallow(&lhs), self.infer.shallow(&rhs));
+        let valid = same
+            && match op {
+                BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div | BinOp::Rem => lhs.is_numeric(),
+                BinOp::BitXor | BinOp::BitAnd | BinOp::BitOr => {
+                    lhs.is_integral() || lhs == Ty::Bool
+                }
+                BinOp::Shl | BinOp::Shr => lhs.is_integral() && rhs.is_integral(),
+                BinOp::Eq | BinOp::Ne => lhs.is_numeric() || matches!(lhs, Ty::Bool | Ty::Ptr(..)),
+                BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge => {
+                    lhs.is_numeric() || matches!(lhs, Ty::Ptr(..))
+                }
+                BinOp::And | BinOp::Or => lhs == Ty::Bool && rhs == Ty::Bool,
+            };
+        if !valid {
+            let (lhs, rhs) = (self.infer.resolve(&lhs), self.infer.resolve(&rhs));
+            self.error(TypeErrorKind::InvalidBinary { op, lhs, rhs }, expr.span);
+            return if op.is_comparison() { Ty::Bool } else { Ty::Error };
+        }
+        result
+    }
+
+    /// Checks the left-hand side of an assignment, which must be a local or a
+    /// field, and returns its type.
+    fn place(&mut self, expr: &Expr) -> Ty {
+        let is_place = match &expr.kind {
+            ExprKind::Path(path) => matches!(self.res.get(path.span), Some(Res::Local(_)) | None),
+            ExprKind::Field(..) => self.res.get(expr.span).is_none(),
+            ExprKind::Paren(inner) => return self.place(inner),
+            _ => false,
+        };
+        let ty = self.expr(expr, None);
+        if !is_place {
+            self.error(TypeErrorKind::NotAssignable, expr.span);
+            return Ty::Error;
+        }
+        self.check_mutable(expr, expr.span);
+        ty
+    }
+
+    /// Reports the assignment at `span` if the place assigned is a binding
+    /// not declared with `let mut` or a field of one. A field reached through
+    /// a pointer does not depend on the binding holding the pointer.
+    fn check_mutable(&mut self, place: &Expr, span: Span) {
+        match &place.kind {
+            ExprKind::Path(path) => {
+                if let Some(Res::Local(binding)) = self.res.get(path.span) {
+                    if !self.mutable.contains(&binding) {
+                        let name = path.segments[0].name;
+                        self.error(TypeErrorKind::Immutable { name, binding }, span);
+                    }
+                }
+            }
+            ExprKind::Field(base, _) => {
+                let base_ty = self.results.bodies.exprs.get(&base.span).cloned();
+                let base_ty = base_ty.map(|ty| self.infer.shallow(&ty));
+                if !matches!(base_ty, Some(Ty::Ptr(..) | Ty::Error)) {
+                    self.check_mutable(base, span);
+                }
+            }
+            ExprKind::Paren(inner) => self.check_mutable(inner, span),
+            _ => {}
+        }
+    }
+
+    /// Checks a call. A call of a field that is not one but a function of the
+    /// impl of the base's type is a method call, the base being passed as the
+    /// first argument.
+    fn call(&mut self, expr: &Expr, callee: &Expr, args: &[Expr]) -> Ty {
+        if let ExprKind::Field(base, name) = &callee.kind {
+            if self.res.get(callee.span).is_none() {
+                let base_ty = self.expr(base, None);
+                let base_ty = self.infer.shallow(&base_ty);
+                if let Some(id) = self.method(&base_ty, name.name) {
+                    let sig = self.sig(id);
+                    let decl = self.fn_decl(id);
+                    let Some((self_param, params)) = sig.params.split_first() else {
+                        // A function without `self` is not a method.
+                        self.error(
+                            TypeErrorKind::NoField { ty: base_ty, name: name.name },
+                            name.span,
+                        );
+                        return Ty::Error;
+                    };
+                    let receiver = match (&base_ty, self_param) {
+                        (Ty::Ptr(_, pointee), Ty::Adt(..)) => pointee.as_ref().clone(),
+                        _ => base_ty,
+                    };
+                    if !self.infer.coerce(&receiver, self_param) {
+                        let kind = TypeErrorKind::Mismatch {
+                            expected: self_param.clone(),
+                            found: self.infer.resolve(&receiver),
+                            origin: Some(decl.params[0].span),
+                        };
+                        self.error(kind, base.span);
+                    }
+                    let method = FnSig {
+                        params: params.to_vec(),
+                        ret: sig.ret.clone(),
+                        variadic: sig.variadic,
+                    };
+                    self.results.bodies.exprs.insert(callee.span, Ty::Fn(Box::new(method.clone())));
+                    let origins: Vec<_> =
+                        decl.params[1..].iter().map(|param| param.ty.span).collect();
+                    return self.args(expr, &method, args, &origins, Some(decl.name.span));
+                }
+                let ty = self.field_ty(base_ty, *name);
+                self.results.bodies.exprs.insert(callee.span, ty.clone());
+                return self.call_ty(expr, callee, ty, args);
+            }
+        }
+        let ty = self.expr(callee, None);
+        self.call_ty(expr, callee, ty, args)
+    }
+
+    /// Checks a call of a callee of the given type.
+    fn call_ty(&mut self, expr: &Expr, callee: &Expr, ty: Ty, args: &[Expr]) -> Ty {
+        let sig = match self.infer.shallow(&ty) {
+            Ty::Fn(sig) => sig,
+            Ty::Error => {
+                for arg in args {
+                    self.expr(arg, None);
+                }
+                return Ty::Error;
+            }
+            ty => {
+                self.error(TypeErrorKind::NotCallable(ty), callee.span);
+                for arg in args {
+                    self.expr(arg, None);
+                }
+                return Ty::Error;
+            }
+        };
+        let span = match &callee.kind {
+            ExprKind::Path(path) => path.span,
+            _ => callee.span,
+        };
+        let (origins, def_span) = match self.res.get(span) {
+            Some(Res::Def(id)) if self.res.def(id).kind == DefKind::Variant => {
+                let ItemKind::Enum(decl) = &self.module_item(id).kind else {
+                    unreachable!("variant outside of an enum");
+                };
+                let variant = &decl.variants[self.res.def(id).path.nested.unwrap_or_default()];
+                (variant.fields.iter().map(|ty| ty.span).collect(), Some(variant.name.span))
+            }
+            Some(Res::Def(id)) => {
+                let decl = self.fn_decl(id);
+                (decl.params.iter().map(|param| param.ty.span).collect(), Some(decl.name.span))
+            }
+            _ => (Vec::new(), None),
+        };
+        self.args(expr, &sig, args, &origins, def_span)
+    }
+
+    /// Checks the arguments of a call against a signature and returns the
+    /// type of the call. `origins` are the spans of the parameter types.
+    fn args(
+        &mut self,
+        expr: &Expr,
+        sig: &FnSig,
+        args: &[Expr],
+        origins: &[Span],
+        def_span: Option<Span>,
+    ) -> Ty {
+        let expected = sig.params.len();
+        if args.len() < expected || (!sig.variadic && args.len() > expected) {
+            let kind = TypeErrorKind::ArgCount {
+                expected,
+                found: args.len(),
+                variadic: sig.variadic,
+                def_span,
+            };
+            self.error(kind, expr.span);
+        }
+        for (i, arg) in args.iter().enumerate() {
+            match sig.params.get(i) {
+                Some(param) => {
+                    self.coerce(arg, param, origins.get(i).copied());
+                }
+                None => {
+                    // Promoted once the type is inferred.
+                    let ty = self.expr(arg, None);
+                    self.body.varargs.push((arg.span, ty));
+                }
+            }
+        }
+        sig.ret.clone()
+    }
+
+    /// Returns the function of the impl of a type, or of the type a pointer
+    /// points to, with the given name.
+    fn method(&self, ty: &Ty, name: Symbol) -> Option<DefId> {
+        let ty = match self.infer.shallow(ty) {
+            Ty::Ptr(_, pointee) => self.infer.shallow(&pointee),
+            ty => ty,
+        };
+        let Ty::Adt(id, _) = ty else {
+            return None;
+        };
+        let method = self.res.assoc(id, name)?;
+        (self.res.def(method).kind == DefKind::Method).then_some(method)
+    }
+
+    /// Checks a field expression: an item of an imported module or a field of
+    /// a struct or union, through a pointer if needed.
+    fn field(&mut self, expr: &Expr, base: &Expr, name: Ident) -> Ty {
+        if self.res.get(expr.span).is_some() {
+            return self.value_ty(expr.span);
+        }
+        let base_ty = self.expr(base, None);
+        self.field_ty(base_ty, name)
+    }
+
+    /// Returns the type of a field of a value of the given type.
+    fn field_ty(&mut self, base_ty: Ty, name: Ident) -> Ty {
+        let base_ty = self.infer.resolve(&base_ty);
+        let adt = match &base_ty {
+            Ty::Error => return Ty::Error,
+            Ty::Ptr(_, pointee) => pointee.as_ref(),
+            ty => ty,
+        };
+        if let Ty::Adt(id, _) = adt {
+            if matches!(self.res.def(*id).kind, DefKind::Struct | DefKind::Union) {
+                let fields = self.fields(*id);
+                if let Some(field) = fields.iter().find(|field| field.name.name == name.name) {
+                    if field.vis == Visibility::Private && self.res.def(*id).module != self.module {
+                        let kind = TypeErrorKind::PrivateField { ty: adt.clone(), name: name.name };
+                        self.error(kind, name.span);
+                    }
+                    return field.ty.clone();
+                }
+            }
+        }
+        self.error(TypeErrorKind::NoField { ty: base_ty.clone(), name: name.name }, name.span);
+        Ty::Error
+    }
+
+    /// Checks a struct or union initializer.
+    fn struct_lit(&mut self, expr: &Expr, path: &Path, inits: &[FieldInit]) -> Ty {
+        let ty = match self.res.get(path.span) {
+            Some(Res::SelfTy) => self.self_ty.clone().unwrap_or(Ty::Error),
+            Some(Res::Def(id)) if self.res.def(id).kind == DefKind::TypeAlias => self.alias_ty(id),
+            Some(Res::Def(id)) => Ty::Adt(id, self.res.def(id).name.name),
+            _ => Ty::Error,
+        };
+        let id = match &ty {
+            Ty::Adt(id, _)
+                if matches!(self.res.def(*id).kind, DefKind::Struct | DefKind::Union) =>
+            {
+                *id
+            }
+            Ty::Error => {
+                for init in inits {
+                    self.expr(&init.expr, None);
+                }
+                return Ty::Error;
+            }
+            _ => {
+                let name = path.segments.last().map_or(kw::SelfType, |segment| segment.name);
+                let found = match ty {
+                    Ty::Adt(id, _) => self.res.def(id).kind.describe(),
+                    _ => "type",
+                };
+                self.error(TypeErrorKind::NotAType { name, found }, path.span);
+                for init in inits {
+                    self.expr(&init.expr, None);
+                }
+                return Ty::Error;
+            }
+        };
+
+        let fields = self.fields(id);
+        for init in inits {
+            match fields.iter().find(|field| field.name.name == init.name.name) {
+                Some(field) => {
+                    if field.vis == Visibility::Private && self.res.def(id).module != self.module {
+                        let kind =
+                            TypeErrorKind::PrivateField { ty: ty.clone(), name: init.name.name };
+                        self.error(kind, init.name.span);
+                    }
+                    self.coerce(&init.expr, &field.ty, Some(field.name.span));
+                }
+                None => {
+                    let kind = TypeErrorKind::NoField { ty: ty.clone(), name: init.name.name };
+                    self.error(kind, init.name.span);
+                    self.expr(&init.expr, None);
+                }
+            }
+        }
+
+        if self.res.def(id).kind == DefKind::Union {
+            if inits.len() != 1 {
+                self.error(TypeErrorKind::UnionInit { ty: ty.clone() }, expr.span);
+            }
+        } else {
+            let names: Vec<_> = (fields.iter())
+                .map(|field| field.name.name)
+                .filter(|name| !inits.iter().any(|init| init.name.name == *name))
+                .collect();
+            if !names.is_empty() {
+                self.error(TypeErrorKind::MissingFields { ty: ty.clone(), names }, path.span);
+            }
+        }
+        ty
+    }
+
+    /// Checks a match. Without an expected type, the arms must have the type
+    /// of the first one that completes, unless the value is discarded.
+    fn match_expr(
+        &mut self,
+        scrutinee: &Expr,
+        arms: &[Arm],
+        expected: Option<&Ty>,
+        discard: bool,
+    ) -> Ty {
+        let scrutinee = self.expr(scrutinee, None);
+        let mut result: Option<Ty> = if discard { None } else { expected.cloned() };
+        let mut diverges = !arms.is_empty();
+        for arm in arms {
+            self.pat(&arm.pat, &scrutinee);
+            if let Some(guard) = &arm.guard {
+                self.coerce(guard, &Ty::Bool, None);
+            }
+            let ty = match (&result, discard) {
+                (Some(expected), false) => self.coerce(&arm.body, &expected.clone(), None),
+                _ => self.expr_stmt(&arm.body),
+            };
+            if ty != Ty::Never {
+                diverges = false;
+                if !discard && result.is_none() {
+                    result = Some(ty);
+                }
+            }
+        }
+        match result {
+            _ if diverges => Ty::Never,
+            Some(ty) if !discard => ty,
+            _ => Ty::Void,
+        }
+    }
+
+    /// Returns the type of a literal, given the type expected for it. A number
+    /// literal without suffix that is not expected to be of a number type
+    /// gets a new variable.
+    fn lit(&mut self, expr: &Expr, lit: &Lit, expected: Option<&Ty>) -> Ty {
+        let expected = expected.map(|ty| self.infer.shallow(ty));
+        let (value, ty) = match lit {
+            Lit::Int(lit) => {
+                let ty = match (lit.suffix, expected) {
+                    (Some(suffix), _) => Ty::from_suffix(suffix).unwrap_or(Ty::Error),
+                    (None, Some(ty)) if ty.is_integral() => ty,
+                    (None, _) => self.infer.new_int(),
+                };
+                (LitValue::Int(lit.value()), ty)
+            }
+            Lit::Float(lit) => {
+                let ty = match (lit.suffix, expected) {
+                    (Some(suffix), _) => Ty::from_suffix(suffix).unwrap_or(Ty::Error),
+                    (None, Some(ty @ (Ty::Float(_) | Ty::Infer(InferTy::Float(_))))) => ty,
+                    (None, _) => self.infer.new_float(),
+                };
+                (LitValue::Float(lit.value()), ty)
+            }
+            Lit::Str(_) | Lit::ByteStr(_) => return Ty::Str,
+            Lit::CStr(_) => return Ty::ptr(Ty::Int(IntTy::U8)),
+            Lit::Char(_) => return Ty::Int(IntTy::U32),
+            Lit::Bool(_) => return Ty::Bool,
+            Lit::Null => {
+                return match expected {
+                    Some(ty @ Ty::Ptr(..)) => ty,
+                    _ => Ty::ptr(Ty::Void),
+                };
+            }
+        };
+        let check = LitCheck { span: expr.span, value, ty: ty.clone(), negated: false };
+        self.body.lits.push(check);
+        ty
+    }
+
+    /// Checks a pattern against the type of the matched value, binding the
+    /// names it introduces.
+    fn pat(&mut self, pat: &Pat, ty: &Ty) {
+        match &pat.kind {
+            PatKind::Wild => {}
+            PatKind::Lit(value) => {
+                self.coerce(value, ty, None);
+            }
+            PatKind::Binding(name) => {
+                self.bind(name.span, ty.clone());
+            }
+            PatKind::Path(path) => {
+                let found = self.value_ty(path.span);
+                if !self.infer.coerce(&found, ty) {
+                    let expected = self.infer.resolve(ty);
+                    let kind = TypeErrorKind::Mismatch { expected, found, origin: None };
+                    self.error(kind, pat.span);
+                }
+            }
+            PatKind::TupleStruct(path, fields) => {
+                let sig = match self.res.get(path.span) {
+                    Some(Res::Def(id)) if self.res.def(id).kind == DefKind::Variant => {
+                        Some(self.sig(id))
+                    }
+                    _ => None,
+                };
+                let Some(sig) = sig else {
+                    if self.res.get(path.span).is_some() {
+                        let found = self.value_ty(path.span);
+                        let expected = self.infer.resolve(ty);
+                        let kind = TypeErrorKind::Mismatch { expected, found, origin: None };
+                        self.error(kind, path.span);
+                    }
+                    for field in fields {
+                        self.pat(field, &Ty::Error);
+                    }
+                    return;
+                };
+                if !self.infer.coerce(&sig.ret, ty) {
+                    let kind = TypeErrorKind::Mismatch {
+                        expected: self.infer.resolve(ty),
+                        found: sig.ret.clone(),
+                        origin: None,
+                    };
+                    self.error(kind, pat.span);
+                }
+                if fields.len() != sig.params.len() {
+                    let kind = TypeErrorKind::FieldCount {
+                        variant: path.segments.last().map_or(kw::SelfType, |segment| segment.name),
+                        expected: sig.params.len(),
+                        found: fields.len(),
+                    };
+                    self.error(kind, pat.span);
+                }
+                for (i, field) in fields.iter().enumerate() {
+                    self.pat(field, sig.params.get(i).unwrap_or(&Ty::Error));
+                }
+            }
+            PatKind::Or(pats) => {
+                for pat in pats {
+                    self.pat(pat, ty);
+                }
+            }
+        }
+    }
+}
+
+/// Returns a pointer type as `*const`, so that pointers differing only in
+/// mutability compare equal.
+fn strip_mut(ty: &Ty) -> Ty {
+    match ty {
+        Ty::Ptr(_, pointee) => Ty::Ptr(Mutability::Const, pointee.clone()),
+        ty => ty.clone(),
+    }
+}
+
+/// Returns whether a value of type `from` can be cast to `to`.
+fn can_cast(from: &Ty, to: &Ty, res: &Resolutions) -> bool {
+    match (from, to) {
+        (Ty::Error | Ty::Never, _) | (_, Ty::Error) => true,
+        _ if from == to => true,
+        (from, to) if from.is_numeric() && to.is_numeric() => true,
+        (Ty::Bool, Ty::Int(_)) => true,
+        (Ty::Adt(id, _), Ty::Int(_)) => res.def(*id).kind == DefKind::Enum,
+        (Ty::Ptr(..), Ty::Ptr(..)) => true,
+        (Ty::Ptr(..), Ty::Int(_)) => true,
+        (from, Ty::Ptr(..)) => from.is_integral(),
+        _ => false,
+    }
+}
+
+/// Returns whether a type is fully known and valid.
+fn is_concrete(ty: &Ty) -> bool {
+    match ty {
+        Ty::Param(_) | Ty::Infer(_) | Ty::Error => false,
+        Ty::Ptr(_, pointee) => is_concrete(pointee),
+        Ty::Fn(sig) => sig.params.iter().all(is_concrete) && is_concrete(&sig.ret),
+        _ => true,
+    }
+}
+
+/// Returns whether a number literal fits in its type. The magnitude of a
+/// negated integer is checked against the smallest value of the type, the
+/// sign of an unsigned one being an error of its own.
+fn lit_fits(value: LitValue, ty: &Ty, negated: bool) -> bool {
+    match (value, ty) {
+        (LitValue::Int(None), Ty::Int(_)) => false,
+        (LitValue::Int(Some(value)), Ty::Int(int)) => match (negated, int.is_signed()) {
+            (true, true) => value <= int.min().unsigned_abs(),
+            (true, false) => true,
+            (false, _) => value <= int.max(),
+        },
+        (LitValue::Float(value), Ty::Float(FloatTy::F32)) => (value as f32).is_finite(),
+        (LitValue::Float(value), Ty::Float(FloatTy::F64)) => value.is_finite(),
+        _ => true,
+    }
+}
@@ -0,0 +1,133 @@
+//! Instances of generic functions and their symbol names.
+//!
+//! A generic function is compiled once per list of type arguments it is used
+//! with, each copy being an instance with its own symbol. Symbol names only
+//! depend on the path of the module and the name of the function and on the
+//! type arguments, so they are the same from one build to the next:
+//!
+//! ```text
+//! symbol = "_D" module ident "I" type+ "E"    module, function, arguments
+//! module = ("L" | "S") (ident | "U")+ "E"     local or std module path, `U` for `..`
+//! ident  = length name                        e.g. `4main`, `3sum`
+//! type   = ident                              a primitive type, e.g. `3i32`
+//!        | "A" module ident                   a struct, union or enum
+//!        | "P" type | "M" type                `*const T`, `*mut T`
+//!        | "F" type* "V"? "R" type            a function, `V` if variadic
+//!        | "X"                                `!`
+//! ```
+//!
+//! `sum[i32]` in `main.dal` is `_DL4mainE3sumI3i32E`, `sum[*const u8]` is
+//! `_DL4mainE3sumIP2u8E` and `sum[i32]` in `a/math.dal` is
+//! `_DL1a4mathE3sumI3i32E`.
+
+use super::ty::{FnSig, Ty};
+use crate::{
+    ast::Mutability,
+    loader::{ModuleGraph, ModulePath},
+    resolve::{DefId, Resolutions},
+    symbol::{Symbol, sym},
+};
+use std::{collections::HashMap, fmt::Write};
+
+/// A generic function with type arguments.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Instance {
+    /// The generic function.
+    pub def: DefId,
+    /// Type arguments, one per generic parameter.
+    pub args: Vec<Ty>,
+}
+
+impl Instance {
+    /// Returns the name of the instance as written in the source, e.g.
+    /// `sum[i32]`.
+    pub fn name(&self, res: &Resolutions) -> String {
+        let args: Vec<_> = self.args.iter().map(Ty::to_string).collect();
+        format!("{}[{}]", res.def(self.def).name.name, args.join(", "))
+    }
+
+    /// Returns the symbol name of the instance.
+    pub fn mangle(&self, res: &Resolutions, graph: &ModuleGraph) -> String {
+        let def = res.def(self.def);
+        let mut out = String::from("_D");
+        push_module(&mut out, &graph.get(def.module).path);
+        push_ident(&mut out, def.name.name);
+        out.push('I');
+        for arg in &self.args {
+            push_ty(&mut out, arg, res, graph);
+        }
+        out.push('E');
+        out
+    }
+}
+
+/// Appends a length-prefixed name.
+fn push_ident(out: &mut String, name: Symbol) {
+    let _ = write!(out, "{}{}", name.as_str().len(), name);
+}
+
+/// Appends the encoding of a module path.
+fn push_module(out: &mut String, path: &ModulePath) {
+    out.push(if path.std { 'S' } else { 'L' });
+    for &segment in &path.segments {
+        if segment == sym::dotdot {
+            out.push('U');
+        } else {
+            push_ident(out, segment);
+        }
+    }
+    out.push('E');
+}
+
+/// Appends the encoding of a type.
+fn push_ty(out: &mut String, ty: &Ty, res: &Resolutions, graph: &ModuleGraph) {
+    match ty {
+        Ty::Int(_) | Ty::Float(_) | Ty::Bool | Ty::Str | Ty::Void => {
+            push_ident(out, Symbol::intern(&ty.to_string()))
+        }
+        Ty::Never => out.push('X'),
+        Ty::Ptr(mutability, pointee) => {
+            out.push(if *mutability == Mutability::Mut { 'M' } else { 'P' });
+            push_ty(out, pointee, res, graph);
+        }
+        Ty::Fn(sig) => {
+            out.push('F');
+            for param in &sig.params {
+                push_ty(out, param, res, graph);
+            }
+            if sig.variadic {
+                out.push('V');
+            }
+            out.push('R');
+            push_ty(out, &sig.ret, res, graph);
+        }
+        Ty::Adt(id, name) => {
+            out.push('A');
+            push_module(out, &graph.get(res.def(*id).module).path);
+            push_ident(out, *name);
+        }
+        Ty::Param(_) | Ty::Infer(_) | Ty::Error => {
+            unreachable!("type arguments of an instance are concrete")
+        }
+    }
+}
+
+/// Returns the type with the generic parameters replaced by their arguments.
+pub fn subst(ty: &Ty, args: &HashMap<Symbol, Ty>) -> Ty {
+    match ty {
+        Ty::Param(name) => args.get(name).cloned().unwrap_or_else(|| ty.clone()),
+        Ty::Ptr(mutability, pointee) => Ty::Ptr(*mutability, Box::new(subst(pointee, args))),
+        Ty::Fn(sig) => Ty::Fn(Box::new(subst_sig(sig, args))),
+        ty => ty.clone(),
+    }
+}
+
+/// Returns the signature with the generic parameters replaced by their
+/// arguments.
+pub fn subst_sig(sig: &FnSig, args: &HashMap<Symbol, Ty>) -> FnSig {
+    FnSig {
+        params: sig.params.iter().map(|param| subst(param, args)).collect(),
+        ret: subst(&sig.ret, args),
+        variadic: sig.variadic,
+    }
+}
@@ -0,0 +1,229 @@
+//! Semantic types.
+
+use crate::{ast::Mutability, resolve::DefId, resolve::PrimTy, symbol::Symbol};
+use std::fmt;
+
+/// Integer type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IntTy {
+    I8,
+    I16,
+    I32,
+    I64,
+    Isize,
+    U8,
+    U16,
+    U32,
+    U64,
+    Usize,
+}
+
+impl IntTy {
+    /// Returns whether the type is signed.
+    pub fn is_signed(self) -> bool {
+        matches!(self, IntTy::I8 | IntTy::I16 | IntTy::I32 | IntTy::I64 | IntTy::Isize)
+    }
+
+    /// Returns the width of the type in bits. Pointer sized types are 64
+    /// bits wide, the only pointer width targeted.
+    pub fn bits(self) -> u32 {
+        match self {
+            IntTy::I8 | IntTy::U8 => 8,
+            IntTy::I16 | IntTy::U16 => 16,
+            IntTy::I32 | IntTy::U32 => 32,
+            IntTy::I64 | IntTy::U64 | IntTy::Isize | IntTy::Usize => 64,
+        }
+    }
+
+    /// Returns the smallest value of the type.
+    pub fn min(self) -> i128 {
+        if self.is_signed() { -(1 << (self.bits() - 1)) } else { 0 }
+    }
+
+    /// Returns the largest value of the type.
+    pub fn max(self) -> u128 {
+        if self.is_signed() {
+            (1 << (self.bits() - 1)) - 1
+        } else {
+            u128::MAX >> (128 - self.bits())
+        }
+    }
+
+    /// Returns the name of the type.
+    pub fn name(self) -> &'static str {
+        match self {
+            IntTy::I8 => "i8",
+            IntTy::I16 => "i16",
+            IntTy::I32 => "i32",
+            IntTy::I64 => "i64",
+            IntTy::Isize => "isize",
+            IntTy::U8 => "u8",
+            IntTy::U16 => "u16",
+            IntTy::U32 => "u32",
+            IntTy::U64 => "u64",
+            IntTy::Usize => "usize",
+        }
+    }
+}
+
+/// Floating point type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FloatTy {
+    F32,
+    F64,
+}
+
+impl FloatTy {
+    /// Returns the name of the type.
+    pub fn name(self) -> &'static str {
+        match self {
+            FloatTy::F32 => "f32",
+            FloatTy::F64 => "f64",
+        }
+    }
+}
+
+/// Type of a value.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Ty {
+    Int(IntTy),
+    Float(FloatTy),
+    Bool,
+    /// `str`, the type of string and byte string literals, whose bytes are
+    /// not NUL-terminated unlike those of C string literals.
+    Str,
+    /// The type of expressions without a value.
+    Void,
+    /// `!`, the type of expressions that never complete, e.g. `return`.
+    Never,
+    /// `*const T` or `*mut T`.
+    Ptr(Mutability, Box<Ty>),
+    /// The type of a function.
+    Fn(Box<FnSig>),
+    /// A struct, union or enum, with its name.
+    Adt(DefId, Symbol),
+    /// A generic parameter, or `Self` in a trait.
+    Param(Symbol),
+    /// An inference variable, only found while a body is checked.
+    Infer(InferTy),
+    /// The type of an expression with an error, compatible with every type
+    /// so that the error is reported once.
+    Error,
+}
+
+/// Inference variable, by index in the inference table of the body being
+/// checked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InferTy {
+    /// A variable of any type.
+    Var(u32),
+    /// The type of an integer literal without suffix.
+    Int(u32),
+    /// The type of a float literal without suffix.
+    Float(u32),
+}
+
+impl InferTy {
+    /// Returns the index of the variable.
+    pub fn index(self) -> usize {
+        match self {
+            InferTy::Var(index) | InferTy::Int(index) | InferTy::Float(index) => index as usize,
+        }
+    }
+}
+
+impl Ty {
+    /// Returns the type of a primitive type.
+    pub fn from_prim(prim: PrimTy) -> Ty {
+        match prim {
+            PrimTy::I8 => Ty::Int(IntTy::I8),
+            PrimTy::I16 => Ty::Int(IntTy::I16),
+            PrimTy::I32 => Ty::Int(IntTy::I32),
+            PrimTy::I64 => Ty::Int(IntTy::I64),
+            PrimTy::Isize => Ty::Int(IntTy::Isize),
+            PrimTy::U8 => Ty::Int(IntTy::U8),
+            PrimTy::U16 => Ty::Int(IntTy::U16),
+            PrimTy::U32 => Ty::Int(IntTy::U32),
+            PrimTy::U64 => Ty::Int(IntTy::U64),
+            PrimTy::Usize => Ty::Int(IntTy::Usize),
+            PrimTy::F32 => Ty::Float(FloatTy::F32),
+            PrimTy::F64 => Ty::Float(FloatTy::F64),
+            PrimTy::Bool => Ty::Bool,
+            PrimTy::Void => Ty::Void,
+        }
+    }
+
+    /// Returns the type named by a literal suffix, e.g. `u8` in `255u8`.
+    pub fn from_suffix(suffix: Symbol) -> Option<Ty> {
+        PrimTy::from_name(suffix).map(Ty::from_prim)
+    }
+
+    /// Returns `*const T`.
+    pub fn ptr(pointee: Ty) -> Ty {
+        Ty::Ptr(Mutability::Const, Box::new(pointee))
+    }
+
+    /// Returns whether the type is an integer type, or the type of an
+    /// integer literal.
+    pub fn is_integral(&self) -> bool {
+        matches!(self, Ty::Int(_) | Ty::Infer(InferTy::Int(_)))
+    }
+
+    /// Returns whether the type is an integer or floating point type, or the
+    /// type of a number literal.
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, Ty::Int(_) | Ty::Float(_) | Ty::Infer(InferTy::Int(_) | InferTy::Float(_)))
+    }
+
+    /// Returns whether the type is `!` or the error type, which coerce to
+    /// every type.
+    pub fn is_never(&self) -> bool {
+        matches!(self, Ty::Never | Ty::Error)
+    }
+}
+
+impl fmt::Display for Ty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Ty::Int(ty) => f.write_str(ty.name()),
+            Ty::Float(ty) => f.write_str(ty.name()),
+            Ty::Bool => f.write_str("bool"),
+            Ty::Str => f.write_str("str"),
+            Ty::Void => f.write_str("void"),
+            Ty::Never => f.write_str("!"),
+            Ty::Ptr(Mutability::Const, pointee) => write!(f, "*const {}", pointee),
+            Ty::Ptr(Mutability::Mut, pointee) => write!(f, "*mut {}", pointee),
+            Ty::Fn(sig) => write!(f, "{}", sig),
+            Ty::Adt(_, name) | Ty::Param(name) => write!(f, "{}", name),
+            Ty::Infer(InferTy::Var(_)) => f.write_str("_"),
+            Ty::Infer(InferTy::Int(_)) => f.write_str("{integer}"),
+            Ty::Infer(InferTy::Float(_)) => f.write_str("{float}"),
+            Ty::Error => f.write_str("{error}"),
+        }
+    }
+}
+
+/// Signature of a function.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FnSig {
+    /// Types of the parameters, `self` included.
+    pub params: Vec<Ty>,
+    /// Return type, `void` when not written.
+    pub ret: Ty,
+    /// Whether the function takes extra arguments after its parameters.
+    pub variadic: bool,
+}
+
+impl fmt::Display for FnSig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut params: Vec<_> = self.params.iter().map(Ty::to_string).collect();
+        if self.variadic {
+            params.push("...".to_string());
+        }
+        write!(f, "fn({})", params.join(", "))?;
+        if self.ret != Ty::Void {
+            write!(f, " -> {}", self.ret)?;
+        }
+        Ok(())
+    }
+}
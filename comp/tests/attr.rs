@@ -0,0 +1,241 @@
+use dal::{
+    ast::{ItemKind, Module},
+    attr::{
+        self, Attrs, CfgSet, Inline, Target,
+        error::{AttrError, AttrErrorKind},
+    },
+    lexer::Lexer,
+    loader::Loader,
+    parser::Parser,
+    resolve,
+    span::SourceMap,
+    symbol::{Symbol, sym},
+};
+use std::{fs, path::PathBuf};
+
+/// Parses a source text as a module.
+fn parse(source: &str) -> Module {
+    let file = SourceMap::new().add_virtual_file("test.dal", source);
+    let (tokens, _) = Lexer::new(&file).lex();
+    Parser::new(tokens).parse_module().unwrap()
+}
+
+/// Returns the codes of the attribute errors of a source text.
+fn codes(source: &str) -> Vec<&'static str> {
+    let errors = attr::check_module(&parse(source));
+    errors.iter().map(|err| err.to_diagnostic().code.unwrap()).collect()
+}
+
+/// Returns the names of the top level functions left after stripping with
+/// the given configuration.
+fn stripped(source: &str, set: &CfgSet) -> Vec<&'static str> {
+    let mut module = parse(source);
+    attr::strip_cfg(&mut module, set);
+    module
+        .items
+        .iter()
+        .filter_map(|item| match &item.kind {
+            ItemKind::Fn(func) => Some(func.name.name.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Writes the files of a program to a new directory, loads the first file
+/// with the given configuration and resolves it. Returns the diagnostics of
+/// both steps as `code: message`.
+fn check_program(name: &str, files: &[(&str, &str)], cfg: CfgSet) -> Vec<String> {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("attr").join(name);
+    let _ = fs::remove_dir_all(&dir);
+    for (path, source) in files {
+        let path = dir.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, source).unwrap();
+    }
+    let root = dir.join(files[0].0).to_string_lossy().to_string();
+    let mut sm = SourceMap::new();
+    let (graph, mut diags) = Loader::new(&mut sm, dir.join("std"), cfg).load(&root);
+    let (_, errors) = resolve::resolve(&graph);
+    diags.extend(errors.iter().map(|err| err.to_diagnostic()));
+    diags.iter().map(|diag| format!("{}: {}", diag.code.unwrap(), diag.message)).collect()
+}
+
+#[test]
+fn valid_attributes_are_lowered() {
+    let module = parse(
+        "@inline\n@deprecated(\"use g\")\n@cfg(unix)\nfn f() {}\n@align(16)\n@packed\nstruct S { x: u8 }",
+    );
+    assert!(attr::check_module(&module).is_empty());
+
+    let (attrs, errors) = Attrs::lower(&module.items[0].attrs, Target::Fn);
+    assert!(errors.is_empty());
+    assert_eq!(attrs.inline, Some(Inline::Always));
+    assert_eq!(attrs.deprecated, Some(Some(Symbol::intern("use g"))));
+    assert_eq!(attrs.cfg.len(), 1);
+
+    let (attrs, _) = Attrs::lower(&module.items[1].attrs, Target::Struct);
+    assert_eq!(attrs.align, Some(16));
+    assert!(attrs.packed);
+}
+
+#[test]
+fn unknown_attributes_are_reported() {
+    assert_eq!(codes("@inlin\nfn f() {}"), ["E0200"]);
+    let errors = attr::check_module(&parse("@frobnicate\nfn f() {}"));
+    assert!(matches!(&errors[..], [AttrError { kind: AttrErrorKind::Unknown(_), .. }]));
+    assert!(!errors[0].to_diagnostic().help.is_empty());
+}
+
+#[test]
+fn misplaced_attributes_are_reported() {
+    assert_eq!(codes("@packed\nfn f() {}"), ["E0201"]);
+    assert_eq!(codes("@link(\"c\")\nfn f() {}"), ["E0201"]);
+    assert_eq!(codes("extern {\n    @export\n    fn f()\n}"), ["E0201"]);
+    assert_eq!(codes("extern {\n    @extern_name(\"puts\")\n    fn f()\n}"), Vec::<&str>::new());
+}
+
+#[test]
+fn invalid_arguments_are_reported() {
+    assert_eq!(codes("@inline(1)\nfn f() {}"), ["E0202"]);
+    assert_eq!(codes("@align(3)\nstruct S { x: u8 }"), ["E0202"]);
+    assert_eq!(codes("@link\nextern {}"), ["E0202"]);
+    assert_eq!(codes("@cfg(1 + 2)\nfn f() {}"), ["E0202"]);
+}
+
+#[test]
+fn duplicate_and_conflicting_attributes_are_reported() {
+    assert_eq!(codes("@inline\n@inline\nfn f() {}"), ["E0203"]);
+    assert_eq!(codes("@link(\"c\")\n@link(\"m\")\nextern {}"), Vec::<&str>::new());
+    assert_eq!(codes("@inline\n@noinline\nfn f() {}"), ["E0204"]);
+}
+
+#[test]
+fn disabled_cfg_items_are_stripped() {
+    let source = "
+@cfg(unix)
+fn on_unix() {}
+@cfg(not(unix))
+fn not_unix() {}
+@cfg(all(unix, target_os = \"linux\"))
+fn on_linux() {}
+@cfg(any(windows, target_os = \"macos\"))
+fn on_other() {}
+@cfg(unix)
+@cfg(windows)
+fn never() {}
+fn always() {}
+";
+    let linux = CfgSet::default().flag(Symbol::intern("unix")).value(sym::target_os, "linux");
+    assert_eq!(stripped(source, &linux), ["on_unix", "on_linux", "always"]);
+    let windows = CfgSet::default().flag(Symbol::intern("windows"));
+    assert_eq!(stripped(source, &windows), ["not_unix", "on_other", "always"]);
+}
+
+#[test]
+fn nested_items_are_stripped() {
+    let mut module = parse("extern {\n    @cfg(windows)\n    fn a()\n    fn b()\n}");
+    attr::strip_cfg(&mut module, &CfgSet::default());
+    let ItemKind::Extern(block) = &module.items[0].kind else { panic!("not an extern block") };
+    assert_eq!(block.items.len(), 1);
+}
+
+#[test]
+fn uses_of_deprecated_items_are_warned() {
+    let files = [
+        (
+            "main.dal",
+            "import \"math\"
+
+@deprecated(\"use `add`\")
+fn plus(a: i32, b: i32) -> i32 {
+    return plus(a, b)
+}
+
+@deprecated
+struct Old {}
+
+struct Point {}
+
+impl Point {
+    @deprecated
+    fn origin() -> Point { return Point {} }
+}
+
+fn main() {
+    let a = plus(1, 2)
+    let o: *const Old = null
+    let p = Point::origin()
+    math.old()
+}
+",
+        ),
+        ("math.dal", "module math\n@deprecated\npub fn old() {}\n"),
+    ];
+    let warnings = check_program("deprecated", &files, CfgSet::default());
+    assert_eq!(
+        warnings,
+        [
+            "W0400: use of deprecated function `plus`",
+            "W0400: use of deprecated struct `Old`",
+            "W0400: use of deprecated associated function `origin`",
+            "W0400: use of deprecated function `old`",
+        ]
+    );
+}
+
+#[test]
+fn deprecation_follows_cfg_and_attribute_errors() {
+    let source = "
+@cfg(unix)
+@deprecated
+fn f() {}
+@cfg(not(unix))
+fn f() {}
+
+@deprecated(1)
+fn g() {}
+
+@deprecated
+@deprecated
+fn h() {}
+
+@deprecate
+fn i() {}
+
+@deprecated
+@inline
+@noinline
+fn j() {}
+
+@deprecated
+extern {}
+
+fn main() {
+    f()
+    g()
+    h()
+    i()
+    j()
+}
+";
+    let unix = CfgSet::default().flag(Symbol::intern("unix"));
+    assert_eq!(
+        check_program("deprecated_unix", &[("main.dal", source)], unix),
+        [
+            "E0202: attribute `deprecated` expects no arguments or a string",
+            "E0203: attribute `deprecated` is used more than once",
+            "E0200: unknown attribute `deprecate`",
+            "E0204: attribute `noinline` cannot be used together with `inline`",
+            "E0201: attribute `deprecated` cannot be used on an extern block",
+            "W0400: use of deprecated function `f`",
+            "W0400: use of deprecated function `h`",
+            "W0400: use of deprecated function `j`",
+        ]
+    );
+    // Without `unix`, `f` is the other declaration, which is not deprecated.
+    let other = check_program("deprecated_other", &[("main.dal", source)], CfgSet::default());
+    assert_eq!(
+        other[5..],
+        ["W0400: use of deprecated function `h`", "W0400: use of deprecated function `j`"]
+    );
+}
@@ -0,0 +1,87 @@
+use std::{fs, path::PathBuf, process::Command};
+
+/// Runs the compiler on a source file with the given arguments, returning its
+/// stdout and stderr.
+fn run(name: &str, source: &str, args: &[&str]) -> (String, String) {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("cli");
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    fs::write(&path, source).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_dal"))
+        .arg("compile")
+        .args(args)
+        .arg(&path)
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    (stdout, stderr)
+}
+
+#[test]
+fn diagnostics_go_to_stderr() {
+    let (stdout, stderr) = run("lex_error.dal", "let x = $\n", &[]);
+    assert!(stdout.is_empty(), "{}", stdout);
+    assert!(stderr.contains("E0001"), "{}", stderr);
+    assert!(!stderr.contains("\x1b["), "{}", stderr);
+}
+
+#[test]
+fn json_diagnostics_are_one_line_each() {
+    let (stdout, stderr) = run("json.dal", "let x = $ $\n", &["--error-format", "json"]);
+    assert!(stdout.is_empty(), "{}", stdout);
+    assert_eq!(
+        stderr.lines().filter(|line| line.starts_with("{\"code\":\"E0001\"")).count(),
+        2,
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn tokens_and_ast_are_dumped_to_stdout() {
+    let source = "fn main() {\n    let x = 1 + 2\n}\n";
+    let (stdout, stderr) = run("emit.dal", source, &["--emit", "tokens"]);
+    assert!(stderr.is_empty(), "{}", stderr);
+    assert_eq!(stdout.lines().count(), 12, "{}", stdout);
+    assert!(stdout.starts_with("1:1    Fn           \"fn\"\n"), "{}", stdout);
+
+    let (stdout, _) = run("emit.dal", source, &["--emit", "ast"]);
+    let expected = "Module 1:1..3:2
+  Fn main 1:1..3:2
+    Block 1:11..3:2
+      Let x 2:5..2:18
+        Binary + 2:13..2:18
+          Lit 1 2:13..2:14
+          Lit 2 2:17..2:18
+";
+    assert_eq!(stdout, expected);
+
+    for emit in ["tokens-json", "ast-json"] {
+        let (stdout, _) = run("emit.dal", source, &["--emit", emit]);
+        assert!(stdout.lines().all(|line| line.starts_with("{\"kind\":")), "{}", stdout);
+    }
+}
+
+#[test]
+fn diagnostics_do_not_mix_with_dumps() {
+    for format in ["human", "json"] {
+        let (stdout, stderr) =
+            run("dump.dal", "let x = $\n", &["--emit", "tokens", "--error-format", format]);
+        assert_eq!(stdout.lines().count(), 4, "{}", stdout);
+        assert!(!stdout.contains("E0001"), "{}", stdout);
+        assert!(stderr.contains("E0001"), "{}", stderr);
+    }
+}
+
+#[test]
+fn dumps_can_be_written_to_a_file() {
+    let out = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("cli").join("out.tokens");
+    let (stdout, _) = run("out.dal", "x", &["--emit", "tokens", "-o", &out.to_string_lossy()]);
+    assert!(stdout.is_empty(), "{}", stdout);
+    assert_eq!(fs::read_to_string(&out).unwrap(), "1:1    Identifier   \"x\"\n");
+
+    let (_, stderr) = run("out.dal", "x", &["--emit", "bogus"]);
+    assert!(stderr.contains("unknown emit type `bogus`"), "{}", stderr);
+}
@@ -0,0 +1,95 @@
+use dal::{
+    cst::{SyntaxKind, SyntaxNode},
+    diag::Diagnostic,
+    lexer::Lexer,
+    parser::cst::parse_cst,
+    span::SourceMap,
+};
+
+/// Lexes a source text losslessly and parses it into a concrete syntax tree.
+fn cst(source: &str) -> (SyntaxNode, Option<Diagnostic>) {
+    let file = SourceMap::new().add_virtual_file("test.dal", source);
+    let (tokens, _) = Lexer::new(&file).lossless().lex();
+    parse_cst(tokens, &file)
+}
+
+/// Asserts that printing the tree of a source text gives it back unchanged.
+fn assert_round_trip(source: &str) -> Option<Diagnostic> {
+    let (root, error) = cst(source);
+    assert_eq!(root.to_string(), source);
+    assert_eq!(root.kind(), SyntaxKind::SourceFile);
+    error
+}
+
+#[test]
+fn valid_sources_round_trip() {
+    let sources = [
+        "",
+        "module main\n",
+        "// leading comment\nmodule main // trailing\n\n\n",
+        "/// Adds numbers.\n@[inline]\npub fn add(a: i32, b: i32) -> i32 {\n    return a + b /* sum */;\n}\n",
+        "import \"std/io\"\nextern {\n    fn printf(fmt: *const u8, ...) -> i32\n}\n",
+        "struct P[T] { x: T }\nenum E { A, B(u8) = 2 }\nfn f() { match x { 1 | 2 => 3, _ => 4 } }",
+        "fn main() {\n    let s = \"a\\n\" ; let c = c\"hi\"\n    for i in 0..10 { }   \n}",
+    ];
+    for source in sources {
+        let error = assert_round_trip(source);
+        assert!(error.is_none(), "{:?}: {:?}", source, error);
+    }
+}
+
+#[test]
+fn invalid_sources_round_trip() {
+    let sources = [
+        "fn (x: i32) {}",
+        "fn main() { let = }\nfn other() {}\n",
+        "fn main() { a < b < c }",
+        "struct { x }",
+        "fn f() { x $ y }",
+        "fn f() { \"unterminated }\n",
+    ];
+    for source in sources {
+        assert!(assert_round_trip(source).is_some(), "{:?}", source);
+    }
+}
+
+#[test]
+fn bom_crlf_and_unterminated_comments_round_trip() {
+    assert_round_trip("\u{feff}module main\n");
+    assert_round_trip("module main\r\nfn main() {\r\n    return\r\n}\r\n");
+    assert_round_trip("module main\n/* unterminated /* nested */");
+    assert_round_trip("fn f() {} /// doc at the end");
+    assert_round_trip("\u{feff}fn main() {\r\n\treturn\u{2028}}\r\n");
+}
+
+#[test]
+fn nodes_cover_their_tokens() {
+    let source = "// c\nfn add(a: i32) -> i32 { a + 1 }\n";
+    let (root, _) = cst(source);
+    let func = root.child_nodes().into_iter().find(|node| node.kind() == SyntaxKind::Fn).unwrap();
+    let span = func.span();
+    assert_eq!(&source[span.lo..span.hi], "fn add(a: i32) -> i32 { a + 1 }");
+    assert!(func.child_nodes().iter().any(|node| node.kind() == SyntaxKind::ParamList));
+
+    let tokens: Vec<_> = root.tokens().iter().map(|token| token.text().to_string()).collect();
+    assert_eq!(tokens.concat(), source);
+    let trivia = root.tokens().iter().filter(|token| token.is_trivia()).count();
+    assert!(trivia >= 3, "{:?}", tokens);
+}
+
+#[test]
+fn function_types_are_nodes() {
+    let source = "fn apply(f: fn(i32) -> i32) {}";
+    let (root, error) = cst(source);
+    assert!(error.is_none(), "{:?}", error);
+    let mut nodes = root.child_nodes();
+    let mut fn_types = Vec::new();
+    while let Some(node) = nodes.pop() {
+        if node.kind() == SyntaxKind::FnType {
+            fn_types.push(node.span());
+        }
+        nodes.extend(node.child_nodes());
+    }
+    let [span] = fn_types[..] else { panic!("{:?}", fn_types) };
+    assert_eq!(&source[span.lo..span.hi], "fn(i32) -> i32");
+}
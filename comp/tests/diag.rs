@@ -0,0 +1,108 @@
+use dal::{
+    diag::{Diagnostic, Emitter, ErrorFormat, json},
+    span::{SourceMap, Span},
+};
+
+/// Creates a source map holding only the given source text.
+fn source_map(source: &str) -> SourceMap {
+    let mut sm = SourceMap::new();
+    sm.add_virtual_file("test.dal", source);
+    sm
+}
+
+#[test]
+fn renders_labels_under_the_source() {
+    let sm = source_map("let x = 1\nlet y = x + true\n");
+    let diag = Diagnostic::error("mismatched types")
+        .code("E0100")
+        .primary(Span::new(22, 26), "expected integer")
+        .secondary(Span::new(18, 19), "this is an integer")
+        .help("remove the `+ true`");
+    let out = Emitter::new(ErrorFormat::Human, false).render(&diag, Some(&sm));
+    let expected = "error[E0100]: mismatched types
+ --> test.dal:2:13
+  |
+2 | let y = x + true
+  |         - this is an integer
+  |             ^^^^ expected integer
+  |
+  = help: remove the `+ true`
+";
+    assert_eq!(out, expected);
+}
+
+#[test]
+fn tabs_and_wide_lines_line_up() {
+    let sm = source_map("\tlet é = $");
+    let diag = Diagnostic::error("unknown character").primary(Span::new(10, 11), "");
+    let out = Emitter::new(ErrorFormat::Human, false).render(&diag, Some(&sm));
+    assert!(out.contains("test.dal:1:10"), "{}", out);
+    assert!(out.ends_with("1 |     let é = $\n  |             ^\n"), "{}", out);
+}
+
+#[test]
+fn diagnostics_without_source_have_no_snippet() {
+    let diag = Diagnostic::error("no target specified").note("pass a file to compile");
+    let out = Emitter::new(ErrorFormat::Human, false).render(&diag, None);
+    assert_eq!(out, "error: no target specified\nnote: pass a file to compile\n");
+}
+
+#[test]
+fn colors_are_optional() {
+    let diag = Diagnostic::warning("unused");
+    assert!(Emitter::new(ErrorFormat::Human, true).render(&diag, None).contains("\x1b["));
+    assert!(!Emitter::new(ErrorFormat::Human, false).render(&diag, None).contains("\x1b["));
+}
+
+#[test]
+fn json_has_spans_and_fixes() {
+    let sm = source_map("let x = \"a\tb\"\nlet é = y");
+    let diag = Diagnostic::error("cannot find `y`")
+        .code("E0300")
+        .primary(Span::new(23, 24), "not found")
+        .suggest("a local with a similar name exists", Span::new(23, 24), "x");
+    let json = json::to_json(&diag, Some(&sm));
+    let span = r#"{"file":"test.dal","byte_start":23,"byte_end":24,"line_start":2,"column_start":9,"line_end":2,"column_end":10}"#;
+    let expected = format!(
+        r#"{{"code":"E0300","severity":"error","message":"cannot find `y`","file":"test.dal","span":{span},"labels":[{{"primary":true,"message":"not found","span":{span}}}],"notes":[],"help":["a local with a similar name exists"],"fixes":[{{"message":"a local with a similar name exists","span":{span},"replacement":"x"}}]}}"#,
+    );
+    assert_eq!(json, expected);
+}
+
+#[test]
+fn json_escapes_strings() {
+    let diag = Diagnostic::warning("a \"quoted\"\n\\ \u{1}");
+    let json = json::to_json(&diag, None);
+    assert!(json.contains(r#""message":"a \"quoted\"\n\\ \u0001""#), "{}", json);
+    assert!(json.contains(r#""code":null"#) && json.contains(r#""span":null"#), "{}", json);
+}
+
+#[test]
+fn labels_in_other_files_get_their_own_header() {
+    let mut sm = SourceMap::new();
+    let a = sm.add_virtual_file("a.dal", "fn f() {}\n");
+    let b = sm.add_virtual_file("b.dal", "\nf(1)\n");
+    let diag = Diagnostic::error("wrong number of arguments")
+        .primary(Span::new(b.start + 1, b.start + 5), "one argument given")
+        .secondary(Span::new(a.start + 3, a.start + 4), "defined here");
+    let out = Emitter::new(ErrorFormat::Human, false).render(&diag, Some(&sm));
+    let expected = "error: wrong number of arguments
+ --> b.dal:2:1
+  |
+2 | f(1)
+  | ^^^^ one argument given
+ ::: a.dal:1:4
+  |
+1 | fn f() {}
+  |    - defined here
+";
+    assert_eq!(out, expected);
+}
+
+#[test]
+fn bidi_controls_are_escaped_in_snippets() {
+    let sm = source_map("let s = \"\u{202e}\" + x");
+    let diag = Diagnostic::error("cannot find `x`").primary(Span::new(16, 17), "");
+    let out = Emitter::new(ErrorFormat::Human, false).render(&diag, Some(&sm));
+    assert!(out.ends_with("1 | let s = \"<U+202E>\" + x\n  |                      ^\n"), "{}", out);
+}
@@ -0,0 +1,419 @@
+use dal::{
+    lexer::{
+        Lexer,
+        error::{LexError, LexErrorKind},
+        token::{Base, DocStyle, FloatLit, IntLit, Token, TokenKind},
+    },
+    span::{SourceMap, Span},
+    symbol::{Symbol, sym},
+};
+
+/// Lexes a source text.
+fn lex(source: &str) -> (Vec<Token>, Vec<LexError>) {
+    let file = SourceMap::new().add_virtual_file("test.dal", source);
+    Lexer::new(&file).lex()
+}
+
+/// Lexes a source text in normal or lossless mode, returning its tokens and
+/// the number of errors.
+fn lex_mode(source: &str, lossless: bool) -> (Vec<Token>, usize) {
+    let file = SourceMap::new().add_virtual_file("test.dal", source);
+    let lexer = Lexer::new(&file);
+    let lexer = if lossless { lexer.lossless() } else { lexer };
+    let (tokens, errors) = lexer.lex();
+    (tokens, errors.len())
+}
+
+/// Returns the kinds of the tokens of a source text.
+fn kinds(source: &str) -> Vec<TokenKind> {
+    lex(source).0.into_iter().map(|tok| tok.kind).collect()
+}
+
+/// Returns the kinds of the errors found in a source text.
+fn errors(source: &str) -> Vec<LexErrorKind> {
+    lex(source).1.into_iter().map(|err| err.kind).collect()
+}
+
+#[test]
+fn lexing_goes_on_after_errors() {
+    let (tokens, errors) = lex("let $ x = # 1");
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].kind, LexErrorKind::UnknownChar('$'));
+    assert_eq!(errors[0].span.lo, 4);
+    assert_eq!(errors[1].kind, LexErrorKind::UnknownChar('#'));
+    let kinds: Vec<_> = tokens.into_iter().map(|tok| tok.kind).collect();
+    assert_eq!(
+        kinds,
+        [
+            TokenKind::Let,
+            TokenKind::Unknown,
+            TokenKind::Identifier(Symbol::intern("x")),
+            TokenKind::Equal,
+            TokenKind::Unknown,
+            TokenKind::Integer(IntLit {
+                digits: Symbol::intern("1"),
+                base: Base::Decimal,
+                suffix: None
+            }),
+        ]
+    );
+}
+
+#[test]
+fn unterminated_string_is_reported() {
+    assert_eq!(errors("let s = \"abc"), [LexErrorKind::UnterminatedString]);
+    assert_eq!(kinds("\"abc")[0], TokenKind::String(Symbol::intern("abc")));
+}
+
+#[test]
+fn valid_source_has_no_errors() {
+    assert!(errors("fn main() {\n    io.println(\"hi\")\n}\n").is_empty());
+}
+
+#[test]
+fn comments_are_skipped() {
+    let source = "a // line\n/* block /* nested */ still */ b /**/ c";
+    assert_eq!(
+        kinds(source),
+        ["a", "b", "c"].map(|name| TokenKind::Identifier(Symbol::intern(name)))
+    );
+    assert!(errors(source).is_empty());
+}
+
+#[test]
+fn doc_comments_are_tokens() {
+    assert_eq!(
+        kinds("//! module\n/// item\n//// not doc\nfn"),
+        [
+            TokenKind::DocComment(DocStyle::Inner, Symbol::intern(" module")),
+            TokenKind::DocComment(DocStyle::Outer, Symbol::intern(" item")),
+            TokenKind::Fn,
+        ]
+    );
+}
+
+#[test]
+fn unterminated_block_comment_is_reported() {
+    assert_eq!(errors("a /* b /* c */"), [LexErrorKind::UnterminatedBlockComment]);
+}
+
+/// Returns the only token of a number literal.
+fn number(source: &str) -> TokenKind {
+    let (tokens, errors) = lex(source);
+    assert!(errors.is_empty(), "{}: {:?}", source, errors);
+    assert_eq!(tokens.len(), 1, "{}", source);
+    tokens[0].kind.clone()
+}
+
+/// Returns an integer literal token.
+fn int(digits: &str, base: Base, suffix: Option<&str>) -> TokenKind {
+    let suffix = suffix.map(Symbol::intern);
+    TokenKind::Integer(IntLit { digits: Symbol::intern(digits), base, suffix })
+}
+
+/// Returns a float literal token.
+fn float(digits: &str, suffix: Option<&str>) -> TokenKind {
+    TokenKind::Float(FloatLit {
+        digits: Symbol::intern(digits),
+        suffix: suffix.map(Symbol::intern),
+    })
+}
+
+#[test]
+fn integer_literals() {
+    assert_eq!(number("1_000"), int("1000", Base::Decimal, None));
+    assert_eq!(number("0xff_FFu16"), int("ffFF", Base::Hexadecimal, Some("u16")));
+    assert_eq!(number("0o17"), int("17", Base::Octal, None));
+    assert_eq!(number("0b1010i8"), int("1010", Base::Binary, Some("i8")));
+    let TokenKind::Integer(lit) = number("0xff") else { panic!("not an integer") };
+    assert_eq!(lit.value(), Some(255));
+}
+
+#[test]
+fn float_literals() {
+    assert_eq!(number("1.5"), float("1.5", None));
+    assert_eq!(number("2e10"), float("2e10", None));
+    assert_eq!(number("1_0.2_5E-3f32"), float("10.25E-3", Some("f32")));
+    assert_eq!(number("1f64"), float("1", Some("f64")));
+    let TokenKind::Float(lit) = number("2.5e1") else { panic!("not a float") };
+    assert_eq!(lit.value(), 25.0);
+}
+
+#[test]
+fn dot_after_integer_is_not_a_float() {
+    let kinds = kinds("1.foo 1..2");
+    assert_eq!(kinds[0], int("1", Base::Decimal, None));
+    assert_eq!(kinds[1], TokenKind::Dot);
+    assert_eq!(kinds[3], int("1", Base::Decimal, None));
+}
+
+#[test]
+fn malformed_number_literals_are_reported() {
+    assert_eq!(errors("0b102"), [LexErrorKind::InvalidDigit('2', 2)]);
+    assert_eq!(errors("0x"), [LexErrorKind::MissingDigits]);
+    assert_eq!(errors("1e+"), [LexErrorKind::MissingExponent]);
+    assert_eq!(errors("12ab + 1"), [LexErrorKind::InvalidSuffix("ab".to_string())]);
+    assert_eq!(kinds("12ab + 1").len(), 3);
+}
+
+/// Lexes `source` and returns its only token, asserting there are no errors.
+fn literal(source: &str) -> TokenKind {
+    let (tokens, errors) = lex(source);
+    assert!(errors.is_empty(), "{source}: {errors:?}");
+    assert_eq!(tokens.len(), 1, "{source}");
+    tokens[0].kind.clone()
+}
+
+#[test]
+fn string_escapes_are_decoded() {
+    assert_eq!(
+        literal(r#""a\n\t\\\"\x41\u{1F600}""#),
+        TokenKind::String(Symbol::intern("a\n\t\\\"A😀"))
+    );
+    assert_eq!(literal(r"'\''"), TokenKind::Char('\''));
+    assert_eq!(literal("'é'"), TokenKind::Char('é'));
+}
+
+#[test]
+fn raw_byte_and_c_strings() {
+    assert_eq!(literal(r#"r"C:\path""#), TokenKind::String(Symbol::intern(r"C:\path")));
+    assert_eq!(literal(r###"r#"say "hi""#"###), TokenKind::String(Symbol::intern(r#"say "hi""#)));
+    assert_eq!(literal(r#"b"\x7fELF\xff""#), TokenKind::ByteString(b"\x7fELF\xff".to_vec()));
+    assert_eq!(literal(r#"c"hi""#), TokenKind::CString(b"hi\0".to_vec()));
+}
+
+#[test]
+fn malformed_string_literals_are_reported() {
+    assert_eq!(errors(r#""\q""#), [LexErrorKind::UnknownEscape('q')]);
+    assert_eq!(errors(r#""\xff""#), [LexErrorKind::InvalidHexEscape]);
+    assert_eq!(errors(r#""\u{110000}""#), [LexErrorKind::InvalidUnicodeEscape]);
+    assert_eq!(errors(r#"b"é""#), [LexErrorKind::NonAsciiByte('é')]);
+    assert_eq!(errors(r#"c"a\0b""#), [LexErrorKind::InteriorNul]);
+    assert_eq!(errors("''"), [LexErrorKind::EmptyChar]);
+    assert_eq!(errors("'ab'"), [LexErrorKind::MultipleChars]);
+    assert_eq!(errors("'a"), [LexErrorKind::UnterminatedChar]);
+    assert_eq!(errors("r#\"abc\""), [LexErrorKind::UnterminatedRawString]);
+}
+
+#[test]
+fn operators_take_the_longest_match() {
+    use TokenKind::*;
+    assert_eq!(
+        kinds("<<= >>= << <= ..= ... .. && & || |= ~ => :: :"),
+        [
+            ShiftLeftEqual,
+            ShiftRightEqual,
+            ShiftLeft,
+            LessEqual,
+            DotDotEqual,
+            Ellipsis,
+            DotDot,
+            AmpAmp,
+            Amp,
+            PipePipe,
+            PipeEqual,
+            Tilde,
+            FatArrow,
+            ColonColon,
+            Colon,
+        ]
+    );
+    assert_eq!(
+        kinds("a&&b"),
+        [Identifier(Symbol::intern("a")), AmpAmp, Identifier(Symbol::intern("b"))]
+    );
+}
+
+#[test]
+fn tabs_crlf_and_unicode_whitespace_are_skipped() {
+    let expected = kinds("let x = 1\nx");
+    assert_eq!(kinds("let\tx\u{b}= 1\r\nx"), expected);
+    assert_eq!(kinds("\u{feff}let\tx = 1\r\n// c\r\nx"), expected);
+    assert_eq!(kinds("let x\u{200e} = 1\u{2028}x"), expected);
+    assert!(errors("\u{feff}let\tx = 1\r\nx").is_empty());
+}
+
+#[test]
+fn line_breaks_set_newline_before() {
+    let (tokens, _) = lex("a\r\nb c\u{2029}d");
+    let newlines: Vec<_> = tokens.iter().map(|tok| tok.newline_before).collect();
+    assert_eq!(newlines, [false, true, false, true]);
+}
+
+#[test]
+fn identifiers_follow_uax31_and_are_nfc_normalized() {
+    assert_eq!(
+        kinds("café _x1 日本"),
+        [
+            TokenKind::Identifier(Symbol::intern("café")),
+            TokenKind::Identifier(Symbol::intern("_x1")),
+            TokenKind::Identifier(Symbol::intern("日本")),
+        ]
+    );
+    // `e` followed by a combining acute accent is the same as `é`.
+    assert_eq!(kinds("cafe\u{301}"), [TokenKind::Identifier(Symbol::intern("café"))]);
+    assert_eq!(errors("a\u{301}b"), []);
+    assert_eq!(errors("x€"), [LexErrorKind::UnknownChar('€')]);
+}
+
+#[test]
+fn confusable_text_is_only_a_warning() {
+    let (_, errors) = lex("let a = 1\nlet \u{430} = 2");
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(errors[0].is_warning());
+    let LexErrorKind::Confusable { name, other, other_span } = &errors[0].kind else {
+        panic!("not a confusable: {:?}", errors)
+    };
+    assert_eq!((name.as_str(), other.as_str(), *other_span), ("\u{430}", "a", Span::new(4, 5)));
+
+    let (_, errors) = lex("let pаypal = 1");
+    assert_eq!(
+        errors.iter().map(|err| &err.kind).collect::<Vec<_>>(),
+        [&LexErrorKind::MixedScript(Symbol::intern("pаypal"))]
+    );
+    assert_eq!(errors[0].to_diagnostic().code, Some("W0002"));
+
+    let (_, errors) = lex("let s = \"\u{202e}\" // \u{2066}");
+    let kinds: Vec<_> = errors.iter().map(|err| &err.kind).collect();
+    assert_eq!(
+        kinds,
+        [&LexErrorKind::BidiControl('\u{202e}'), &LexErrorKind::BidiControl('\u{2066}')]
+    );
+    assert!(!errors[0].to_diagnostic().is_error());
+}
+
+/// Applies an edit to a source text and asserts that relexing the old
+/// tokens gives the same tokens as lexing the new text, and only errors that
+/// lexing it finds too.
+fn assert_relex(source: &str, lo: usize, hi: usize, text: &str) {
+    for lossless in [false, true] {
+        let mut sm = SourceMap::new();
+        let file = sm.add_virtual_file("test.dal", source);
+        let lexer = |file| if lossless { Lexer::new(file).lossless() } else { Lexer::new(file) };
+        let (old, _) = lexer(&file).lex();
+        let (new_file, edit) = file.edit(Span::new(file.start + lo, file.start + hi), text);
+        let (relexed, errors) = lexer(&new_file).relex(&old, edit);
+        let (lexed, expected) = lexer(&new_file).lex();
+        assert_eq!(relexed, lexed, "{:?} -> {:?}", source, new_file.content);
+        // Only the text lexed again is checked for errors.
+        assert!(errors.iter().all(|error| expected.contains(error)), "{:?}", errors);
+    }
+}
+
+#[test]
+fn relex_reuses_tokens_around_the_edit() {
+    assert_relex("let ab = 1\nfoo(ab)", 6, 6, "c");
+    assert_relex("let ab = 1\nfoo(ab)", 4, 6, "");
+    assert_relex("a + b\n// c\nd", 2, 3, "-=");
+    assert_relex("a\nb\nc", 1, 1, " x\ny");
+    assert_relex("x = \"abc\" + y", 5, 5, "\\");
+}
+
+#[test]
+fn lexer_is_an_iterator() {
+    let file = SourceMap::new().add_virtual_file("test.dal", "a $ b");
+    let mut lexer = Lexer::new(&file);
+    assert_eq!(lexer.next().map(|tok| tok.kind), Some(TokenKind::Identifier(Symbol::intern("a"))));
+    assert_eq!(lexer.next().map(|tok| tok.kind), Some(TokenKind::Unknown));
+    assert_eq!(lexer.take_errors().len(), 1);
+    assert_eq!(lexer.next().map(|tok| tok.span), Some(Span::new(4, 5)));
+    assert_eq!(lexer.next(), None);
+    assert!(lexer.take_errors().is_empty());
+}
+
+#[test]
+fn payloads_are_interned() {
+    let (tokens, _) = lex("name \"name\" name 10u8");
+    let TokenKind::Identifier(first) = tokens[0].kind else { panic!("not an identifier") };
+    assert_eq!(tokens[1].kind, TokenKind::String(first));
+    assert_eq!(tokens[2].kind, TokenKind::Identifier(first));
+    let TokenKind::Integer(lit) = tokens[3].kind else { panic!("not an integer") };
+    assert_eq!(lit.suffix.map(Symbol::as_str), Some("u8"));
+    assert_eq!(Symbol::intern("name"), first);
+    assert_eq!(first.as_str(), "name");
+}
+
+#[test]
+fn keywords_are_lexed_from_symbols() {
+    use TokenKind::*;
+    assert_eq!(
+        kinds("fn self Self sizeof main"),
+        [Fn, SelfValue, SelfType, Sizeof, Identifier(sym::main)]
+    );
+}
+
+#[test]
+fn many_blank_lines() {
+    let source = format!("{}x", "\n".repeat(200_000));
+    for lossless in [false, true] {
+        let (tokens, errors) = lex_mode(&source, lossless);
+        assert_eq!(errors, 0);
+        assert!(tokens[0].newline_before);
+        assert!(matches!(tokens[0].kind, TokenKind::Identifier(_)));
+    }
+}
+
+#[test]
+fn many_comments() {
+    let source = format!("{}{}x", "// c\n".repeat(100_000), "/* c */ ".repeat(100_000));
+    for lossless in [false, true] {
+        let (tokens, errors) = lex_mode(&source, lossless);
+        assert_eq!(errors, 0);
+        assert!(matches!(tokens[0].kind, TokenKind::Identifier(_)));
+    }
+    let (tokens, _) = lex_mode(&source, true);
+    assert_eq!(tokens[0].trivia.len(), 400_000);
+}
+
+#[test]
+fn doc_comments_after_trivia_are_tokens() {
+    let kinds = kinds("// plain\n/* plain */\n/// doc\nx");
+    assert!(matches!(kinds[..], [TokenKind::DocComment(..), TokenKind::Identifier(_)]));
+}
+
+#[test]
+fn relex_looks_behind_the_edit() {
+    assert_relex("x = 1. y", 6, 6, "5");
+    assert_relex("x = 1 . y", 5, 6, "");
+    assert_relex("a..b", 3, 3, "=");
+    assert_relex("r## x", 4, 5, "\"s\"##");
+}
+
+#[test]
+fn relex_equals_lex() {
+    let sources = [
+        "x = 1. y\nz",
+        "let a = 1e5 + 0x1f.b\n",
+        "a >>= b..=c // d\n/* e */ f",
+        "r##\"s\"## b\"t\" c'u' 'v",
+        "/// doc\nfn f() {\r\n    g(1.5, \"h\\n\")\n}",
+    ];
+    let inserts = ["", "5", ".", "e", "=", "/", "*", "\"", "#", "r", "\n", " ", "x"];
+    for source in sources {
+        for lo in (0..=source.len()).filter(|&i| source.is_char_boundary(i)) {
+            for hi in [lo, lo + 1].into_iter().filter(|&i| i <= source.len()) {
+                for text in inserts {
+                    assert_relex(source, lo, hi, text);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn missing_exponent_is_reported_at_the_e() {
+    for source in ["1e", "1.5e", "1e+", "2E-", "1e)", "1.5e f"] {
+        let mut sm = SourceMap::new();
+        let file = sm.add_virtual_file("test.dal", source);
+        let (tokens, errors) = Lexer::new(&file).lex();
+        assert!(matches!(tokens[0].kind, TokenKind::Float(_)), "{}", source);
+        assert_eq!(errors.len(), 1, "{}", source);
+        assert_eq!(errors[0].kind, LexErrorKind::MissingExponent, "{}", source);
+        let e = file.start + source.find(['e', 'E']).unwrap();
+        assert_eq!(errors[0].span, Span::new(e, e + 1), "{}", source);
+    }
+    let mut sm = SourceMap::new();
+    let file = sm.add_virtual_file("test.dal", "1ex");
+    let (_, errors) = Lexer::new(&file).lex();
+    assert!(matches!(errors[..], [LexError { kind: LexErrorKind::InvalidSuffix(_), .. }]));
+}
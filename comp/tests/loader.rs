@@ -0,0 +1,131 @@
+use dal::{
+    attr::CfgSet,
+    diag::Diagnostic,
+    loader::{Loader, ModuleGraph},
+    span::SourceMap,
+    symbol::Symbol,
+};
+use std::{fs, path::PathBuf};
+
+/// Writes the files of a program to a new directory, with the standard
+/// library in its `std` subdirectory, and loads the first file.
+fn load_program(name: &str, files: &[(&str, &str)]) -> (ModuleGraph, Vec<Diagnostic>) {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("loader").join(name);
+    let _ = fs::remove_dir_all(&dir);
+    for (path, source) in files {
+        let path = dir.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, source).unwrap();
+    }
+    let root = dir.join(files[0].0).to_string_lossy().to_string();
+    let mut sm = SourceMap::new();
+    let cfg = CfgSet::default().flag(Symbol::intern("unix"));
+    Loader::new(&mut sm, dir.join("std"), cfg).load(&root)
+}
+
+/// Loads a program and returns the codes of the errors found.
+fn load(name: &str, files: &[(&str, &str)]) -> Vec<&'static str> {
+    let (_, diags) = load_program(name, files);
+    diags.iter().map(|diag| diag.code.unwrap()).collect()
+}
+
+/// Returns the module names of a graph, in dependency order.
+fn order(graph: &ModuleGraph) -> Vec<&'static str> {
+    graph.order().iter().map(|id| graph.get(*id).name.as_str()).collect()
+}
+
+#[test]
+fn imports_are_loaded_once_in_dependency_order() {
+    let files = [
+        ("main.dal", "import \"std/io\"\nimport \"util/math\"\nfn main() {}"),
+        ("util/math.dal", "module math\nimport \"std/io\"\n"),
+        ("std/io.dal", "module io\n"),
+    ];
+    let (graph, diags) = load_program("order", &files);
+    assert!(diags.is_empty(), "{:?}", diags);
+    assert_eq!(graph.modules().len(), 3);
+    assert_eq!(order(&graph), ["io", "math", "main"]);
+
+    let root = graph.get(graph.root().unwrap());
+    let imports: Vec<_> = root.imports.iter().map(|import| import.name.as_str()).collect();
+    assert_eq!(imports, ["io", "math"]);
+    assert_eq!(graph.get(root.imports[1].module).imports[0].module, root.imports[0].module);
+}
+
+#[test]
+fn missing_modules_are_reported() {
+    assert_eq!(
+        load("missing", &[("main.dal", "import \"nope\"\nimport \"std/nope\"\n")]),
+        ["E0300", "E0300"]
+    );
+
+    let mut sm = SourceMap::new();
+    let (graph, diags) = Loader::new(&mut sm, PathBuf::new(), CfgSet::default()).load("nope.dal");
+    assert!(graph.root().is_none());
+    assert_eq!(diags[0].code, Some("E0020"));
+}
+
+#[test]
+fn module_declarations_must_match_the_import() {
+    let files = [("main.dal", "import \"math\"\n"), ("math.dal", "module maths\n")];
+    let (graph, diags) = load_program("mismatch", &files);
+    assert_eq!(diags[0].code, Some("E0301"));
+    assert_eq!(diags[0].help[0].suggestion.as_ref().unwrap().replacement, "math");
+    // The module is still bound under the name of the import.
+    assert_eq!(graph.modules()[1].name.as_str(), "math");
+}
+
+#[test]
+fn import_cycles_are_reported() {
+    assert_eq!(load("self_cycle", &[("main.dal", "import \"main\"\n")]), ["E0302"]);
+
+    let files =
+        [("main.dal", "import \"a\"\n"), ("a.dal", "import \"b\"\n"), ("b.dal", "import \"a\"\n")];
+    let (_, diags) = load_program("cycle", &files);
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0].message, "import cycle between `a` and `b`");
+    assert_eq!(diags[0].notes, ["cycle: a -> b -> a"]);
+}
+
+#[test]
+fn duplicate_imports_are_reported() {
+    let files = [
+        ("main.dal", "import \"a/io\"\nimport \"std/io\"\n"),
+        ("a/io.dal", ""),
+        ("std/io.dal", ""),
+    ];
+    assert_eq!(load("duplicate", &files), ["E0303"]);
+}
+
+#[test]
+fn disabled_imports_are_not_loaded() {
+    let files = [
+        ("main.dal", "@cfg(windows)\nimport \"win\"\n@cfg(unix)\nimport \"posix\"\n"),
+        ("posix.dal", ""),
+    ];
+    let (graph, diags) = load_program("cfg", &files);
+    assert!(diags.is_empty(), "{:?}", diags);
+    assert_eq!(order(&graph), ["posix", "main"]);
+}
+
+#[test]
+fn errors_in_imported_modules_are_reported() {
+    let files = [
+        ("main.dal", "import \"bad\"\nimport \"worse\"\n"),
+        ("bad.dal", "let x = $\n"),
+        ("worse.dal", "fn (\n"),
+    ];
+    assert_eq!(load("errors", &files), ["E0001", "E0100"]);
+}
+
+#[test]
+fn broken_module_is_reported_once() {
+    let files = [
+        ("main.dal", "import \"a\"\nimport \"b\"\nimport \"bad\"\n"),
+        ("a.dal", "import \"bad\"\nimport \"worse\"\n"),
+        ("b.dal", "import \"bad\"\nimport \"worse\"\n"),
+        ("bad.dal", "let x = $\n"),
+        ("worse.dal", "fn (\n"),
+    ];
+    assert_eq!(load("broken_once", &files).len(), 2);
+}
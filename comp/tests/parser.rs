@@ -0,0 +1,420 @@
+use dal::{
+    ast::{
+        Expr, ExprKind, ItemKind, Lit, Module, PatKind, Path, Stmt, StmtKind, Ty, TyKind,
+        Visibility,
+    },
+    lexer::Lexer,
+    parser::{PResult, Parser},
+    span::{SourceFile, SourceMap},
+    symbol::{Symbol, kw, sym},
+};
+use std::rc::Rc;
+
+/// Loads a source text into a new source map.
+fn source_file(source: &str) -> Rc<SourceFile> {
+    SourceMap::new().add_virtual_file("test.dal", source)
+}
+
+/// Parses a source text as a module.
+fn parse(source: &str) -> PResult<Module> {
+    let file = source_file(source);
+    let (tokens, _) = Lexer::new(&file).lex();
+    Parser::new(tokens).parse_module()
+}
+
+/// Parses a source text as an expression and renders it with every operation
+/// in parentheses.
+fn expr(source: &str) -> String {
+    render(&parse_expr(source).unwrap())
+}
+
+/// Parses a source text as an expression.
+fn parse_expr(source: &str) -> PResult<Expr> {
+    let file = source_file(source);
+    let (tokens, _) = Lexer::new(&file).lex();
+    Parser::new(tokens).parse_expr()
+}
+
+/// Renders an expression with every operation in parentheses.
+fn render(expr: &Expr) -> String {
+    match &expr.kind {
+        ExprKind::Lit(Lit::Int(lit)) => lit.digits.to_string(),
+        ExprKind::Lit(Lit::Float(lit)) => lit.digits.to_string(),
+        ExprKind::Lit(Lit::Str(s)) => format!("{:?}", s),
+        ExprKind::Lit(Lit::Char(c)) => format!("{:?}", c),
+        ExprKind::Lit(Lit::ByteStr(bytes)) | ExprKind::Lit(Lit::CStr(bytes)) => {
+            format!("{:?}", bytes)
+        }
+        ExprKind::Lit(Lit::Bool(b)) => b.to_string(),
+        ExprKind::Lit(Lit::Null) => "null".to_string(),
+        ExprKind::Path(path) => render_path(path),
+        ExprKind::Unary(op, operand) => {
+            format!("({}{})", op.as_str(), render(operand))
+        }
+        ExprKind::Binary(op, lhs, rhs) => {
+            format!("({} {} {})", render(lhs), op.as_str(), render(rhs))
+        }
+        ExprKind::Assign(lhs, rhs) => format!("({} = {})", render(lhs), render(rhs)),
+        ExprKind::AssignOp(op, lhs, rhs) => {
+            format!("({} {}= {})", render(lhs), op.as_str(), render(rhs))
+        }
+        ExprKind::Range(start, end, inclusive) => {
+            let start = start.as_ref().map_or(String::new(), |start| render(start));
+            let end = end.as_ref().map_or(String::new(), |end| render(end));
+            format!("({}{}{})", start, if *inclusive { "..=" } else { ".." }, end)
+        }
+        ExprKind::Call(callee, args) => {
+            let args: Vec<_> = args.iter().map(render).collect();
+            format!("{}({})", render(callee), args.join(", "))
+        }
+        ExprKind::TypeArgs(callee, args) => {
+            let args: Vec<_> = args.iter().map(render_ty).collect();
+            format!("{}[{}]", render(callee), args.join(", "))
+        }
+        ExprKind::Field(base, name) => format!("{}.{}", render(base), name.name),
+        ExprKind::Cast(value, ty) => format!("({} as {})", render(value), render_ty(ty)),
+        ExprKind::Paren(inner) => format!("({})", render(inner)),
+        ExprKind::Block(block) => format!("{{{} stmts}}", block.stmts.len()),
+        ExprKind::If(cond, _, els) => {
+            let els = els.as_ref().map_or(String::new(), |els| format!(" else {}", render(els)));
+            format!("(if {}{})", render(cond), els)
+        }
+        ExprKind::While(cond, body) => {
+            format!("(while {} {{{} stmts}})", render(cond), body.stmts.len())
+        }
+        ExprKind::For(name, iter, body) => {
+            format!("(for {} in {} {{{} stmts}})", name.name, render(iter), body.stmts.len())
+        }
+        ExprKind::Loop(body) => format!("(loop {{{} stmts}})", body.stmts.len()),
+        ExprKind::Break(value) => match value {
+            Some(value) => format!("(break {})", render(value)),
+            None => "break".to_string(),
+        },
+        ExprKind::Continue => "continue".to_string(),
+        ExprKind::Match(scrutinee, arms) => {
+            format!("(match {}, {} arms)", render(scrutinee), arms.len())
+        }
+        ExprKind::Struct(path, fields) => {
+            let fields: Vec<_> = fields
+                .iter()
+                .map(|field| format!("{}: {}", field.name.name, render(&field.expr)))
+                .collect();
+            format!("{} {{ {} }}", render_path(path), fields.join(", "))
+        }
+        ExprKind::SizeOf(ty) => format!("sizeof({})", render_ty(ty)),
+    }
+}
+
+/// Renders a path with its segments separated by `::`.
+fn render_path(path: &Path) -> String {
+    path.segments.iter().map(|ident| ident.name.as_str()).collect::<Vec<_>>().join("::")
+}
+
+/// Renders a type.
+fn render_ty(ty: &Ty) -> String {
+    match &ty.kind {
+        TyKind::Path(path) => render_path(path),
+        TyKind::Ptr(mutability, pointee) => format!("*{:?} {}", mutability, render_ty(pointee)),
+        TyKind::Never => "!".to_string(),
+        TyKind::Fn(params, ret) => {
+            let params: Vec<_> = params.iter().map(render_ty).collect();
+            match ret {
+                Some(ret) => format!("fn({}) -> {}", params.join(", "), render_ty(ret)),
+                None => format!("fn({})", params.join(", ")),
+            }
+        }
+    }
+}
+
+#[test]
+fn binary_operators_follow_precedence() {
+    assert_eq!(expr("1 + 2 * 3"), "(1 + (2 * 3))");
+    assert_eq!(expr("1 * 2 + 3"), "((1 * 2) + 3)");
+    assert_eq!(expr("a << b + c"), "(a << (b + c))");
+    assert_eq!(expr("a ^ b << c"), "(a ^ (b << c))");
+    assert_eq!(expr("a < b ^ c"), "(a < (b ^ c))");
+    assert_eq!(expr("a & b ^ c | d"), "(((a & b) ^ c) | d)");
+    assert_eq!(expr("a == b & c"), "(a == (b & c))");
+    assert_eq!(expr("a || b && c == d"), "(a || (b && (c == d)))");
+    assert_eq!(expr("(1 + 2) * 3"), "(((1 + 2)) * 3)");
+}
+
+#[test]
+fn binary_operators_are_left_associative() {
+    assert_eq!(expr("a - b - c"), "((a - b) - c)");
+    assert_eq!(expr("a / b % c"), "((a / b) % c)");
+    assert_eq!(expr("a && b && c"), "((a && b) && c)");
+}
+
+#[test]
+fn comparisons_cannot_be_chained() {
+    for source in ["a < b < c", "a == b != c", "a < b == c"] {
+        let err = parse_expr(source).unwrap_err();
+        assert_eq!(err.code, Some("E0103"), "{}", source);
+    }
+    assert_eq!(expr("(a < b) == c"), "(((a < b)) == c)");
+}
+
+#[test]
+fn unary_postfix_and_cast_bind_tighter_than_binary() {
+    assert_eq!(expr("-a * b"), "((-a) * b)");
+    assert_eq!(expr("~a & b"), "((~a) & b)");
+    assert_eq!(expr("-a as u8"), "((-a) as u8)");
+    assert_eq!(expr("!f(x).y"), "(!f(x).y)");
+    assert_eq!(expr("a + b as u8"), "(a + (b as u8))");
+    assert_eq!(expr("p as *const u8 as *mut u8"), "((p as *Const u8) as *Mut u8)");
+    assert_eq!(expr("io.println(\"hi\", 1 + 2)"), "io.println(\"hi\", (1 + 2))");
+    assert_eq!(expr("-sum[u8, *const T](a).b"), "(-sum[u8, *Const T](a).b)");
+}
+
+#[test]
+fn assignment_is_right_associative() {
+    assert_eq!(expr("a = b = c + 1"), "(a = (b = (c + 1)))");
+    assert_eq!(expr("a += b <<= 2"), "(a += (b <<= 2))");
+    assert_eq!(expr("a |= b & c"), "(a |= (b & c))");
+}
+
+#[test]
+fn ranges() {
+    assert_eq!(expr("a + 1..b"), "((a + 1)..b)");
+    assert_eq!(expr("..=n"), "(..=n)");
+    assert_eq!(expr("i.."), "(i..)");
+    assert_eq!(expr("x = 0..n"), "(x = (0..n))");
+    assert_eq!(parse_expr("a..=").unwrap_err().code, Some("E0104"));
+}
+
+#[test]
+fn if_else_chains() {
+    assert_eq!(expr("if a { } else if b { } else { x }"), "(if a else (if b else {1 stmts}))");
+}
+
+#[test]
+fn module_items() {
+    let source = "
+module main
+
+import \"std/io\"
+
+@[link(\"c\")]
+extern {
+    pub fn printf(fmt: *const u8, ...) -> i32
+}
+
+pub fn main() -> i32 {
+    let mut x: i32 = 1;
+    const y = x + 2;
+    io.println(\"hi\");
+    return y
+}
+";
+    let module = parse(source).unwrap();
+    assert_eq!(module.decl.unwrap().name, sym::main);
+    assert_eq!(module.items.len(), 3);
+
+    let ItemKind::Import(import) = &module.items[0].kind else { panic!("not an import") };
+    assert_eq!(import.path.as_str(), "std/io");
+
+    let ItemKind::Extern(block) = &module.items[1].kind else { panic!("not an extern block") };
+    assert_eq!(module.items[1].attrs[0].name.name, sym::link);
+    let ItemKind::Fn(printf) = &block.items[0].kind else { panic!("not a function") };
+    assert!(printf.variadic && printf.body.is_none());
+    assert_eq!(render_ty(&printf.params[0].ty), "*Const u8");
+
+    let item = &module.items[2];
+    assert_eq!(item.vis, Visibility::Public);
+    let ItemKind::Fn(main) = &item.kind else { panic!("not a function") };
+    let stmts = &main.body.as_ref().unwrap().stmts;
+    assert_eq!(stmts.len(), 4);
+    assert!(matches!(&stmts[0].kind, StmtKind::Local(local) if local.ty.is_some()));
+    assert!(matches!(&stmts[2].kind, StmtKind::Expr(Expr { kind: ExprKind::Call(..), .. })));
+    assert!(matches!(&stmts[3].kind, StmtKind::Return(Some(_))));
+}
+
+#[test]
+fn syntax_errors_point_at_the_unexpected_token() {
+    let source = "fn (x: i32) {}";
+    let err = parse(source).unwrap_err();
+    let span = err.primary_span().unwrap();
+    assert_eq!(&source[span.lo..span.hi], "(");
+
+    let err = parse("fn f(...) {}").unwrap_err();
+    assert!(err.message.contains("variadic"), "{}", err.message);
+
+    let err = parse("fn f() { const x }").unwrap_err();
+    assert!(err.message.contains("initializer"), "{}", err.message);
+}
+
+#[test]
+fn doc_comments_attach_to_items() {
+    let source = "//! The module.\n\n/// Adds.\n/// Twice.\nfn add() {}\n\nfn sub() {}\n";
+    let module = parse(source).unwrap();
+    assert_eq!(module.docs, [Symbol::intern(" The module.")]);
+    assert_eq!(module.items[0].docs, [" Adds.", " Twice."].map(Symbol::intern));
+    assert!(module.items[1].docs.is_empty());
+}
+
+#[test]
+fn loops_and_jumps() {
+    assert_eq!(expr("while i < n { i += 1; }"), "(while (i < n) {1 stmts})");
+    assert_eq!(expr("for i in 0..n { }"), "(for i in (0..n) {0 stmts})");
+    assert_eq!(expr("for i in 0.. { }"), "(for i in (0..) {0 stmts})");
+    assert_eq!(expr("loop { break }"), "(loop {1 stmts})");
+    assert_eq!(expr("break x + 1"), "(break (x + 1))");
+    assert_eq!(expr("continue"), "continue");
+}
+
+#[test]
+fn struct_literals_are_not_allowed_in_conditions() {
+    assert_eq!(expr("Point { x: 1, y }"), "Point { x: 1, y: y }");
+    assert_eq!(expr("shapes::Point { x: a + b }"), "shapes::Point { x: (a + b) }");
+    assert_eq!(expr("if p == Point { }"), "(if (p == Point))");
+    assert_eq!(
+        expr("while (p == Point { x: 1 }) { }"),
+        "(while ((p == Point { x: 1 })) {0 stmts})"
+    );
+    assert_eq!(expr("sizeof(*const T)"), "sizeof(*Const T)");
+}
+
+#[test]
+fn match_arms_and_patterns() {
+    let source = "match shape {
+        Shape::Circle(r) if r > 0 => r,
+        Shape::Square(_) | Shape::Empty => 0,
+        -1 => { 1 },
+        null => 2,
+        x => x,
+    }";
+    let expr = parse_expr(source).unwrap();
+    let ExprKind::Match(_, arms) = &expr.kind else { panic!("not a match") };
+    assert_eq!(arms.len(), 5);
+    let PatKind::TupleStruct(path, fields) = &arms[0].pat.kind else { panic!("not a variant") };
+    assert_eq!(render_path(path), "Shape::Circle");
+    assert!(matches!(fields[0].kind, PatKind::Binding(_)));
+    assert!(arms[0].guard.is_some());
+    let PatKind::Or(alts) = &arms[1].pat.kind else { panic!("not alternatives") };
+    assert!(
+        matches!(alts[0].kind, PatKind::TupleStruct(_, ref fields) if fields[0].kind == PatKind::Wild)
+    );
+    assert!(matches!(alts[1].kind, PatKind::Path(_)));
+    assert!(matches!(arms[2].pat.kind, PatKind::Lit(_)));
+    assert!(matches!(arms[3].pat.kind, PatKind::Lit(_)));
+    assert!(matches!(arms[4].pat.kind, PatKind::Binding(_)));
+}
+
+#[test]
+fn type_definitions() {
+    let source = "
+struct Point[T] { pub x: T, y: T }
+union Bits { i: u32, f: f32 }
+enum Color { Red, Rgb(u8, u8, u8), Max = 10 }
+type Ptr = *mut u8
+impl[T] Point {
+    fn len(self) -> T { defer free(self); self.x }
+}
+impl Show for Color {
+    fn show(self) {}
+}
+trait Show {
+    fn show(self)
+    fn twice(self) { self.show(); self.show() }
+}
+";
+    let module = parse(source).unwrap();
+    let kinds: Vec<_> = module.items.iter().map(|item| &item.kind).collect();
+    let [
+        ItemKind::Struct(point),
+        ItemKind::Union(bits),
+        ItemKind::Enum(color),
+        ItemKind::TypeAlias(alias),
+        ItemKind::Impl(inherent),
+        ItemKind::Impl(show),
+        ItemKind::Trait(trait_decl),
+    ] = kinds.as_slice()
+    else {
+        panic!("unexpected items: {:?}", kinds);
+    };
+    assert_eq!(point.generics.len(), 1);
+    assert_eq!(point.fields[0].vis, Visibility::Public);
+    assert_eq!(bits.fields.len(), 2);
+    assert_eq!(color.variants[1].fields.len(), 3);
+    assert!(color.variants[2].discriminant.is_some());
+    assert_eq!(render_ty(&alias.ty), "*Mut u8");
+    assert!(inherent.trait_ref.is_none() && inherent.generics.len() == 1);
+    let ItemKind::Fn(len) = &inherent.items[0].kind else { panic!("not a method") };
+    assert_eq!(len.params[0].name.name, kw::SelfValue);
+    assert!(matches!(len.body.as_ref().unwrap().stmts[0].kind, StmtKind::Defer(_)));
+    assert_eq!(render_path(show.trait_ref.as_ref().unwrap()), "Show");
+    let ItemKind::Fn(required) = &trait_decl.items[0].kind else { panic!("not a method") };
+    assert!(required.body.is_none());
+}
+
+#[test]
+fn self_is_only_allowed_in_methods() {
+    assert!(parse("fn f(self) {}").is_err());
+    assert!(parse("struct S { x: i32 }\nimpl S { fn f() }").is_err());
+}
+
+/// Parses a source text as a module and returns the statements of its first
+/// function.
+fn fn_body(source: &str) -> Vec<Stmt> {
+    let module = parse(source).unwrap();
+    let ItemKind::Fn(func) = &module.items[0].kind else { panic!("not a function") };
+    func.body.clone().unwrap().stmts
+}
+
+#[test]
+fn statements_end_at_line_breaks_or_semicolons() {
+    assert_eq!(fn_body("fn f() {\n    let a = 1\n    let b = 2; let c = 3\n    a\n}").len(), 4);
+    assert_eq!(fn_body("fn f() { a; b; }").len(), 2);
+    assert_eq!(fn_body("fn f() {\n    return\n    x\n}").len(), 2);
+
+    let err = parse("fn f() { let a = 1 let b = 2 }").unwrap_err();
+    assert_eq!(err.code, Some("E0108"));
+
+    let module = parse("struct A { x: i32 }\nstruct B { y: i32 } fn f() {}");
+    assert_eq!(module.unwrap_err().code, Some("E0108"));
+}
+
+#[test]
+fn expressions_continue_after_trailing_operators_and_inside_brackets() {
+    let stmts = fn_body(
+        "fn f() {\n    let sum = a +\n        b\n    foo(a,\n        b);\n    (x\n    + y)\n}",
+    );
+    assert_eq!(stmts.len(), 3);
+    let StmtKind::Local(local) = &stmts[0].kind else { panic!("not a let") };
+    assert_eq!(render(local.init.as_ref().unwrap()), "(a + b)");
+
+    let stmts = fn_body("fn f() {\n    let s = io\n        .println\n}");
+    let StmtKind::Local(local) = &stmts[0].kind else { panic!("not a let") };
+    assert_eq!(render(local.init.as_ref().unwrap()), "io.println");
+
+    let stmts = fn_body("fn f() {\n    x = match y {\n        1 => 2\n        _ => 3\n    }\n}");
+    let StmtKind::Expr(Expr { kind: ExprKind::Assign(_, rhs), .. }) = &stmts[0].kind else {
+        panic!("not an assignment")
+    };
+    assert!(matches!(&rhs.kind, ExprKind::Match(_, arms) if arms.len() == 2));
+}
+
+#[test]
+fn operators_at_line_start_are_ambiguous() {
+    for source in [
+        "fn f() {\n    a\n    - b\n}",
+        "fn f() {\n    a\n    (b)\n}",
+        "fn f() {\n    a\n    == b\n}",
+    ] {
+        let err = parse(source).unwrap_err();
+        assert_eq!(err.code, Some("E0107"), "{}", source);
+        assert_eq!(err.help.len(), 2, "{}", source);
+    }
+    assert_eq!(fn_body("fn f() {\n    a;\n    -b\n}").len(), 2);
+}
+
+#[test]
+fn function_types() {
+    let source = "fn f(a: fn(i32, *const u8) -> fn() -> bool, b: fn(), c: *mut fn(u8,) -> !) {}";
+    let module = parse(source).unwrap();
+    let ItemKind::Fn(decl) = &module.items[0].kind else { panic!() };
+    let params: Vec<_> = decl.params.iter().map(|param| render_ty(&param.ty)).collect();
+    assert_eq!(params, ["fn(i32, *Const u8) -> fn() -> bool", "fn()", "*Mut fn(u8) -> !"]);
+    assert_eq!(parse("fn f(a: fn i32) {}").unwrap_err().code, Some("E0100"));
+}
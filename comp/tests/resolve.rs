@@ -0,0 +1,170 @@
+use dal::{
+    attr::CfgSet,
+    loader::{Loader, ModuleGraph},
+    resolve::{self, DefKind, Res, Resolutions, error::ResolveError},
+    span::{SourceMap, Span},
+};
+use std::{
+    fs,
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Writes the files of a program to a new directory, loads the first file
+/// and resolves the names of every module.
+fn resolve_program(
+    name: &str,
+    files: &[(&str, &str)],
+) -> (ModuleGraph, Resolutions, Vec<ResolveError>) {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("resolve").join(name);
+    let _ = fs::remove_dir_all(&dir);
+    for (path, source) in files {
+        let path = dir.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, source).unwrap();
+    }
+    let root = dir.join(files[0].0).to_string_lossy().to_string();
+    let mut sm = SourceMap::new();
+    let (graph, diags) = Loader::new(&mut sm, dir.join("std"), CfgSet::default()).load(&root);
+    assert!(diags.is_empty(), "{:?}", diags);
+    let (res, errors) = resolve::resolve(&graph);
+    (graph, res, errors)
+}
+
+/// Resolves a single module and returns its errors as `code: message`.
+fn messages(source: &str) -> Vec<String> {
+    // Tests run in parallel, so every program gets its own directory.
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+    let name = format!("messages{}", COUNT.fetch_add(1, Ordering::Relaxed));
+    let (_, _, errors) = resolve_program(&name, &[("main.dal", source)]);
+    errors
+        .iter()
+        .map(|error| format!("{}: {}", error.to_diagnostic().code.unwrap(), error.kind))
+        .collect()
+}
+
+/// Returns the span of the `n`th occurrence of `text` in the root module.
+fn span_of(graph: &ModuleGraph, text: &str, n: usize) -> Span {
+    let file = &graph.get(graph.root().unwrap()).file;
+    let (offset, _) = file.content.match_indices(text).nth(n).unwrap();
+    let lo = file.start + offset;
+    Span::new(lo, lo + text.len())
+}
+
+#[test]
+fn locals_shadow_items_and_outer_bindings() {
+    let source = "fn x() {}\nfn f(x: i32) {\n    let y = x\n    let x = y\n    x\n}\n";
+    let (graph, res, errors) = resolve_program("shadowing", &[("main.dal", source)]);
+    assert!(errors.is_empty(), "{:?}", errors);
+    // `x` in `let y = x` is the parameter, the last `x` is the local.
+    assert_eq!(res.get(span_of(&graph, "x", 2)), Some(Res::Local(span_of(&graph, "x", 1))));
+    assert_eq!(res.get(span_of(&graph, "x", 4)), Some(Res::Local(span_of(&graph, "x", 3))));
+    assert_eq!(res.get(span_of(&graph, "y", 1)), Some(Res::Local(span_of(&graph, "y", 0))));
+}
+
+#[test]
+fn items_can_be_used_before_they_are_defined() {
+    let source = "fn main() { f(Color::Red) }\nfn f(c: Color) {}\nenum Color { Red, Green }\n";
+    let (graph, res, errors) = resolve_program("forward", &[("main.dal", source)]);
+    assert!(errors.is_empty(), "{:?}", errors);
+    let Some(Res::Def(red)) = res.get(span_of(&graph, "Color::Red", 0)) else { panic!() };
+    assert_eq!(res.def(red).kind, DefKind::Variant);
+    assert_eq!(res.def(red).name.name.as_str(), "Red");
+    let Some(Res::Def(color)) = res.get(span_of(&graph, "Color", 1)) else { panic!() };
+    assert_eq!(res.def(red).parent, Some(color));
+}
+
+#[test]
+fn module_members_resolve_across_modules() {
+    let files = [
+        ("main.dal", "import \"math\"\nfn main() { math.add(1, 2) }\n"),
+        ("math.dal", "module math\npub fn add(a: i32, b: i32) -> i32 { a + b }\n"),
+    ];
+    let (graph, res, errors) = resolve_program("members", &files);
+    assert!(errors.is_empty(), "{:?}", errors);
+    let math = graph.get(graph.root().unwrap()).imports[0].module;
+    assert_eq!(res.get(span_of(&graph, "math", 1)), Some(Res::Module(math)));
+    let Some(Res::Def(add)) = res.get(span_of(&graph, "math.add", 0)) else { panic!() };
+    assert_eq!(res.def(add).module, math);
+    assert_eq!(res.def(add).kind, DefKind::Fn);
+}
+
+#[test]
+fn undefined_names_suggest_a_close_name() {
+    let (_, _, errors) = resolve_program(
+        "undefined",
+        &[("main.dal", "fn main() {\n    let count = 1\n    cont\n}\n")],
+    );
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].kind.to_string(), "cannot find value `cont` in this scope");
+    let diag = errors[0].to_diagnostic();
+    assert_eq!(diag.code, Some("E0400"));
+    assert_eq!(diag.help[0].suggestion.as_ref().unwrap().replacement, "count");
+
+    assert_eq!(
+        messages("fn f(x: Strng) {}\nstruct String {}\n"),
+        ["E0400: cannot find type `Strng` in this scope"]
+    );
+    // Names too far from every name in scope get no suggestion.
+    let (_, _, errors) = resolve_program("far", &[("main.dal", "fn main() { xyz }\n")]);
+    assert!(errors[0].to_diagnostic().help.is_empty());
+}
+
+#[test]
+fn names_must_be_in_the_right_namespace() {
+    assert_eq!(
+        messages("struct S {}\nfn main() { S }\n"),
+        ["E0401: expected value, found struct `S`"]
+    );
+    assert_eq!(
+        messages("fn f() {}\nfn g(x: f) {}\n"),
+        ["E0401: expected type, found function `f`"]
+    );
+}
+
+#[test]
+fn members_must_exist_and_be_public() {
+    let files = [
+        ("main.dal", "import \"math\"\nfn main() {\n    math.ad(1, 2)\n    math.helper()\n}\n"),
+        ("math.dal", "module math\npub fn add(a: i32, b: i32) -> i32 { a + b }\nfn helper() {}\n"),
+    ];
+    let (_, _, errors) = resolve_program("members_errors", &files);
+    let codes: Vec<_> = errors.iter().map(|error| error.to_diagnostic().code.unwrap()).collect();
+    assert_eq!(codes, ["E0402", "E0403"]);
+    assert_eq!(errors[0].kind.to_string(), "cannot find `ad` in module `math`");
+    assert_eq!(errors[0].to_diagnostic().help[0].suggestion.as_ref().unwrap().replacement, "add");
+    assert_eq!(errors[1].kind.to_string(), "function `helper` is private");
+
+    assert_eq!(
+        messages("enum Color { Red }\nfn main() { Color::Blue }\n"),
+        ["E0402: cannot find `Blue` in enum `Color`"]
+    );
+}
+
+#[test]
+fn duplicate_definitions_are_reported() {
+    assert_eq!(
+        messages("fn f() {}\nstruct f {}\nfn f() {}\n"),
+        ["E0404: the name `f` is defined more than once"]
+    );
+    assert_eq!(
+        messages("fn f(a: i32, a: i32) {}\n"),
+        ["E0404: the name `a` is defined more than once"]
+    );
+}
+
+#[test]
+fn path_prefixes_must_be_modules_or_types() {
+    assert_eq!(
+        messages("fn main() { i32::max }\n"),
+        ["E0405: expected module or type, found primitive type `i32`"]
+    );
+}
+
+#[test]
+fn primitive_types_resolve_without_a_definition() {
+    let (graph, res, errors) =
+        resolve_program("prim", &[("main.dal", "fn f(x: i32) -> bool { true }\n")]);
+    assert!(errors.is_empty(), "{:?}", errors);
+    assert!(matches!(res.get(span_of(&graph, "i32", 0)), Some(Res::PrimTy(_))));
+}
@@ -0,0 +1,132 @@
+use dal::{
+    lexer::Lexer,
+    span::{
+        LineCol, SourceFile, SourceMap, Span,
+        error::{LoadError, LoadErrorKind},
+    },
+};
+use std::{fs, path::PathBuf, rc::Rc};
+
+/// Loads a source text into a new source map.
+fn source_file(source: &str) -> Rc<SourceFile> {
+    SourceMap::new().add_virtual_file("test.dal", source)
+}
+
+#[test]
+fn line_col_counts_characters() {
+    let file = source_file("let a\nlet é = ü\n\nx");
+    assert_eq!(file.line_col(0), LineCol { line: 1, col: 1 });
+    assert_eq!(file.line_col(5), LineCol { line: 1, col: 6 });
+    assert_eq!(file.line_col(6), LineCol { line: 2, col: 1 });
+    // `=` comes after the two byte `é`.
+    assert_eq!(file.line_col(13), LineCol { line: 2, col: 7 });
+    assert_eq!(file.line_col(19), LineCol { line: 4, col: 1 });
+}
+
+#[test]
+fn offsets_inside_a_character_round_down() {
+    let file = source_file("é!");
+    assert_eq!(file.line_col(1), LineCol { line: 1, col: 1 });
+    assert_eq!(file.line_col(2), LineCol { line: 1, col: 2 });
+}
+
+#[test]
+fn utf16_columns_count_surrogate_pairs() {
+    let file = source_file("\"😀\" x");
+    assert_eq!(file.line_col(7), LineCol { line: 1, col: 5 });
+    assert_eq!(file.utf16_col(7), 6);
+}
+
+#[test]
+fn line_text_and_snippets() {
+    let file = source_file("fn main() {\n    x\n}");
+    assert_eq!(file.line_text(1), Some("fn main() {"));
+    assert_eq!(file.line_text(2), Some("    x"));
+    assert_eq!(file.line_text(3), Some("}"));
+    assert_eq!(file.line_text(0), None);
+    assert_eq!(file.line_text(4), None);
+    assert_eq!(file.snippet(Span::new(3, 7)), "main");
+}
+
+#[test]
+fn token_spans_are_byte_offsets() {
+    let file = source_file("é + ab");
+    let (tokens, _) = Lexer::new(&file).lex();
+    let snippets: Vec<_> = tokens.iter().map(|tok| file.snippet(tok.span)).collect();
+    assert_eq!(snippets, ["é", "+", "ab"]);
+    assert_eq!(tokens[2].span, Span::new(5, 7));
+}
+
+#[test]
+fn source_map_gives_files_distinct_offsets() {
+    let mut sm = SourceMap::new();
+    let a = sm.add_virtual_file("a.dal", "let a = 1\n");
+    let b = sm.add_virtual_file("b.dal", "let b = 2\n");
+    assert_eq!(a.start, 0);
+    assert_eq!(b.start, a.end() + 1);
+    assert_eq!(sm.lookup_file(a.end()).unwrap().path, "a.dal");
+    assert_eq!(sm.lookup_file(b.start + 4).unwrap().path, "b.dal");
+    assert!(sm.lookup_file(b.end() + 1).is_none());
+
+    let (file, pos) = sm.lookup(b.start + 4).unwrap();
+    assert_eq!(file.name, "b.dal");
+    assert_eq!(pos, LineCol { line: 1, col: 5 });
+    assert_eq!(sm.snippet(Span::new(b.start + 4, b.start + 5)), Some("b"));
+}
+
+#[test]
+fn tokens_of_later_files_have_global_spans() {
+    let mut sm = SourceMap::new();
+    sm.add_virtual_file("a.dal", "let a = 1\n");
+    let b = sm.add_virtual_file("b.dal", "é + ab");
+    let (tokens, _) = Lexer::new(&b).lex();
+    assert_eq!(tokens[2].span, Span::new(b.start + 5, b.start + 7));
+    assert_eq!(sm.snippet(tokens[0].span), Some("é"));
+}
+
+/// Loads the file with the given name and content from a scratch directory.
+fn load(name: &str, content: &[u8]) -> Result<Rc<SourceFile>, LoadError> {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("span");
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    fs::write(&path, content).unwrap();
+    SourceMap::new().load_file(&path.to_string_lossy())
+}
+
+#[test]
+fn load_file_reads_utf8_files() {
+    let file = load("ok.dal", "fn main() {}\n".as_bytes()).unwrap();
+    assert_eq!(file.name, "ok.dal");
+    assert_eq!(file.content, "fn main() {}\n");
+}
+
+#[test]
+fn load_errors_are_structured() {
+    let err = load("bad.dal", b"let a\nlet \xff = 1\n").err().unwrap();
+    assert_eq!(err.kind, LoadErrorKind::NotUtf8 { offset: 10, line: 2 });
+    assert_eq!(err.to_diagnostic().code, Some("E0023"));
+
+    let err = SourceMap::new().load_file("does/not/exist.dal").err().unwrap();
+    assert_eq!(err.kind, LoadErrorKind::NotFound);
+    assert_eq!(err.to_string(), "couldn't find `does/not/exist.dal`");
+
+    let err = SourceMap::new().load_file(env!("CARGO_TARGET_TMPDIR")).err().unwrap();
+    assert_eq!(err.kind, LoadErrorKind::IsDirectory);
+}
+
+#[test]
+fn crlf_and_unicode_line_breaks_start_lines() {
+    let file = source_file("a\r\nb\rc\u{2028}d\n");
+    assert_eq!(file.line_col(3), LineCol { line: 2, col: 1 });
+    assert_eq!(file.line_col(5), LineCol { line: 3, col: 1 });
+    assert_eq!(file.line_col(9), LineCol { line: 4, col: 1 });
+    assert_eq!(file.line_text(1), Some("a"));
+    assert_eq!(file.line_text(3), Some("c"));
+}
+
+#[test]
+fn bom_is_not_a_column() {
+    let file = source_file("\u{feff}let x");
+    assert_eq!(file.line_col(3), LineCol { line: 1, col: 1 });
+    assert_eq!(file.line_col(7), LineCol { line: 1, col: 5 });
+}
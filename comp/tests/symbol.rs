@@ -0,0 +1,66 @@
+use dal::symbol::{
+    Symbol,
+    edit_distance::{edit_distance, find_best_match},
+    kw, sym,
+};
+
+#[test]
+fn predefined_symbols_are_interned_up_front() {
+    assert_eq!(Symbol::intern("fn"), kw::Fn);
+    assert_eq!(Symbol::intern("Self"), kw::SelfType);
+    assert_eq!(Symbol::intern("main"), sym::main);
+    assert_eq!(kw::Sizeof.as_str(), "sizeof");
+    assert_eq!(sym::underscore.to_string(), "_");
+}
+
+#[test]
+fn only_keywords_are_keywords() {
+    assert!(kw::Module.is_keyword() && kw::Sizeof.is_keyword());
+    assert!(!sym::main.is_keyword());
+    assert!(!Symbol::intern("not_a_keyword").is_keyword());
+}
+
+#[test]
+fn interning_is_idempotent() {
+    let a = Symbol::intern("some_identifier");
+    assert_eq!(Symbol::intern("some_identifier"), a);
+    assert_ne!(Symbol::intern("some_identifier2"), a);
+    assert_eq!(a.as_str(), "some_identifier");
+    assert_eq!(format!("{:?}", a), "\"some_identifier\"");
+}
+
+#[test]
+fn edit_distance_counts_characters() {
+    assert_eq!(edit_distance("kitten", "sitting"), 3);
+    assert_eq!(edit_distance("", "abc"), 3);
+    assert_eq!(edit_distance("naïve", "naive"), 1);
+    assert_eq!(edit_distance("same", "same"), 0);
+}
+
+#[test]
+fn close_names_are_suggested() {
+    let names = ["count", "counter", "Count", "total"].map(Symbol::intern);
+    assert_eq!(find_best_match(Symbol::intern("cont"), names), Some(Symbol::intern("count")));
+    // Case-insensitive matches win over every other edit.
+    assert_eq!(find_best_match(Symbol::intern("COUNTER"), names), Some(Symbol::intern("counter")));
+    assert_eq!(find_best_match(Symbol::intern("xyz"), names), None);
+    // A name is never a suggestion for itself.
+    assert_eq!(find_best_match(Symbol::intern("total"), names), None);
+}
+
+#[test]
+fn transpositions_are_one_edit() {
+    assert_eq!(edit_distance("valeu", "value"), 1);
+    assert_eq!(edit_distance("ab", "ba"), 1);
+    assert_eq!(edit_distance("abc", "ca"), 3);
+    assert_eq!(edit_distance("kitten", "sitting"), 3);
+    assert_eq!(edit_distance("", "abc"), 3);
+    assert_eq!(edit_distance("héllo", "hlélo"), 1);
+}
+
+#[test]
+fn transposed_names_are_suggested() {
+    let candidates = ["value", "values", "other"].map(Symbol::intern);
+    let best = find_best_match(Symbol::intern("valeu"), candidates);
+    assert_eq!(best, Some(Symbol::intern("value")));
+}
@@ -0,0 +1,642 @@
+use dal::{
+    attr::CfgSet,
+    loader::{Loader, default_std_dir},
+    resolve,
+    span::{SourceMap, Span},
+    typeck::{self, TypeckResults},
+};
+use std::{fs, path::PathBuf};
+
+/// Writes the files of a program to a new directory and returns the path of
+/// the first one, the root module.
+fn write_program(name: &str, files: &[(&str, &str)]) -> String {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("typeck").join(name);
+    let _ = fs::remove_dir_all(&dir);
+    for (path, source) in files {
+        let path = dir.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, source).unwrap();
+    }
+    dir.join(files[0].0).to_string_lossy().to_string()
+}
+
+/// Loads, resolves and type checks a program, returning its results and the
+/// codes of the errors found.
+fn check_program(name: &str, files: &[(&str, &str)]) -> (TypeckResults, Vec<&'static str>) {
+    let root = write_program(name, files);
+    let mut sm = SourceMap::new();
+    let (graph, diags) = Loader::new(&mut sm, default_std_dir(), CfgSet::host()).load(&root);
+    assert!(diags.is_empty(), "load errors: {:?}", diags);
+    let (res, errors) = resolve::resolve(&graph);
+    assert!(errors.is_empty(), "resolve errors: {:?}", errors);
+    let (results, errors) = typeck::check(&graph, &res);
+    let codes = errors.iter().map(|err| err.to_diagnostic().code.unwrap()).collect();
+    (results, codes)
+}
+
+/// Type checks a single module, returning the codes of the errors found.
+fn check(name: &str, source: &str) -> Vec<&'static str> {
+    check_program(name, &[("main.dal", source)]).1
+}
+
+/// Type checks a single module, returning the error messages as
+/// `code: message`.
+fn messages(name: &str, source: &str) -> Vec<String> {
+    let root = write_program(name, &[("main.dal", source)]);
+    let mut sm = SourceMap::new();
+    let (graph, _) = Loader::new(&mut sm, default_std_dir(), CfgSet::host()).load(&root);
+    let (res, _) = resolve::resolve(&graph);
+    let (_, errors) = typeck::check(&graph, &res);
+    errors
+        .iter()
+        .map(|err| format!("{}: {}", err.to_diagnostic().code.unwrap(), err.kind))
+        .collect()
+}
+
+#[test]
+fn well_typed_programs_have_no_errors() {
+    let source = "
+struct Point { x: i32, y: i32 }
+
+fn add(a: i32, b: i32) -> i32 {
+    return a + b
+}
+
+fn main() {
+    let p = Point { x: 1, y: 2 }
+    let sum: i32 = add(p.x, p.y)
+    let f: f64 = sum as f64 * 0.5
+    let ok = sum > 2 && f < 10.0
+    let q: *const Point = null
+    for i in 0..10 {
+        if ok { break }
+    }
+}
+";
+    assert_eq!(check("well_typed", source), Vec::<&str>::new());
+}
+
+#[test]
+fn mismatched_types_are_reported() {
+    assert_eq!(
+        messages("mismatch", "fn f() {\n    let x: u8 = true\n}\n"),
+        ["E0500: mismatched types"]
+    );
+    assert_eq!(check("return_mismatch", "fn f() -> bool {\n    return 1\n}\n"), ["E0500"]);
+    // `*mut T` coerces to `*const T`, not the other way around.
+    let source = "fn f(a: *mut i32, b: *const i32) {\n    let c: *const i32 = a\n    let d: *mut i32 = b\n}\n";
+    assert_eq!(check("pointer_coercion", source), ["E0500"]);
+}
+
+#[test]
+fn calls_are_checked_against_the_signature() {
+    let source = "
+fn add(a: i32, b: i32) -> i32 {
+    return a + b
+}
+
+fn main() {
+    add(1)
+    add(1, true)
+    let x: bool = add(1, 2)
+    let y = 1
+    y(2)
+}
+";
+    assert_eq!(check("calls", source), ["E0501", "E0500", "E0500", "E0502"]);
+}
+
+#[test]
+fn operators_require_matching_operands() {
+    let source = "
+fn f(a: i32, b: f64, c: bool) {
+    let x = a + b
+    let y = -c
+    let z = !b
+    let w = c < c
+}
+";
+    assert_eq!(check("operators", source), ["E0503", "E0504", "E0504", "E0503"]);
+}
+
+#[test]
+fn fields_must_exist_and_be_initialized() {
+    let source = "
+struct Point { x: i32, y: i32 }
+
+fn main() {
+    let p = Point { x: 1 }
+    let q = Point { x: 1, y: 2, z: 3 }
+    let z = q.z
+}
+";
+    assert_eq!(check("fields", source), ["E0516", "E0505", "E0505"]);
+}
+
+#[test]
+fn private_fields_of_other_modules_are_reported() {
+    let files = [
+        ("main.dal", "import \"geo\"\nfn f(p: geo::Point) -> i32 {\n    return p.x\n}\n"),
+        ("geo.dal", "module geo\npub struct Point { x: i32 }\n"),
+    ];
+    assert_eq!(check_program("private_field", &files).1, ["E0506"]);
+}
+
+#[test]
+fn casts_are_between_numbers_and_pointers() {
+    let source = "
+fn f(a: i32, p: *const u8, c: bool) {
+    let x = a as f32
+    let y = p as *const i32
+    let z = p as usize
+    let w = c as *const u8
+}
+";
+    assert_eq!(check("casts", source), ["E0507"]);
+}
+
+#[test]
+fn functions_must_return_a_value() {
+    assert_eq!(
+        messages("missing_return", "fn f(c: bool) -> i32 {\n    if c { return 1 }\n}\n")[0],
+        "E0508: function `f` can reach the end of its body without returning a value of type `i32`"
+    );
+    assert_eq!(check("diverging_loop", "fn f() -> i32 {\n    loop {}\n}\n"), Vec::<&str>::new());
+}
+
+#[test]
+fn loops_control_break_and_continue() {
+    let source = "
+fn f() {
+    break
+    while true { break 1 }
+    for i in 1 { }
+    let r = 0..1
+}
+";
+    assert_eq!(check("loops", source), ["E0512", "E0513", "E0514", "E0515"]);
+}
+
+#[test]
+fn only_places_can_be_assigned() {
+    assert_eq!(
+        check("assign", "fn f(a: i32) {\n    1 = a\n    let mut b = a\n    b = 2\n}\n"),
+        ["E0518"]
+    );
+}
+
+/// Type checks a single module and returns the types of the locals declared
+/// by `let <name>`, in the order of `names`.
+fn local_types(name: &str, source: &str, names: &[&str]) -> Vec<String> {
+    let root = write_program(name, &[("main.dal", source)]);
+    let mut sm = SourceMap::new();
+    let (graph, _) = Loader::new(&mut sm, default_std_dir(), CfgSet::host()).load(&root);
+    let (res, _) = resolve::resolve(&graph);
+    let (results, errors) = typeck::check(&graph, &res);
+    assert!(errors.is_empty(), "type errors: {:?}", errors);
+    let file = &graph.get(graph.root().unwrap()).file;
+    names
+        .iter()
+        .map(|name| {
+            let content = &file.content;
+            let offset = content.find(&format!("let {}", name)).map(|i| i + 4);
+            let offset =
+                offset.or_else(|| content.find(&format!("let mut {}", name)).map(|i| i + 8));
+            let lo = file.start + offset.unwrap();
+            results.bodies().local_ty(Span::new(lo, lo + name.len())).unwrap().to_string()
+        })
+        .collect()
+}
+
+#[test]
+fn literals_default_to_i32_and_f64() {
+    let source = "fn f() {\n    let a = 1\n    let b = 1.5\n    let c = 1u8\n    let d = -1\n}\n";
+    assert_eq!(local_types("defaults", source, &["a", "b", "c", "d"]), ["i32", "f64", "u8", "i32"]);
+}
+
+#[test]
+fn local_types_are_inferred_from_later_uses() {
+    let source = "
+fn g(x: u64, y: f32) {}
+
+fn f() {
+    let a = 1
+    let b = a
+    let c = 2.0
+    g(b, c)
+    let mut d
+    d = 2u16
+}
+";
+    assert_eq!(
+        local_types("later_uses", source, &["a", "b", "c", "d"]),
+        ["u64", "u64", "f32", "u16"]
+    );
+}
+
+#[test]
+fn locals_without_a_known_type_need_an_annotation() {
+    assert_eq!(
+        messages("annotation", "fn f() {\n    let p\n}\n"),
+        ["E0509: type annotations needed for `p`"]
+    );
+}
+
+#[test]
+fn literals_must_fit_in_their_type() {
+    let source = "
+fn f() {
+    let a: u8 = 256
+    let b: i8 = -128
+    let c: i8 = 128
+    let d = 255u8
+    let e: u32 = -1
+    let f: f32 = 1e39
+}
+";
+    assert_eq!(check("ranges", source), ["E0520", "E0520", "E0504", "E0520"]);
+}
+
+#[test]
+fn variadic_arguments_are_promoted() {
+    let source = "
+extern {
+    fn printf(fmt: *const u8, ...) -> i32
+}
+
+struct S { x: i32 }
+
+fn f(s: S, a: u8, b: f32, c: bool) {
+    printf(c\"\", a, b, c, 1, s)
+}
+";
+    assert_eq!(check("variadic", source), ["E0521"]);
+}
+
+/// Type checks a single module and returns the names of the instances of
+/// generic functions it uses.
+fn instances(name: &str, source: &str) -> Vec<String> {
+    let root = write_program(name, &[("main.dal", source)]);
+    let mut sm = SourceMap::new();
+    let (graph, _) = Loader::new(&mut sm, default_std_dir(), CfgSet::host()).load(&root);
+    let (res, _) = resolve::resolve(&graph);
+    let (results, errors) = typeck::check(&graph, &res);
+    assert!(errors.is_empty(), "type errors: {:?}", errors);
+    results.instances().iter().map(|mono| mono.instance.name(&res)).collect()
+}
+
+#[test]
+fn generic_functions_are_instantiated_once_per_type_arguments() {
+    let source = "
+fn sum[T](a: T, b: T) -> T {
+    return a + b
+}
+
+fn twice[T](x: T) -> T {
+    return sum(x, x)
+}
+
+fn main() {
+    let a = sum(1, 2)
+    let b = sum(1.0, 2.0)
+    let c = sum[u8](200, 55)
+    let d: i64 = twice(7)
+    let e = sum(3, 4)
+}
+";
+    assert_eq!(
+        instances("instances", source),
+        ["sum[i32]", "sum[f64]", "sum[u8]", "twice[i64]", "sum[i64]"]
+    );
+}
+
+#[test]
+fn errors_in_instances_are_reported_at_the_use() {
+    let source = "
+fn sum[T](a: T, b: T) -> T {
+    return a + b
+}
+
+fn main() {
+    let a = sum(1, 2)
+    let b = sum(true, false)
+}
+";
+    assert_eq!(check("instance_error", source), ["E0503"]);
+    let message = messages("instance_error_message", source);
+    assert_eq!(message, ["E0503: cannot apply `+` to `bool` and `bool`"]);
+}
+
+#[test]
+fn type_arguments_must_match_the_generic_parameters() {
+    let source = "
+fn id[T](x: T) -> T {
+    return x
+}
+
+fn f() {}
+
+fn main() {
+    let a = id[i32, u8](1)
+    f[i32]()
+}
+";
+    assert_eq!(
+        messages("type_args", source),
+        [
+            "E0522: function takes 1 type argument but 2 were supplied",
+            "E0522: type arguments given to something else than a generic function"
+        ]
+    );
+}
+
+#[test]
+fn type_arguments_must_be_inferable() {
+    let source = "
+fn make[T]() -> *const T {
+    return null
+}
+
+fn main() {
+    make()
+}
+";
+    assert_eq!(check("cannot_infer", source), ["E0523"]);
+}
+
+#[test]
+fn unbounded_polymorphic_recursion_is_reported() {
+    let source = "
+fn f[T](x: T) {
+    f[*const T](x as *const T)
+}
+
+fn main() {
+    f(1)
+}
+";
+    assert_eq!(check("recursion", source), ["E0524"]);
+}
+
+#[test]
+fn else_branch_is_checked() {
+    let source = "
+fn f(c: bool) {
+    if c {
+        let a = 1
+    } else {
+        let x: u8 = true
+    }
+}
+";
+    assert_eq!(check("else_mismatch", source), ["E0500"]);
+}
+
+#[test]
+fn else_branch_return_is_checked() {
+    let source = "
+fn f(c: bool) -> i32 {
+    if c {
+        return 1
+    } else {
+        return true
+    }
+}
+";
+    assert_eq!(check("else_return", source), ["E0500"]);
+}
+
+#[test]
+fn else_if_branch_is_checked() {
+    let source = "
+fn f(c: bool) {
+    if c {
+    } else if c {
+    } else {
+        let x: bool = 1
+    }
+}
+";
+    assert_eq!(check("else_if", source), ["E0500"]);
+}
+
+#[test]
+fn if_diverges_only_when_both_branches_do() {
+    let source = "
+fn f(c: bool) -> i32 {
+    if c {
+        return 1
+    } else {
+        return 2
+    }
+}
+
+fn g(c: bool) -> i32 {
+    if c {
+        return 1
+    } else {
+    }
+}
+";
+    assert_eq!(check("if_diverges", source), ["E0508"]);
+}
+
+#[test]
+fn generic_call_in_else_branch_is_instantiated() {
+    let source = "
+fn sum[T](a: T, b: T) -> T {
+    return a + b
+}
+
+fn f(c: bool) {
+    if c {
+    } else {
+        let x = sum(true, false)
+    }
+}
+";
+    assert_eq!(check("else_generic", source), ["E0503"]);
+}
+
+#[test]
+fn modules_with_the_same_name_mangle_apart() {
+    let files = [
+        (
+            "main.dal",
+            "import \"a/math\"\nimport \"b/util\"\n\nfn f() {\n    let x = math.id(1)\n}\n",
+        ),
+        ("a/math.dal", "pub fn id[T](x: T) -> T {\n    return x\n}\n"),
+        ("b/util.dal", "import \"math\"\n\npub fn g() {\n    let x = math.id(1)\n}\n"),
+        ("b/math.dal", "pub fn id[T](x: T) -> T {\n    return x\n}\n"),
+    ];
+    let (results, errors) = check_program("same_name", &files);
+    assert!(errors.is_empty(), "{:?}", errors);
+    let mut symbols: Vec<_> = results.instances().iter().map(|mono| mono.symbol.as_str()).collect();
+    symbols.sort();
+    assert_eq!(symbols, ["_DL1a4mathE2idI3i32E", "_DL1b4mathE2idI3i32E"]);
+}
+
+#[test]
+fn immutable_bindings_are_not_assignable() {
+    let source = "
+struct Point {
+    x: i32,
+}
+
+fn f(n: i32, p: *mut Point) {
+    let k = 1
+    k = 2
+    const m = 3
+    m += 4
+    n = 5
+    let q = Point { x: 1 }
+    q.x = 6;
+    (k) = 7
+}
+";
+    assert_eq!(check("immutable", source), ["E0525"; 5]);
+}
+
+#[test]
+fn mutable_bindings_are_assignable() {
+    let source = "
+struct Point {
+    x: i32,
+}
+
+fn f(p: *mut Point) {
+    let mut k = 1
+    k = 2
+    k += 3
+    let mut q = Point { x: 1 }
+    q.x = 4
+    p.x = 5
+    let x: i32
+    let mut y: i32
+    y = 6
+}
+";
+    assert_eq!(check("mutable", source), [] as [&str; 0]);
+}
+
+#[test]
+fn only_c_strings_are_pointers() {
+    let source = "
+fn f(s: *const u8) {
+    f(c\"a\")
+    f(\"a\")
+    f(b\"a\")
+    let s: *const u8 = \"a\"
+}
+";
+    assert_eq!(check("strings", source), ["E0500"; 3]);
+}
+
+#[test]
+fn readme_examples_check() {
+    let readme = fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/../README.md")).unwrap();
+    let examples = readme.split("```rust\n").skip(1).map(|rest| rest.split("```").next().unwrap());
+    for (i, example) in examples.enumerate() {
+        assert_eq!(check(&format!("readme_{}", i), example), [] as [&str; 0], "{}", example);
+    }
+}
+
+#[test]
+fn errors_shared_by_instances_are_reported_once() {
+    let source = "
+fn f[T](x: T) {
+    let y: bool = x
+    f[*const T](x as *const T)
+}
+
+fn main() {
+    f(1)
+}
+";
+    assert_eq!(check("recursion_errors", source), ["E0500", "E0524"]);
+    // `sum[bool]` and `sum[S]` have the same error, reported for the first.
+    let source = "
+struct S {}
+
+fn sum[T](a: T, b: T) -> T {
+    return a + b
+}
+
+fn main() {
+    sum(true, false)
+    sum(1, 2)
+    sum(S {}, S {})
+}
+";
+    assert_eq!(check("shared_errors", source), ["E0503"]);
+}
+
+#[test]
+fn only_free_functions_can_be_generic() {
+    let source = "
+struct Pair[T] { a: T, b: T }
+union Either[T, U] { a: T, b: U }
+enum Opt[T] { Some(T), None }
+type Ptr[T] = *const T
+
+trait Show[T] {
+    fn show[U](self)
+}
+
+impl[T] Pair {
+    fn first[U](self) {}
+}
+
+extern {
+    fn malloc[T](size: usize) -> *mut T
+}
+
+fn id[T](x: T) -> T {
+    return x
+}
+";
+    assert_eq!(
+        messages("unsupported_generics", source),
+        [
+            "E0526: generic parameters are not supported on structs",
+            "E0526: generic parameters are not supported on unions",
+            "E0526: generic parameters are not supported on enums",
+            "E0526: generic parameters are not supported on type aliases",
+            "E0526: generic parameters are not supported on traits",
+            "E0526: generic parameters are not supported on methods",
+            "E0526: generic parameters are not supported on impl blocks",
+            "E0526: generic parameters are not supported on methods",
+            "E0526: generic parameters are not supported on extern functions",
+        ]
+    );
+}
+
+#[test]
+fn functions_have_function_types() {
+    let source = "
+fn g(x: i32) -> i32 {
+    return x
+}
+
+fn k(x: u8) {}
+
+fn apply(f: fn(i32) -> i32, x: i32) -> i32 {
+    return f(x)
+}
+
+fn main() {
+    let h: fn(i32) -> i32 = g
+    let y: i32 = apply(h, 2)
+    let z = apply(k, 2)
+}
+";
+    assert_eq!(messages("fn_types", source), ["E0500: mismatched types"]);
+    let source = "fn k(x: u8) {}\nfn f() {\n    let a: fn(i32) -> i32 = k\n}\n";
+    let root = write_program("fn_type_mismatch", &[("main.dal", source)]);
+    let mut sm = SourceMap::new();
+    let (graph, _) = Loader::new(&mut sm, default_std_dir(), CfgSet::host()).load(&root);
+    let (res, _) = resolve::resolve(&graph);
+    let (_, errors) = typeck::check(&graph, &res);
+    let diag = errors[0].to_diagnostic();
+    assert_eq!(diag.labels[0].message, "expected `fn(i32) -> i32`, found `fn(u8)`");
+}